@@ -0,0 +1,128 @@
+//! Call auctions.
+//!
+//! While a book is in its call phase, orders rest without matching (the
+//! book may be crossed). After every change the book publishes the
+//! indicative uncross: the price the auction would clear at right now,
+//! with the volume that would trade and the imbalance left over.
+//!
+//! At the uncross every crossing order trades at that single clearing
+//! price, picked from the limit prices in the book by, in turn:
+//! 1. maximum executable volume,
+//! 2. minimum imbalance,
+//! 3. closeness to the reference price (the last trade price, if any),
+//! 4. the lower price.
+//!
+//! The book then goes back to continuous matching.
+
+use std::cmp::Reverse;
+
+use crate::side::Side;
+
+/// What an auction command does to a book.
+///
+/// The numeric values are part of the binary wire protocol.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AuctionAction {
+    /// Enter the call phase: stop matching, start collecting orders.
+    Start = 0,
+    /// Execute the auction at the clearing price and resume continuous
+    /// matching.
+    Uncross = 1,
+}
+
+impl AuctionAction {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(AuctionAction::Start),
+            1 => Some(AuctionAction::Uncross),
+            _ => None,
+        }
+    }
+
+    /// Text code used by the CSV protocol (`START`, `UNCROSS`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuctionAction::Start => "START",
+            AuctionAction::Uncross => "UNCROSS",
+        }
+    }
+
+    /// Parse the CSV text code (case-sensitive).
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        match s {
+            "START" => Some(AuctionAction::Start),
+            "UNCROSS" => Some(AuctionAction::Uncross),
+            _ => None,
+        }
+    }
+}
+
+/// Indicative (or final) result of an auction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Uncross {
+    /// Clearing price (`0` = the book doesn't cross).
+    pub price: u32,
+    /// Quantity that trades at `price`.
+    pub volume: u32,
+    /// Quantity left unmatched at `price` on `imbalance_side`.
+    pub imbalance_qty: u32,
+    /// Side with more quantity at `price` (`Buy` when balanced).
+    pub imbalance_side: Side,
+}
+
+impl Uncross {
+    /// The book doesn't cross: nothing would trade.
+    pub const NONE: Uncross = Uncross {
+        price: 0,
+        volume: 0,
+        imbalance_qty: 0,
+        imbalance_side: Side::Buy,
+    };
+}
+
+/// Find the clearing price for `bids` and `asks`, given as
+/// `(price, quantity)` pairs in any order ([`Uncross::NONE`] if nothing
+/// crosses). `reference_price == 0` skips the reference tie-breaker.
+pub(crate) fn find_uncross(bids: &[(u32, u32)], asks: &[(u32, u32)], reference_price: u32) -> Uncross {
+    let mut bids = bids.to_vec();
+    let mut asks = asks.to_vec();
+    bids.sort_unstable();
+    asks.sort_unstable();
+
+    // Quantity bid at or above / offered at or below each price.
+    let demand = |price: u32| -> u64 {
+        let start = bids.partition_point(|&(p, _)| p < price);
+        bids[start..].iter().map(|&(_, q)| q as u64).sum()
+    };
+    let supply = |price: u32| -> u64 {
+        let end = asks.partition_point(|&(p, _)| p <= price);
+        asks[..end].iter().map(|&(_, q)| q as u64).sum()
+    };
+
+    let mut best: Option<(UncrossRank, Uncross)> = None;
+    for &(price, _) in bids.iter().chain(asks.iter()) {
+        let (bought, sold) = (demand(price), supply(price));
+        let volume = bought.min(sold);
+        if volume == 0 {
+            continue;
+        }
+        let imbalance = bought.abs_diff(sold);
+        let distance = if reference_price == 0 { 0 } else { price.abs_diff(reference_price) };
+        let rank = (volume, Reverse(imbalance), Reverse(distance), Reverse(price));
+        if best.as_ref().is_none_or(|(best_rank, _)| rank > *best_rank) {
+            let uncross = Uncross {
+                price,
+                volume: u32::try_from(volume).unwrap_or(u32::MAX),
+                imbalance_qty: u32::try_from(imbalance).unwrap_or(u32::MAX),
+                imbalance_side: if sold > bought { Side::Sell } else { Side::Buy },
+            };
+            best = Some((rank, uncross));
+        }
+    }
+    best.map_or(Uncross::NONE, |(_, uncross)| uncross)
+}
+
+/// Candidate ordering: higher is better (volume, then lower imbalance,
+/// then closer to the reference price, then lower price).
+type UncrossRank = (u64, Reverse<u64>, Reverse<u32>, Reverse<u32>);
@@ -0,0 +1,83 @@
+//! Market-by-order (L3) events.
+//!
+//! Every change to a resting order is reported with its public,
+//! engine-assigned `order_id` (never the user ids), in the style of an
+//! ITCH feed. Applying the events of one book in order, starting from
+//! an empty book, reproduces every resting order with its price, size
+//! and queue position.
+//!
+//! `order_id`s are assigned by each [`OrderBook`](crate::order_book::OrderBook)
+//! when an order starts resting, increase monotonically and are never
+//! reused within a book; `(symbol, order_id)` is unique.
+//!
+//! Events are collected only while enabled (see
+//! [`MatchingEngine::set_book_events`](crate::matching_engine::MatchingEngine::set_book_events)).
+
+use crate::side::Side;
+
+/// One order-level change in a book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderBookEvent {
+    /// An order started resting at the back of its price level.
+    Add {
+        symbol: String,
+        order_id: u64,
+        side: Side,
+        price: u32,
+        quantity: u32,
+    },
+
+    /// A resting order traded `executed_qty` at `price`.
+    ///
+    /// `remaining_qty == 0` means it was fully executed and has left the
+    /// book; otherwise it was partially executed and keeps its position.
+    ///
+    /// For an iceberg, quantities are those of the displayed slice. When
+    /// a slice is used up, `remaining_qty == 0` is followed by an `Add` of
+    /// the next slice under a new order id at the back of the level.
+    Execute {
+        symbol: String,
+        order_id: u64,
+        price: u32,
+        executed_qty: u32,
+        remaining_qty: u32,
+    },
+
+    /// `cancelled_qty` was taken off a resting order without trading.
+    ///
+    /// `remaining_qty == 0` means the order was deleted; otherwise it was
+    /// reduced in place and keeps its position.
+    Cancel {
+        symbol: String,
+        order_id: u64,
+        cancelled_qty: u32,
+        remaining_qty: u32,
+    },
+
+    /// A resting order was replaced without trading: `old_order_id` is
+    /// deleted and `new_order_id` (same side) joins the back of the level
+    /// at `price` with `quantity`.
+    ///
+    /// A replace that trades on re-entry is reported as `Cancel` of the
+    /// old id, `Execute`s against the resting orders it hit, then `Add`
+    /// of any remainder.
+    Replace {
+        symbol: String,
+        old_order_id: u64,
+        new_order_id: u64,
+        price: u32,
+        quantity: u32,
+    },
+}
+
+impl OrderBookEvent {
+    /// Symbol of the book this event belongs to.
+    pub fn symbol(&self) -> &str {
+        match self {
+            OrderBookEvent::Add { symbol, .. }
+            | OrderBookEvent::Execute { symbol, .. }
+            | OrderBookEvent::Cancel { symbol, .. }
+            | OrderBookEvent::Replace { symbol, .. } => symbol,
+        }
+    }
+}
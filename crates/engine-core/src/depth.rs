@@ -0,0 +1,79 @@
+//! Helper types for market-by-price (L2) depth.
+//!
+//! [`BookDepth`] is a point-in-time view of the top `n` price levels
+//! per side (see [`OrderBook::depth`](crate::order_book::OrderBook::depth)).
+//! Incremental changes travel as [`DepthEvent`]s inside an
+//! [`OutputMessage::DepthUpdate`](crate::messages::DepthUpdate).
+
+use crate::side::Side;
+
+/// Aggregated quantity resting at one price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthLevel {
+    pub price: u32,
+    /// Total remaining quantity at this price.
+    pub quantity: u32,
+}
+
+/// Top `n` levels of both sides of a book, best price first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BookDepth {
+    /// Highest price first.
+    pub bids: Vec<DepthLevel>,
+    /// Lowest price first.
+    pub asks: Vec<DepthLevel>,
+}
+
+/// What happened to a price level.
+///
+/// The numeric values are part of the binary wire protocol.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DepthAction {
+    /// A new price level appeared.
+    Add = 0,
+    /// An existing level's quantity changed.
+    Change = 1,
+    /// The level is gone (quantity is `0`).
+    Delete = 2,
+}
+
+impl DepthAction {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(DepthAction::Add),
+            1 => Some(DepthAction::Change),
+            2 => Some(DepthAction::Delete),
+            _ => None,
+        }
+    }
+
+    /// Single-letter code used by the CSV protocol (`A`, `C`, `D`).
+    pub fn as_char(self) -> char {
+        match self {
+            DepthAction::Add => 'A',
+            DepthAction::Change => 'C',
+            DepthAction::Delete => 'D',
+        }
+    }
+
+    /// Parse the CSV letter code (case-sensitive).
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'A' => Some(DepthAction::Add),
+            'C' => Some(DepthAction::Change),
+            'D' => Some(DepthAction::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// One level change within a depth update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthEvent {
+    pub action: DepthAction,
+    pub side: Side,
+    pub price: u32,
+    /// New total quantity at `price` (`0` for `Delete`).
+    pub quantity: u32,
+}
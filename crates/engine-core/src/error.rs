@@ -1,17 +1,205 @@
 //! Error types for the core matching engine.
 //!
-//! Right now the core engine API is designed to be infallible for
-//! normal operations (invalid input should generally be filtered out
-//! at the parsing / protocol layer).
+//! Business-level failures (unknown order, duplicate id, ...) are not
+//! Rust errors: the engine reports them to the client as an
+//! [`OutputMessage::Reject`](crate::messages::OutputMessage::Reject)
+//! carrying a [`RejectReason`] code, so callers can always tell a
+//! failure from a success.
 //!
-//! This module is a placeholder for future extensions where you might
-//! want to return rich errors from certain admin operations.
+//! [`EngineError`] is kept for admin operations that can fail for
+//! well-defined reasons.
 
-/// Placeholder error type for the engine.
+use std::fmt;
+
+/// Why a request was rejected.
 ///
-/// Currently unused, but kept for future-proofing in case we add
-/// admin APIs (e.g. “drop symbol”, “replay snapshot”, etc.) that
-/// can fail for well-defined reasons.
+/// The numeric values are part of the binary wire protocol.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RejectReason {
+    /// Cancel / replace for an order that is not resting.
+    UnknownOrder = 1,
+
+    /// New order reusing a `(user_id, user_order_id)` that is still live.
+    DuplicateOrderId = 2,
+
+    /// New order with `quantity == 0`.
+    ZeroQuantity = 3,
+
+    /// Empty or malformed symbol.
+    InvalidSymbol = 4,
+
+    /// Market order with nothing on the opposite side.
+    NoLiquidity = 5,
+
+    /// The request could not be decoded at the protocol layer.
+    ParseError = 6,
+
+    /// Replace that changes nothing, makes the order a market order, or
+    /// reduces the total quantity to (or below) what already filled.
+    InvalidReplace = 7,
+
+    /// Post-only order (or replace of one) that would trade on arrival,
+    /// or can't be repriced to a valid price.
+    PostOnlyWouldTrade = 8,
+
+    /// Flags that don't fit the order: post-only on a market or stop
+    /// order, both post-only flags, or a hidden iceberg.
+    InvalidFlags = 9,
+
+    /// Market, IOC or FOK order sent while the book is in a call auction
+    /// (only orders that can rest are accepted).
+    InvalidInAuction = 10,
+
+    /// New order or replace for a symbol that is halted.
+    TradingHalted = 11,
+
+    /// New order or replace for a symbol that is closed.
+    MarketClosed = 12,
+
+    /// Symbol not in the instrument registry.
+    UnknownInstrument = 13,
+
+    /// Price or stop price not a multiple of the instrument's tick size.
+    InvalidTickSize = 14,
+
+    /// Quantity or display quantity not a multiple of the instrument's
+    /// lot size.
+    InvalidLotSize = 15,
+
+    /// Quantity outside the instrument's min / max order quantity.
+    QuantityOutOfRange = 16,
+
+    /// Price outside the instrument's static price collar.
+    PriceOutOfRange = 17,
+
+    /// Pre-trade risk: quantity above the user's max order quantity.
+    RiskOrderQuantity = 18,
+
+    /// Pre-trade risk: notional above the user's max order notional.
+    RiskNotional = 19,
+
+    /// Pre-trade risk: the user already has the max number of open orders.
+    RiskOpenOrders = 20,
+
+    /// Pre-trade risk: a full fill would take the user's net position in
+    /// the symbol beyond the max.
+    RiskPosition = 21,
+
+    /// Pre-trade risk: the order would take the user's gross exposure
+    /// beyond the max.
+    RiskGrossExposure = 22,
+
+    /// New order or replace from a user blocked by a kill switch.
+    TradingDisabled = 23,
+
+    /// Admin command with a missing or wrong admin token.
+    Unauthorized = 24,
+}
+
+impl RejectReason {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            1 => Some(RejectReason::UnknownOrder),
+            2 => Some(RejectReason::DuplicateOrderId),
+            3 => Some(RejectReason::ZeroQuantity),
+            4 => Some(RejectReason::InvalidSymbol),
+            5 => Some(RejectReason::NoLiquidity),
+            6 => Some(RejectReason::ParseError),
+            7 => Some(RejectReason::InvalidReplace),
+            8 => Some(RejectReason::PostOnlyWouldTrade),
+            9 => Some(RejectReason::InvalidFlags),
+            10 => Some(RejectReason::InvalidInAuction),
+            11 => Some(RejectReason::TradingHalted),
+            12 => Some(RejectReason::MarketClosed),
+            13 => Some(RejectReason::UnknownInstrument),
+            14 => Some(RejectReason::InvalidTickSize),
+            15 => Some(RejectReason::InvalidLotSize),
+            16 => Some(RejectReason::QuantityOutOfRange),
+            17 => Some(RejectReason::PriceOutOfRange),
+            18 => Some(RejectReason::RiskOrderQuantity),
+            19 => Some(RejectReason::RiskNotional),
+            20 => Some(RejectReason::RiskOpenOrders),
+            21 => Some(RejectReason::RiskPosition),
+            22 => Some(RejectReason::RiskGrossExposure),
+            23 => Some(RejectReason::TradingDisabled),
+            24 => Some(RejectReason::Unauthorized),
+            _ => None,
+        }
+    }
+
+    /// Text code used by the CSV protocol.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RejectReason::UnknownOrder => "UNKNOWN_ORDER",
+            RejectReason::DuplicateOrderId => "DUPLICATE_ORDER_ID",
+            RejectReason::ZeroQuantity => "ZERO_QUANTITY",
+            RejectReason::InvalidSymbol => "INVALID_SYMBOL",
+            RejectReason::NoLiquidity => "NO_LIQUIDITY",
+            RejectReason::ParseError => "PARSE_ERROR",
+            RejectReason::InvalidReplace => "INVALID_REPLACE",
+            RejectReason::PostOnlyWouldTrade => "POST_ONLY_WOULD_TRADE",
+            RejectReason::InvalidFlags => "INVALID_FLAGS",
+            RejectReason::InvalidInAuction => "INVALID_IN_AUCTION",
+            RejectReason::TradingHalted => "TRADING_HALTED",
+            RejectReason::MarketClosed => "MARKET_CLOSED",
+            RejectReason::UnknownInstrument => "UNKNOWN_INSTRUMENT",
+            RejectReason::InvalidTickSize => "INVALID_TICK_SIZE",
+            RejectReason::InvalidLotSize => "INVALID_LOT_SIZE",
+            RejectReason::QuantityOutOfRange => "QUANTITY_OUT_OF_RANGE",
+            RejectReason::PriceOutOfRange => "PRICE_OUT_OF_RANGE",
+            RejectReason::RiskOrderQuantity => "RISK_ORDER_QUANTITY",
+            RejectReason::RiskNotional => "RISK_NOTIONAL",
+            RejectReason::RiskOpenOrders => "RISK_OPEN_ORDERS",
+            RejectReason::RiskPosition => "RISK_POSITION",
+            RejectReason::RiskGrossExposure => "RISK_GROSS_EXPOSURE",
+            RejectReason::TradingDisabled => "TRADING_DISABLED",
+            RejectReason::Unauthorized => "UNAUTHORIZED",
+        }
+    }
+
+    /// Parse the CSV text code.
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        match s {
+            "UNKNOWN_ORDER" => Some(RejectReason::UnknownOrder),
+            "DUPLICATE_ORDER_ID" => Some(RejectReason::DuplicateOrderId),
+            "ZERO_QUANTITY" => Some(RejectReason::ZeroQuantity),
+            "INVALID_SYMBOL" => Some(RejectReason::InvalidSymbol),
+            "NO_LIQUIDITY" => Some(RejectReason::NoLiquidity),
+            "PARSE_ERROR" => Some(RejectReason::ParseError),
+            "INVALID_REPLACE" => Some(RejectReason::InvalidReplace),
+            "POST_ONLY_WOULD_TRADE" => Some(RejectReason::PostOnlyWouldTrade),
+            "INVALID_FLAGS" => Some(RejectReason::InvalidFlags),
+            "INVALID_IN_AUCTION" => Some(RejectReason::InvalidInAuction),
+            "TRADING_HALTED" => Some(RejectReason::TradingHalted),
+            "MARKET_CLOSED" => Some(RejectReason::MarketClosed),
+            "UNKNOWN_INSTRUMENT" => Some(RejectReason::UnknownInstrument),
+            "INVALID_TICK_SIZE" => Some(RejectReason::InvalidTickSize),
+            "INVALID_LOT_SIZE" => Some(RejectReason::InvalidLotSize),
+            "QUANTITY_OUT_OF_RANGE" => Some(RejectReason::QuantityOutOfRange),
+            "PRICE_OUT_OF_RANGE" => Some(RejectReason::PriceOutOfRange),
+            "RISK_ORDER_QUANTITY" => Some(RejectReason::RiskOrderQuantity),
+            "RISK_NOTIONAL" => Some(RejectReason::RiskNotional),
+            "RISK_OPEN_ORDERS" => Some(RejectReason::RiskOpenOrders),
+            "RISK_POSITION" => Some(RejectReason::RiskPosition),
+            "RISK_GROSS_EXPOSURE" => Some(RejectReason::RiskGrossExposure),
+            "TRADING_DISABLED" => Some(RejectReason::TradingDisabled),
+            "UNAUTHORIZED" => Some(RejectReason::Unauthorized),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error type for engine admin operations.
+///
+/// Kept for admin APIs (e.g. “drop symbol”, “replay snapshot”, etc.)
+/// that can fail for well-defined reasons.
 #[derive(Debug)]
 pub enum EngineError {
     /// The requested symbol does not exist.
@@ -21,3 +209,13 @@ pub enum EngineError {
     Internal(String),
 }
 
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::UnknownSymbol(s) => write!(f, "Unknown symbol: {}", s),
+            EngineError::Internal(msg) => write!(f, "Internal error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
@@ -0,0 +1,331 @@
+//! Execution reports, modelled on FIX `ExecutionReport` (35=8).
+//!
+//! With execution reports on (see
+//! [`MatchingEngine::set_execution_reports`](crate::MatchingEngine::set_execution_reports)),
+//! every output that changes an order (`Ack`, `Trade`, `CancelAck`,
+//! `ReplaceAck`, `Expired`, `Triggered`, `SelfTradeCancel`, and `Reject`
+//! of an order that isn't live) is followed by an
+//! `OutputMessage::ExecutionReport` for each order it touches. The report
+//! carries what happened ([`ExecType`]), the order's resulting status
+//! ([`OrdStatus`]), the last fill and the order's cumulative and leaves
+//! quantity, so a client doesn't have to rebuild fill state itself.
+//!
+//! Exec ids count up from `1` across the engine; they and each live
+//! order's progress are part of snapshots, so a replay reports the same
+//! ids.
+
+use std::collections::HashMap;
+
+use crate::messages::{ExecutionReport, OutputMessage};
+
+/// What an execution report reports (FIX `ExecType`, tag 150).
+///
+/// The numeric values are part of the binary wire protocol.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExecType {
+    /// Order accepted (`0`).
+    New = 0,
+    /// Fill (`F`).
+    Trade = 1,
+    /// Order cancelled, by request or by self-trade prevention (`4`).
+    Canceled = 2,
+    /// Order replaced (`5`).
+    Replaced = 3,
+    /// Order rejected (`8`).
+    Rejected = 4,
+    /// Unfilled remainder expired (`C`).
+    Expired = 5,
+    /// Stop order triggered (`L`).
+    Triggered = 6,
+    /// Quantity reduced by self-trade prevention (`D`).
+    Restated = 7,
+}
+
+impl ExecType {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(ExecType::New),
+            1 => Some(ExecType::Trade),
+            2 => Some(ExecType::Canceled),
+            3 => Some(ExecType::Replaced),
+            4 => Some(ExecType::Rejected),
+            5 => Some(ExecType::Expired),
+            6 => Some(ExecType::Triggered),
+            7 => Some(ExecType::Restated),
+            _ => None,
+        }
+    }
+
+    /// Text code used by the CSV protocol.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExecType::New => "NEW",
+            ExecType::Trade => "TRADE",
+            ExecType::Canceled => "CANCELED",
+            ExecType::Replaced => "REPLACED",
+            ExecType::Rejected => "REJECTED",
+            ExecType::Expired => "EXPIRED",
+            ExecType::Triggered => "TRIGGERED",
+            ExecType::Restated => "RESTATED",
+        }
+    }
+
+    /// Parse the text code (case-sensitive).
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        match s {
+            "NEW" => Some(ExecType::New),
+            "TRADE" => Some(ExecType::Trade),
+            "CANCELED" => Some(ExecType::Canceled),
+            "REPLACED" => Some(ExecType::Replaced),
+            "REJECTED" => Some(ExecType::Rejected),
+            "EXPIRED" => Some(ExecType::Expired),
+            "TRIGGERED" => Some(ExecType::Triggered),
+            "RESTATED" => Some(ExecType::Restated),
+            _ => None,
+        }
+    }
+}
+
+/// An order's status after an execution report (FIX `OrdStatus`, tag
+/// 39).
+///
+/// The numeric values are part of the binary wire protocol.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OrdStatus {
+    /// Live, nothing filled (`0`).
+    New = 0,
+    /// Live, partly filled (`1`).
+    PartiallyFilled = 1,
+    /// Completely filled (`2`).
+    Filled = 2,
+    /// Cancelled (`4`).
+    Canceled = 3,
+    /// Rejected (`8`).
+    Rejected = 4,
+    /// Remainder expired (`C`).
+    Expired = 5,
+}
+
+impl OrdStatus {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(OrdStatus::New),
+            1 => Some(OrdStatus::PartiallyFilled),
+            2 => Some(OrdStatus::Filled),
+            3 => Some(OrdStatus::Canceled),
+            4 => Some(OrdStatus::Rejected),
+            5 => Some(OrdStatus::Expired),
+            _ => None,
+        }
+    }
+
+    /// Text code used by the CSV protocol.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrdStatus::New => "NEW",
+            OrdStatus::PartiallyFilled => "PARTIALLY_FILLED",
+            OrdStatus::Filled => "FILLED",
+            OrdStatus::Canceled => "CANCELED",
+            OrdStatus::Rejected => "REJECTED",
+            OrdStatus::Expired => "EXPIRED",
+        }
+    }
+
+    /// Parse the text code (case-sensitive).
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        match s {
+            "NEW" => Some(OrdStatus::New),
+            "PARTIALLY_FILLED" => Some(OrdStatus::PartiallyFilled),
+            "FILLED" => Some(OrdStatus::Filled),
+            "CANCELED" => Some(OrdStatus::Canceled),
+            "REJECTED" => Some(OrdStatus::Rejected),
+            "EXPIRED" => Some(OrdStatus::Expired),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` once the order is done (nothing left to trade).
+    pub fn is_final(self) -> bool {
+        !matches!(self, OrdStatus::New | OrdStatus::PartiallyFilled)
+    }
+}
+
+/// Filled and open quantity of a live order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub(crate) struct OrderProgress {
+    pub(crate) cum_qty: u32,
+    pub(crate) leaves_qty: u32,
+}
+
+impl OrderProgress {
+    /// Status of the order while it is live.
+    fn live_status(self) -> OrdStatus {
+        if self.cum_qty > 0 {
+            OrdStatus::PartiallyFilled
+        } else {
+            OrdStatus::New
+        }
+    }
+}
+
+/// Builds execution reports from engine outputs, keeping each live
+/// order's progress.
+#[derive(Debug, Clone)]
+pub(crate) struct ExecutionTracker {
+    orders: HashMap<(u32, u32), OrderProgress>,
+    next_exec_id: u64,
+}
+
+impl Default for ExecutionTracker {
+    fn default() -> Self {
+        ExecutionTracker {
+            orders: HashMap::new(),
+            next_exec_id: 1,
+        }
+    }
+}
+
+impl ExecutionTracker {
+    /// Rebuild from snapshot contents.
+    pub(crate) fn from_state(orders: Vec<((u32, u32), OrderProgress)>, next_exec_id: u64) -> Self {
+        ExecutionTracker {
+            orders: orders.into_iter().collect(),
+            next_exec_id,
+        }
+    }
+
+    /// Live orders' progress, sorted, for snapshots.
+    pub(crate) fn order_progress(&self) -> Vec<((u32, u32), OrderProgress)> {
+        let mut orders: Vec<_> = self.orders.iter().map(|(&key, &p)| (key, p)).collect();
+        orders.sort_by_key(|&(key, _)| key);
+        orders
+    }
+
+    /// Id of the next report.
+    pub(crate) fn next_exec_id(&self) -> u64 {
+        self.next_exec_id
+    }
+
+    /// The execution reports for one engine output.
+    ///
+    /// `new_order` is the `(key, quantity)` of the request if it was a new
+    /// order (its `Ack` opens the order); `is_live` tells whether an order
+    /// is still live once the request is done, so a `Reject` of a request
+    /// against a live order (duplicate id, bad replace) isn't reported.
+    pub(crate) fn reports(
+        &mut self,
+        out: &OutputMessage,
+        new_order: Option<((u32, u32), u32)>,
+        is_live: impl Fn((u32, u32)) -> bool,
+        now: u64,
+    ) -> Vec<ExecutionReport> {
+        // (order, symbol, what happened, last fill, progress after it)
+        let mut events = Vec::new();
+        match out {
+            OutputMessage::Ack(a) => {
+                let key = (a.user_id, a.user_order_id);
+                let quantity = match new_order {
+                    Some((new_key, quantity)) if new_key == key => quantity,
+                    _ => self.progress(key).leaves_qty,
+                };
+                let progress = OrderProgress { cum_qty: 0, leaves_qty: quantity };
+                events.push((key, &a.symbol, (ExecType::New, OrdStatus::New), (0, 0), progress));
+            }
+            OutputMessage::Trade(t) => {
+                for key in [(t.user_id_buy, t.user_order_id_buy), (t.user_id_sell, t.user_order_id_sell)] {
+                    let mut progress = self.progress(key);
+                    progress.cum_qty = progress.cum_qty.saturating_add(t.quantity);
+                    progress.leaves_qty = progress.leaves_qty.saturating_sub(t.quantity);
+                    let status = if progress.leaves_qty == 0 {
+                        OrdStatus::Filled
+                    } else {
+                        progress.live_status()
+                    };
+                    events.push((key, &t.symbol, (ExecType::Trade, status), (t.quantity, t.price), progress));
+                }
+            }
+            OutputMessage::CancelAck(c) => {
+                let key = (c.user_id, c.user_order_id);
+                let progress = OrderProgress { leaves_qty: 0, ..self.progress(key) };
+                events.push((key, &c.symbol, (ExecType::Canceled, OrdStatus::Canceled), (0, 0), progress));
+            }
+            OutputMessage::ReplaceAck(r) => {
+                let key = (r.user_id, r.user_order_id);
+                let progress = OrderProgress { leaves_qty: r.remaining_qty, ..self.progress(key) };
+                events.push((key, &r.symbol, (ExecType::Replaced, progress.live_status()), (0, 0), progress));
+            }
+            OutputMessage::Expired(e) => {
+                let key = (e.user_id, e.user_order_id);
+                let progress = OrderProgress { leaves_qty: 0, ..self.progress(key) };
+                events.push((key, &e.symbol, (ExecType::Expired, OrdStatus::Expired), (0, 0), progress));
+            }
+            OutputMessage::Triggered(t) => {
+                let key = (t.user_id, t.user_order_id);
+                let progress = self.progress(key);
+                events.push((key, &t.symbol, (ExecType::Triggered, progress.live_status()), (0, 0), progress));
+            }
+            OutputMessage::SelfTradeCancel(c) => {
+                let key = (c.user_id, c.user_order_id);
+                let progress = OrderProgress { leaves_qty: c.remaining_qty, ..self.progress(key) };
+                let exec = if c.remaining_qty == 0 {
+                    (ExecType::Canceled, OrdStatus::Canceled)
+                } else {
+                    (ExecType::Restated, progress.live_status())
+                };
+                events.push((key, &c.symbol, exec, (0, 0), progress));
+            }
+            OutputMessage::Reject(r) => {
+                let key = (r.user_id, r.user_order_id);
+                if !is_live(key) {
+                    let progress = OrderProgress { leaves_qty: 0, ..self.progress(key) };
+                    events.push((key, &r.symbol, (ExecType::Rejected, OrdStatus::Rejected), (0, 0), progress));
+                }
+            }
+            _ => {}
+        }
+
+        events
+            .into_iter()
+            .map(|(key, symbol, exec, last, progress)| self.update(key, symbol, exec, last, progress, now))
+            .collect()
+    }
+
+    fn progress(&self, key: (u32, u32)) -> OrderProgress {
+        self.orders.get(&key).copied().unwrap_or_default()
+    }
+
+    /// Record `progress` (dropping the order once it is done) and report.
+    fn update(
+        &mut self,
+        key: (u32, u32),
+        symbol: &str,
+        (exec_type, order_status): (ExecType, OrdStatus),
+        (last_qty, last_price): (u32, u32),
+        progress: OrderProgress,
+        now: u64,
+    ) -> ExecutionReport {
+        if order_status.is_final() {
+            self.orders.remove(&key);
+        } else {
+            self.orders.insert(key, progress);
+        }
+
+        let exec_id = self.next_exec_id;
+        self.next_exec_id += 1;
+        ExecutionReport {
+            user_id: key.0,
+            user_order_id: key.1,
+            symbol: symbol.to_string(),
+            exec_id,
+            exec_type,
+            order_status,
+            last_qty,
+            last_price,
+            cum_qty: progress.cum_qty,
+            leaves_qty: progress.leaves_qty,
+            timestamp_ns: now,
+        }
+    }
+}
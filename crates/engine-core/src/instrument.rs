@@ -0,0 +1,160 @@
+//! Instrument reference data.
+//!
+//! An [`InstrumentRegistry`] lists the symbols that may trade and the
+//! rules their orders must follow:
+//! - prices (limit and stop) are multiples of the tick size and inside
+//!   the static price collar,
+//! - quantities (total and iceberg display) are multiples of the lot
+//!   size, and the total is within the min / max order quantity,
+//! - market orders only sweep so many ticks through the touch (market
+//!   order protection; the rest is expired).
+//!
+//! The engine checks new orders and replaces against their instrument
+//! before they reach the book. An empty registry lists nothing and
+//! allows every symbol without any rule (the behaviour before reference
+//! data existed).
+
+use std::collections::HashMap;
+
+use crate::error::RejectReason;
+use crate::messages::NewOrder;
+
+/// Definition of a single instrument.
+///
+/// For the limits, `0` means "no limit".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instrument {
+    pub symbol: String,
+
+    /// Price increment in ticks (at least `1`).
+    pub tick_size: u32,
+
+    /// Quantity increment (at least `1`).
+    pub lot_size: u32,
+
+    /// Minimum total order quantity.
+    pub min_qty: u32,
+
+    /// Maximum total order quantity.
+    pub max_qty: u32,
+
+    /// Lowest allowed price (static collar).
+    pub min_price: u32,
+
+    /// Highest allowed price (static collar).
+    pub max_price: u32,
+
+    /// How many ticks through the touch a market order may trade.
+    pub market_protection_ticks: u32,
+}
+
+impl Instrument {
+    /// An instrument without rules: tick and lot size `1`, no limits.
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Instrument {
+            symbol: symbol.into(),
+            tick_size: 1,
+            lot_size: 1,
+            min_qty: 0,
+            max_qty: 0,
+            min_price: 0,
+            max_price: 0,
+            market_protection_ticks: 0,
+        }
+    }
+
+    /// Why `price` (non-zero) isn't allowed, if it isn't.
+    pub fn check_price(&self, price: u32) -> Option<RejectReason> {
+        if !price.is_multiple_of(self.tick_size.max(1)) {
+            return Some(RejectReason::InvalidTickSize);
+        }
+        if price < self.min_price || (self.max_price > 0 && price > self.max_price) {
+            return Some(RejectReason::PriceOutOfRange);
+        }
+        None
+    }
+
+    /// Why `quantity` (non-zero total order quantity) isn't allowed, if
+    /// it isn't.
+    pub fn check_quantity(&self, quantity: u32) -> Option<RejectReason> {
+        if !quantity.is_multiple_of(self.lot_size.max(1)) {
+            return Some(RejectReason::InvalidLotSize);
+        }
+        if quantity < self.min_qty || (self.max_qty > 0 && quantity > self.max_qty) {
+            return Some(RejectReason::QuantityOutOfRange);
+        }
+        None
+    }
+
+    /// Why `msg` breaks this instrument's rules, if it does. Zero
+    /// quantities and prices are left to the book (zero-quantity reject,
+    /// market order, not a stop, fully displayed).
+    pub fn check_new_order(&self, msg: &NewOrder) -> Option<RejectReason> {
+        if msg.quantity == 0 {
+            return None;
+        }
+        if let Some(reason) = self.check_quantity(msg.quantity) {
+            return Some(reason);
+        }
+        if !msg.display_qty.is_multiple_of(self.lot_size.max(1)) {
+            return Some(RejectReason::InvalidLotSize);
+        }
+        [msg.price, msg.stop_price]
+            .into_iter()
+            .filter(|&price| price > 0)
+            .find_map(|price| self.check_price(price))
+    }
+}
+
+/// The set of listed instruments, by symbol.
+#[derive(Debug, Clone, Default)]
+pub struct InstrumentRegistry {
+    instruments: HashMap<String, Instrument>,
+}
+
+impl InstrumentRegistry {
+    /// An empty registry (every symbol allowed, no rules).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace an instrument.
+    pub fn insert(&mut self, instrument: Instrument) {
+        self.instruments.insert(instrument.symbol.clone(), instrument);
+    }
+
+    /// The listed definition of `symbol`.
+    pub fn get(&self, symbol: &str) -> Option<&Instrument> {
+        self.instruments.get(symbol)
+    }
+
+    /// Returns `true` if no instrument is listed.
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    /// Number of listed instruments.
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    /// Tick size orders for `symbol` are priced in (`1` if unlisted).
+    pub fn tick_size(&self, symbol: &str) -> u32 {
+        self.get(symbol).map_or(1, |i| i.tick_size.max(1))
+    }
+
+    /// Market order protection of `symbol` in ticks (`0` if unlisted).
+    pub fn market_protection_ticks(&self, symbol: &str) -> u32 {
+        self.get(symbol).map_or(0, |i| i.market_protection_ticks)
+    }
+
+    /// The rules that apply to `symbol`: its definition, an unrestricted
+    /// one if the registry is empty, or `None` if `symbol` isn't listed.
+    pub fn resolve(&self, symbol: &str) -> Option<Instrument> {
+        match self.get(symbol) {
+            Some(instrument) => Some(instrument.clone()),
+            None if self.is_empty() => Some(Instrument::new(symbol)),
+            None => None,
+        }
+    }
+}
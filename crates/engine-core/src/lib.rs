@@ -5,33 +5,88 @@
 //! - order representation
 //! - per-symbol order book
 //! - multi-symbol matching engine
+//! - instrument reference data
+//! - FIX-style execution reports
+//! - injectable clock for deterministic timestamps
 
 pub mod side;
 pub mod order_type;
+pub mod time_in_force;
+pub mod order_flags;
+pub mod self_trade_prevention;
 pub mod messages;
 pub mod order;
 pub mod order_book;
+pub mod auction;
+pub mod trading_state;
+pub mod instrument;
+pub mod volatility;
+pub mod execution;
+mod price_level;
+mod stop_book;
+mod user_index;
 pub mod matching_engine;
 pub mod error;
 pub mod top_of_book;
+pub mod depth;
+pub mod book_event;
+pub mod snapshot;
+pub mod clock;
 
 pub use side::Side;
 pub use order_type::OrderType;
+pub use time_in_force::TimeInForce;
+pub use order_flags::OrderFlags;
+pub use self_trade_prevention::SelfTradePrevention;
+pub use auction::AuctionAction;
+pub use trading_state::TradingState;
+pub use instrument::{Instrument, InstrumentRegistry};
+pub use volatility::{VolatilityAction, VolatilityBands};
+pub use execution::{ExecType, OrdStatus};
 
 pub use messages::{
     Ack,
+    AuctionCommand,
     Cancel,
     CancelAck,
+    DepthQuery,
+    DepthUpdate,
+    EnableTrading,
+    ExecutionReport,
+    Expired,
+    FlushCommand,
+    IndicativeUncross,
     InputMessage,
+    InstrumentQuery,
+    KillSwitch,
+    Logon,
+    MassCancel,
+    MassCancelAck,
     NewOrder,
     OutputMessage,
+    PublicTrade,
+    Reject,
+    Replace,
+    ReplaceAck,
+    RiskLimits,
+    SelfTradeCancel,
+    SessionStatus,
+    SetRiskLimits,
+    SetTradingState,
     TopOfBook,
     TopOfBookQuery,
     Trade,
+    TradingStatus,
+    Triggered,
+    UserTradingStatus,
+    VolatilityInterruption,
 };
 
 pub use order::Order;
 pub use order_book::OrderBook;
 pub use matching_engine::MatchingEngine;
-pub use error::EngineError;
+pub use error::{EngineError, RejectReason};
+pub use depth::{BookDepth, DepthAction, DepthEvent, DepthLevel};
+pub use book_event::OrderBookEvent;
+pub use clock::{Clock, ManualClock, ReplayClock, SystemClock};
 
@@ -10,59 +10,434 @@
 //! - Supports `InputMessage::QueryTopOfBook` to snapshot current TOB
 //!   for a given symbol.
 //! - All outputs are symbol-aware (the book injects `symbol`).
+//! - Requests that can't be applied (unknown order, duplicate id, bad
+//!   symbol, ...) produce an `OutputMessage::Reject` instead of a
+//!   placeholder `CancelAck`.
+//! - Each symbol has a [`TradingState`], switched by
+//!   `InputMessage::SetTradingState` (or `InputMessage::Auction`) and
+//!   announced with `OutputMessage::TradingStatus`. Halted and closed
+//!   symbols refuse new orders and replaces, or queue new orders until
+//!   they reopen (see [`MatchingEngine::set_queue_when_halted`]);
+//!   cancels always go through.
+//! - With an [`InstrumentRegistry`] set, only listed symbols trade, and
+//!   new orders and replaces must follow their instrument's tick size,
+//!   lot size, quantity limits and price collar, and market orders get
+//!   their instrument's market order protection.
+//! - With [`VolatilityBands`] set, a match outside a symbol's band
+//!   interrupts it (`OutputMessage::VolatilityInterruption`): the symbol
+//!   goes into an auction or a halt, and back to `Continuous` once the
+//!   interruption's time is up (see [`crate::volatility`]).
+//! - With execution reports on, each output that changes an order is
+//!   followed by its `OutputMessage::ExecutionReport`s (see
+//!   [`crate::execution`]).
+//! - `InputMessage::KillSwitch` blocks a user's (or every user's) new
+//!   orders and replaces until `InputMessage::EnableTrading`, optionally
+//!   cancelling their live orders, and reports it with
+//!   `OutputMessage::UserTradingStatus`.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read, Write};
+use std::sync::Arc;
 
+use crate::auction::AuctionAction;
+use crate::error::RejectReason;
+use crate::execution::ExecutionTracker;
+use crate::instrument::InstrumentRegistry;
 use crate::messages::{
     // Ack,
+    AuctionCommand,
     Cancel,
     // CancelAck,
+    DepthQuery,
+    DepthUpdate,
+    EnableTrading,
     InputMessage,
+    InstrumentQuery,
+    KillSwitch,
+    MassCancel,
+    MassCancelAck,
     NewOrder,
     OutputMessage,
+    Replace,
+    SetTradingState,
     // TopOfBook,
+    TradingStatus,
     TopOfBookQuery,
+    UserTradingStatus,
+    VolatilityInterruption,
 };
+use crate::book_event::OrderBookEvent;
+use crate::clock::{Clock, SystemClock};
+use crate::depth::{DepthAction, DepthEvent, DepthLevel};
+use crate::order::Order;
 use crate::order_book::OrderBook;
+use crate::self_trade_prevention::SelfTradePrevention;
+use crate::snapshot::{self, EngineState};
 use crate::side::Side;
+use crate::trading_state::TradingState;
+use crate::volatility::{VolatilityAction, VolatilityBands};
 
 /// Multi-symbol matching engine.
 ///
 /// Owns a set of `OrderBook`s, one per symbol, and a cross-book
 /// mapping from `(user_id, user_order_id)` to symbol for cancel
 /// routing (mirroring your C++ `order_to_symbol_` map).
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct MatchingEngine {
+    /// Time source shared by every book.
+    clock: Arc<dyn Clock>,
+
     /// Symbol -> OrderBook.
     order_books: HashMap<String, OrderBook>,
 
     /// Tracks which symbol an order belongs to, keyed by `(user_id, user_order_id)`.
     ///
+    /// Only live (resting, pending stop or queued) orders are kept here;
+    /// entries are dropped on cancel and when an order fully fills.
+    ///
     /// This mirrors your C++:
     /// ```cpp
     /// std::unordered_map<uint64_t, std::string> order_to_symbol_;
     /// ```
     /// but we use a `(u32, u32)` tuple rather than a packed u64.
     order_to_symbol: HashMap<(u32, u32), String>,
+
+    /// Whether books emit incremental `DepthUpdate`s (see
+    /// [`MatchingEngine::set_depth_updates`]).
+    depth_updates: bool,
+
+    /// Whether books record L3 `OrderBookEvent`s.
+    book_events: bool,
+
+    /// L3 events from books that have since been dropped (flush).
+    flushed_book_events: Vec<OrderBookEvent>,
+
+    /// Per-user self-trade prevention mode, used for new orders that
+    /// don't set their own. Configuration, not part of snapshots.
+    self_trade_prevention: HashMap<u32, SelfTradePrevention>,
+
+    /// Trading state per symbol; symbols not listed are `Continuous`.
+    trading_states: HashMap<String, TradingState>,
+
+    /// Queue new orders for halted / closed symbols instead of rejecting
+    /// them. Configuration, not part of snapshots.
+    queue_when_halted: bool,
+
+    /// New orders queued while their symbol was halted or closed, per
+    /// symbol in arrival order.
+    queued_orders: HashMap<String, Vec<NewOrder>>,
+
+    /// Instrument reference data. Configuration, not part of snapshots.
+    instruments: InstrumentRegistry,
+
+    /// Volatility band settings. Configuration, not part of snapshots.
+    volatility_bands: Option<VolatilityBands>,
+
+    /// Symbol -> engine time its volatility interruption ends.
+    volatility_resumes: HashMap<String, u64>,
+
+    /// Whether `ExecutionReport`s are added to the outputs (see
+    /// [`MatchingEngine::set_execution_reports`]).
+    execution_reports: bool,
+
+    /// Exec ids and live orders' progress, for execution reports.
+    executions: ExecutionTracker,
+
+    /// Users blocked by a kill switch of their own.
+    disabled_users: HashSet<u32>,
+
+    /// Every user is blocked by a kill switch.
+    all_users_disabled: bool,
+}
+
+impl Default for MatchingEngine {
+    fn default() -> Self {
+        MatchingEngine::with_clock(Arc::new(SystemClock))
+    }
 }
 
 impl MatchingEngine {
-    /// Create a new, empty matching engine.
+    /// Create a new, empty matching engine on the system clock.
     pub fn new() -> Self {
         MatchingEngine::default()
     }
 
+    /// Create a new, empty matching engine whose books take order
+    /// priority and trade timestamps from `clock`.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        MatchingEngine {
+            clock,
+            order_books: HashMap::new(),
+            order_to_symbol: HashMap::new(),
+            depth_updates: false,
+            book_events: false,
+            flushed_book_events: Vec::new(),
+            self_trade_prevention: HashMap::new(),
+            trading_states: HashMap::new(),
+            queue_when_halted: false,
+            queued_orders: HashMap::new(),
+            instruments: InstrumentRegistry::new(),
+            volatility_bands: None,
+            volatility_resumes: HashMap::new(),
+            execution_reports: false,
+            executions: ExecutionTracker::default(),
+            disabled_users: HashSet::new(),
+            all_users_disabled: false,
+        }
+    }
+
+    /// Switch every book, current and future, to `clock`.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        for book in self.order_books.values_mut() {
+            book.set_clock(Arc::clone(&clock));
+        }
+        self.clock = clock;
+    }
+
+    /// Enable or disable incremental `DepthUpdate` outputs on every book,
+    /// current and future. Off by default.
+    pub fn set_depth_updates(&mut self, enabled: bool) {
+        self.depth_updates = enabled;
+        for book in self.order_books.values_mut() {
+            book.set_depth_updates(enabled);
+        }
+    }
+
+    /// Enable or disable `ExecutionReport` outputs. Off by default.
+    ///
+    /// Reports only cover orders entered while they are on; turn them on
+    /// before the first order (and keep the setting when replaying).
+    pub fn set_execution_reports(&mut self, enabled: bool) {
+        self.execution_reports = enabled;
+    }
+
+    /// Enable or disable recording of L3 [`OrderBookEvent`]s on every
+    /// book, current and future. Off by default.
+    pub fn set_book_events(&mut self, enabled: bool) {
+        self.book_events = enabled;
+        self.flushed_book_events.clear();
+        for book in self.order_books.values_mut() {
+            book.set_book_events(enabled);
+        }
+    }
+
+    /// Set `user_id`'s default self-trade prevention mode, applied to its
+    /// new orders that don't set one (`None` removes the default).
+    pub fn set_self_trade_prevention(&mut self, user_id: u32, mode: SelfTradePrevention) {
+        if mode.is_enabled() {
+            self.self_trade_prevention.insert(user_id, mode);
+        } else {
+            self.self_trade_prevention.remove(&user_id);
+        }
+    }
+
+    /// Queue new orders that arrive while their symbol is halted or
+    /// closed (acked now, entered in arrival order when it reopens)
+    /// instead of rejecting them. Off by default.
+    pub fn set_queue_when_halted(&mut self, enabled: bool) {
+        self.queue_when_halted = enabled;
+    }
+
+    /// Replace the instrument registry. Checks apply to requests from now
+    /// on; orders already in the books are kept.
+    pub fn set_instruments(&mut self, instruments: InstrumentRegistry) {
+        for (symbol, book) in self.order_books.iter_mut() {
+            book.set_tick_size(instruments.tick_size(symbol));
+            book.set_market_protection(instruments.market_protection_ticks(symbol));
+        }
+        self.instruments = instruments;
+    }
+
+    /// The instrument registry.
+    pub fn instruments(&self) -> &InstrumentRegistry {
+        &self.instruments
+    }
+
+    /// Set (or, with `None`, remove) the volatility bands of every book,
+    /// current and future. Off by default. Interruptions already running
+    /// still end at their resume time.
+    pub fn set_volatility_bands(&mut self, bands: Option<VolatilityBands>) {
+        let band_bps = bands.map_or(0, |b| b.band_bps);
+        for book in self.order_books.values_mut() {
+            book.set_price_band(band_bps);
+        }
+        self.volatility_bands = bands;
+    }
+
+    /// The volatility interruption that ends first, as
+    /// `(resume_ns, symbol)`. It only ends when the engine next processes
+    /// a request at or after that time, so a server that wants the symbol
+    /// back on time sends one then (`SetTradingState` to `Continuous`
+    /// resumes it the same way).
+    pub fn next_volatility_resume(&self) -> Option<(u64, &str)> {
+        self.volatility_resumes
+            .iter()
+            .map(|(symbol, &resume_ns)| (resume_ns, symbol.as_str()))
+            .min()
+    }
+
+    /// Whether `user_id` may enter orders (no kill switch blocks it).
+    pub fn is_trading_enabled(&self, user_id: u32) -> bool {
+        !self.all_users_disabled && !self.disabled_users.contains(&user_id)
+    }
+
+    /// Current trading state of `symbol`.
+    pub fn trading_state(&self, symbol: &str) -> TradingState {
+        self.trading_states.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Take the L3 events recorded since the last call.
+    ///
+    /// Call after each `process_message` to keep the feed in step with
+    /// the `OutputMessage`s; events of a single book are in order.
+    pub fn take_book_events(&mut self) -> Vec<OrderBookEvent> {
+        let mut events = std::mem::take(&mut self.flushed_book_events);
+        for book in self.order_books.values_mut() {
+            events.append(&mut book.take_book_events());
+        }
+        events
+    }
+
+    /// Write a snapshot of all book state to `out`.
+    ///
+    /// `journal_seq` is the sequence number of the last journal record
+    /// already applied to this engine; recovery restores the snapshot
+    /// and replays only later records. See [`snapshot`](crate::snapshot)
+    /// for the format.
+    ///
+    /// Output settings (depth updates, book events) are configuration,
+    /// not state, and are not included.
+    pub fn snapshot<W: Write>(&self, journal_seq: u64, out: W) -> io::Result<()> {
+        let mut books: Vec<_> = self.order_books.values().map(OrderBook::to_state).collect();
+        books.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+        let mut order_to_symbol: Vec<_> = self
+            .order_to_symbol
+            .iter()
+            .map(|(&key, symbol)| (key, symbol.clone()))
+            .collect();
+        order_to_symbol.sort();
+
+        let mut trading_states: Vec<_> = self
+            .trading_states
+            .iter()
+            .map(|(symbol, &state)| (symbol.clone(), state))
+            .collect();
+        trading_states.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut queued: Vec<_> = self.queued_orders.iter().collect();
+        queued.sort_by(|a, b| a.0.cmp(b.0));
+        let queued_orders = queued.into_iter().flat_map(|(_, orders)| orders.iter().cloned()).collect();
+
+        let mut volatility_resumes: Vec<_> = self
+            .volatility_resumes
+            .iter()
+            .map(|(symbol, &resume_ns)| (symbol.clone(), resume_ns))
+            .collect();
+        volatility_resumes.sort();
+
+        let mut disabled_users: Vec<_> = self.disabled_users.iter().copied().collect();
+        disabled_users.sort_unstable();
+
+        let state = EngineState {
+            journal_seq,
+            books,
+            order_to_symbol,
+            trading_states,
+            queued_orders,
+            volatility_resumes,
+            next_exec_id: self.executions.next_exec_id(),
+            order_progress: self.executions.order_progress(),
+            all_users_disabled: self.all_users_disabled,
+            disabled_users,
+        };
+        snapshot::write_state(&state, out)
+    }
+
+    /// Rebuild an engine from a snapshot written by
+    /// [`MatchingEngine::snapshot`], returning it with the journal
+    /// sequence number the snapshot covers. The engine uses the system
+    /// clock.
+    pub fn restore<R: Read>(input: R) -> io::Result<(Self, u64)> {
+        Self::restore_with_clock(input, Arc::new(SystemClock))
+    }
+
+    /// Like [`MatchingEngine::restore`], with the restored engine reading
+    /// time from `clock`.
+    pub fn restore_with_clock<R: Read>(input: R, clock: Arc<dyn Clock>) -> io::Result<(Self, u64)> {
+        let state = snapshot::read_state(input)?;
+
+        let mut engine = MatchingEngine::with_clock(clock);
+        for book in state.books {
+            let clock = Arc::clone(&engine.clock);
+            engine
+                .order_books
+                .insert(book.symbol.clone(), OrderBook::from_state(book, clock));
+        }
+        engine.order_to_symbol = state.order_to_symbol.into_iter().collect();
+        engine.trading_states = state.trading_states.into_iter().collect();
+        for order in state.queued_orders {
+            engine.queued_orders.entry(order.symbol.clone()).or_default().push(order);
+        }
+        engine.volatility_resumes = state.volatility_resumes.into_iter().collect();
+        engine.executions = ExecutionTracker::from_state(state.order_progress, state.next_exec_id);
+        engine.all_users_disabled = state.all_users_disabled;
+        engine.disabled_users = state.disabled_users.into_iter().collect();
+
+        Ok((engine, state.journal_seq))
+    }
+
     /// Process a single input message and return any output events.
     ///
     /// This combines the behavior of your C++ `processMessage`,
     /// `processNewOrder`, `processCancelOrder`, and `processFlush`,
     /// plus the new `QueryTopOfBook` support.
+    ///
+    /// Before any input other than a query, symbols whose volatility
+    /// interruption is over go back to `Continuous`; their outputs come
+    /// first.
     pub fn process_message(&mut self, msg: InputMessage) -> Vec<OutputMessage> {
+        let is_query = matches!(
+            msg,
+            InputMessage::QueryTopOfBook(_) | InputMessage::QueryDepth(_) | InputMessage::QueryInstrument(_)
+        );
+        let new_order = match &msg {
+            InputMessage::NewOrder(o) => Some(((o.user_id, o.user_order_id), o.quantity)),
+            _ => None,
+        };
+
+        let mut outputs = if is_query {
+            Vec::new()
+        } else {
+            self.resume_interrupted_symbols()
+        };
+        outputs.extend(self.dispatch(msg));
+
+        if self.execution_reports && !is_query {
+            outputs = self.add_execution_reports(outputs, new_order);
+        }
+        outputs
+    }
+
+    fn dispatch(&mut self, msg: InputMessage) -> Vec<OutputMessage> {
         match msg {
             InputMessage::NewOrder(new) => self.process_new_order(&new),
             InputMessage::Cancel(cancel) => self.process_cancel(cancel),
-            InputMessage::Flush => self.process_flush(),
+            InputMessage::Replace(replace) => self.process_replace(replace),
+            InputMessage::Flush(_) => self.process_flush(),
             InputMessage::QueryTopOfBook(query) => self.process_query_top_of_book(query),
+            InputMessage::QueryDepth(query) => self.process_query_depth(query),
+            InputMessage::Auction(command) => self.process_auction(command),
+            InputMessage::SetTradingState(msg) => self.process_set_trading_state(msg),
+            InputMessage::QueryInstrument(query) => self.process_query_instrument(query),
+            InputMessage::MassCancel(request) => self.process_mass_cancel(request),
+            InputMessage::KillSwitch(command) => self.process_kill_switch(command),
+            InputMessage::EnableTrading(command) => self.process_enable_trading(command),
+            // Pre-trade risk and sessions live in front of the engine.
+            InputMessage::SetRiskLimits(_)
+            | InputMessage::Logon(_)
+            | InputMessage::Heartbeat
+            | InputMessage::Logout => Vec::new(),
         }
     }
 
@@ -75,70 +450,464 @@ impl MatchingEngine {
         // Your order_to_symbol map is keyed by (u32, u32), so:
         let key = (msg.user_id, msg.user_order_id);
 
+        if !Self::is_valid_symbol(&symbol) {
+            return vec![OutputMessage::reject(
+                msg.user_id,
+                msg.user_order_id,
+                String::new(),
+                RejectReason::InvalidSymbol,
+            )];
+        }
+
+        if !self.is_trading_enabled(msg.user_id) {
+            return vec![OutputMessage::reject(
+                msg.user_id,
+                msg.user_order_id,
+                symbol,
+                RejectReason::TradingDisabled,
+            )];
+        }
+
+        if let Some(reason) = self.check_instrument(msg) {
+            return vec![OutputMessage::reject(msg.user_id, msg.user_order_id, symbol, reason)];
+        }
+
+        // `order_to_symbol` only holds live orders, so a hit here means the
+        // id is still in use (possibly in another book).
+        if let Some(live_symbol) = self.order_to_symbol.get(&key) {
+            return vec![OutputMessage::reject(
+                msg.user_id,
+                msg.user_order_id,
+                live_symbol.clone(),
+                RejectReason::DuplicateOrderId,
+            )];
+        }
+
+        let state = self.trading_state(&symbol);
+        if !state.accepts_orders() {
+            if self.queue_when_halted {
+                self.queued_orders.entry(symbol.clone()).or_default().push(msg.clone());
+                self.order_to_symbol.insert(key, symbol.clone());
+                return vec![OutputMessage::ack(msg.user_id, msg.user_order_id, symbol)];
+            }
+            return vec![OutputMessage::reject(
+                msg.user_id,
+                msg.user_order_id,
+                symbol,
+                Self::not_trading_reason(state),
+            )];
+        }
+
+        // Orders without their own self-trade prevention mode get the
+        // user's default.
+        let user_stp = match self.self_trade_prevention.get(&msg.user_id) {
+            Some(&mode) if !msg.self_trade_prevention.is_enabled() => Some(NewOrder {
+                self_trade_prevention: mode,
+                ..msg.clone()
+            }),
+            _ => None,
+        };
+        let msg = user_stp.as_ref().unwrap_or(msg);
+
         // Limit the &mut self borrow (via book) to this block:
-        let outputs = {
+        let (mut outputs, rested) = {
             let book = self.get_or_create_order_book(&symbol);
-            book.add_order(msg)
+            let outputs = book.add_order(msg);
+            (outputs, book.contains_order(msg.user_id, msg.user_order_id))
         };
 
         // Now the book borrow is over, so it's safe to mutate self.order_to_symbol
-        self.order_to_symbol.insert(key, symbol);
+        if rested {
+            self.order_to_symbol.insert(key, symbol.clone());
+        }
+        self.forget_filled_orders(&symbol, &outputs);
+        outputs.extend(self.check_band_breach(&symbol));
 
         outputs
-    }    
+    }
 
     fn process_cancel(&mut self, msg: Cancel) -> Vec<OutputMessage> {
         let key = (msg.user_id, msg.user_order_id);
 
+        // A queued order never reached its book.
+        if let Some(symbol) = self.order_to_symbol.get(&key).cloned() {
+            if let Some(queue) = self.queued_orders.get_mut(&symbol) {
+                if let Some(pos) = queue
+                    .iter()
+                    .position(|o| (o.user_id, o.user_order_id) == key)
+                {
+                    queue.remove(pos);
+                    self.order_to_symbol.remove(&key);
+                    return vec![OutputMessage::cancel_ack(msg.user_id, msg.user_order_id, symbol)];
+                }
+            }
+        }
+
         // Find which symbol this order belongs to.
-        let symbol_opt = self.order_to_symbol.get(&key).cloned();
+        let book = self
+            .order_to_symbol
+            .get(&key)
+            .and_then(|symbol| self.order_books.get_mut(symbol));
+
+        match book {
+            Some(book) => {
+                let outputs = book.cancel_order(msg.user_id, msg.user_order_id);
+                self.order_to_symbol.remove(&key);
+                outputs
+            }
+            None => vec![OutputMessage::reject(
+                msg.user_id,
+                msg.user_order_id,
+                String::new(),
+                RejectReason::UnknownOrder,
+            )],
+        }
+    }
+
+    /// Cancel every order `request` matches, symbol by symbol (in symbol
+    /// order, queued orders first), then report how many.
+    fn process_mass_cancel(&mut self, request: MassCancel) -> Vec<OutputMessage> {
+        let mut symbols: Vec<String> = match &request.symbol {
+            Some(symbol) => vec![symbol.clone()],
+            None => self
+                .order_books
+                .keys()
+                .chain(self.queued_orders.keys())
+                .cloned()
+                .collect(),
+        };
+        symbols.sort();
+        symbols.dedup();
+
+        let mut outputs = Vec::new();
+        for symbol in &symbols {
+            if let Some(queue) = self.queued_orders.get_mut(symbol) {
+                queue.retain(|o| {
+                    // Not yet triggered: a stop goes by its stop price.
+                    let price = if o.stop_price > 0 { o.stop_price } else { o.price };
+                    let cancel = request.matches(o.user_id, o.side, price);
+                    if cancel {
+                        outputs.push(OutputMessage::cancel_ack(o.user_id, o.user_order_id, symbol.clone()));
+                    }
+                    !cancel
+                });
+            }
+            if let Some(book) = self.order_books.get_mut(symbol) {
+                outputs.extend(book.mass_cancel(&request));
+            }
+        }
+
+        let mut cancelled_count = 0;
+        for out in &outputs {
+            if let OutputMessage::CancelAck(c) = out {
+                self.order_to_symbol.remove(&(c.user_id, c.user_order_id));
+                cancelled_count += 1;
+            }
+        }
+        outputs.push(OutputMessage::MassCancelAck(MassCancelAck {
+            user_id: request.user_id,
+            symbol: request.symbol.unwrap_or_default(),
+            cancelled_count,
+        }));
+        outputs
+    }
+
+    /// Block the user (or everyone), then mass cancel each affected
+    /// user's live orders if asked, in user id order.
+    fn process_kill_switch(&mut self, command: KillSwitch) -> Vec<OutputMessage> {
+        if command.user_id == 0 {
+            self.all_users_disabled = true;
+        } else {
+            self.disabled_users.insert(command.user_id);
+        }
+        let mut outputs = vec![OutputMessage::UserTradingStatus(UserTradingStatus {
+            user_id: command.user_id,
+            enabled: false,
+        })];
+
+        if command.cancel_orders {
+            let mut users: Vec<u32> = if command.user_id == 0 {
+                self.order_to_symbol.keys().map(|&(user_id, _)| user_id).collect()
+            } else {
+                vec![command.user_id]
+            };
+            users.sort_unstable();
+            users.dedup();
+            for user_id in users {
+                outputs.extend(self.process_mass_cancel(MassCancel {
+                    user_id,
+                    symbol: None,
+                    side: None,
+                    min_price: None,
+                    max_price: None,
+                }));
+            }
+        }
+        outputs
+    }
 
-        match symbol_opt {
+    fn process_enable_trading(&mut self, command: EnableTrading) -> Vec<OutputMessage> {
+        let enabled = if command.user_id == 0 {
+            self.all_users_disabled = false;
+            true
+        } else {
+            self.disabled_users.remove(&command.user_id);
+            self.is_trading_enabled(command.user_id)
+        };
+        vec![OutputMessage::UserTradingStatus(UserTradingStatus {
+            user_id: command.user_id,
+            enabled,
+        })]
+    }
+
+    fn process_replace(&mut self, msg: Replace) -> Vec<OutputMessage> {
+        let key = (msg.user_id, msg.user_order_id);
+
+        let symbol = match self.order_to_symbol.get(&key) {
+            Some(symbol) => symbol.clone(),
             None => {
-                // Order not found globally - still send CancelAck (C++ behavior).
-                vec![OutputMessage::cancel_ack(
+                return vec![OutputMessage::reject(
                     msg.user_id,
                     msg.user_order_id,
-                    "<unknown>".to_string(), // We don't know the symbol; can be adjusted.
-                )]
+                    String::new(),
+                    RejectReason::UnknownOrder,
+                )];
             }
-            Some(symbol) => {
-                // If the symbol is known but the book somehow doesn't exist,
-                // still send CancelAck and clean up the mapping (C++ behavior).
-                let outputs = if let Some(book) = self.order_books.get_mut(&symbol) {
-                    book.cancel_order(msg.user_id, msg.user_order_id)
-                } else {
-                    vec![OutputMessage::cancel_ack(
-                        msg.user_id,
-                        msg.user_order_id,
-                        symbol.clone(),
-                    )]
-                };
-
-                // Remove from tracking map regardless of whether it existed in the book.
-                self.order_to_symbol.remove(&key);
+        };
 
-                outputs
-            }
+        if !self.is_trading_enabled(msg.user_id) {
+            return vec![OutputMessage::reject(
+                msg.user_id,
+                msg.user_order_id,
+                symbol,
+                RejectReason::TradingDisabled,
+            )];
+        }
+
+        let state = self.trading_state(&symbol);
+        if !state.accepts_orders() {
+            return vec![OutputMessage::reject(
+                msg.user_id,
+                msg.user_order_id,
+                symbol,
+                Self::not_trading_reason(state),
+            )];
+        }
+
+        if let Some(reason) = self.check_replace(&symbol, &msg) {
+            return vec![OutputMessage::reject(msg.user_id, msg.user_order_id, symbol, reason)];
         }
+
+        let mut outputs = match self.order_books.get_mut(&symbol) {
+            Some(book) => book.replace_order(
+                msg.user_id,
+                msg.user_order_id,
+                msg.new_price,
+                msg.new_quantity,
+            ),
+            None => Vec::new(),
+        };
+
+        // A replace to a crossing price can fill this and other orders.
+        self.forget_filled_orders(&symbol, &outputs);
+        outputs.extend(self.check_band_breach(&symbol));
+
+        outputs
     }
 
     fn process_flush(&mut self) -> Vec<OutputMessage> {
         let mut outputs = Vec::new();
 
-        // For each order book, flush and collect its outputs
-        for (_symbol, book) in self.order_books.iter_mut() {
+        // For each order book (in symbol order, so the output is
+        // deterministic), flush and collect its outputs
+        let mut books: Vec<_> = self.order_books.iter_mut().collect();
+        books.sort_by(|a, b| a.0.cmp(b.0));
+        for (_symbol, book) in books {
             let mut book_outputs = book.flush();
             outputs.append(&mut book_outputs);
+            self.flushed_book_events.append(&mut book.take_book_events());
+        }
+
+        // Queued orders are cancelled too.
+        let mut queued: Vec<_> = self.queued_orders.drain().collect();
+        queued.sort_by(|a, b| a.0.cmp(&b.0));
+        for (symbol, orders) in queued {
+            for order in orders {
+                outputs.push(OutputMessage::cancel_ack(order.user_id, order.user_order_id, symbol.clone()));
+            }
         }
 
-        // Clear tracking structures
+        // Clear tracking structures (trading states are kept)
         self.order_books.clear();
         self.order_to_symbol.clear();
 
         outputs
     }
 
+    /// Start or uncross a symbol's call auction: shorthand for moving it
+    /// to `Auction`, or from `PreOpen` / `Auction` to `Continuous` (see
+    /// `process_set_trading_state`). `Uncross` does nothing in other
+    /// states.
+    fn process_auction(&mut self, command: AuctionCommand) -> Vec<OutputMessage> {
+        if !Self::is_valid_symbol(&command.symbol) {
+            return Vec::new();
+        }
+        match command.action {
+            AuctionAction::Start => self.change_trading_state(&command.symbol, TradingState::Auction),
+            AuctionAction::Uncross if self.trading_state(&command.symbol).is_call_phase() => {
+                self.change_trading_state(&command.symbol, TradingState::Continuous)
+            }
+            AuctionAction::Uncross => Vec::new(),
+        }
+    }
+
+    /// Move a symbol to another trading state. Emits, in order:
+    /// - `TradingStatus` (nothing at all if the state doesn't change),
+    /// - entering `PreOpen` / `Auction`: the indicative uncross (the book
+    ///   is created if needed),
+    /// - entering `Halted` / `Closed`: nothing, but a call auction is
+    ///   suspended (no indicative is published until the next one),
+    /// - entering `Continuous`: the uncross of whatever a call auction
+    ///   left crossing (and, if a stop it triggers breaches the volatility
+    ///   band, the interruption),
+    /// - entering a state that accepts orders: the outputs of each queued
+    ///   order, entered in arrival order (without a second `Ack`).
+    ///
+    /// An invalid symbol is ignored.
+    fn process_set_trading_state(&mut self, msg: SetTradingState) -> Vec<OutputMessage> {
+        if !Self::is_valid_symbol(&msg.symbol) {
+            return Vec::new();
+        }
+        self.change_trading_state(&msg.symbol, msg.state)
+    }
+
+    fn change_trading_state(&mut self, symbol: &str, state: TradingState) -> Vec<OutputMessage> {
+        if self.trading_state(symbol) == state {
+            return Vec::new();
+        }
+        // Any change, by an admin or not, ends a volatility interruption.
+        self.volatility_resumes.remove(symbol);
+        let mut outputs = vec![OutputMessage::TradingStatus(TradingStatus {
+            symbol: symbol.to_string(),
+            state,
+        })];
+
+        if state.is_call_phase() {
+            // Created before the state is recorded, so a new book doesn't
+            // start its auction silently.
+            let book = self.get_or_create_order_book(symbol);
+            outputs.extend(book.start_auction());
+        }
+        if state == TradingState::Continuous {
+            self.trading_states.remove(symbol);
+        } else {
+            self.trading_states.insert(symbol.to_string(), state);
+        }
+
+        if matches!(state, TradingState::Halted | TradingState::Closed) {
+            // Nothing is published while the symbol is stopped.
+            if let Some(book) = self.order_books.get_mut(symbol) {
+                book.suspend_auction();
+            }
+        }
+        if state == TradingState::Continuous {
+            if let Some(book) = self.order_books.get_mut(symbol) {
+                let uncross = book.resume();
+                self.forget_filled_orders(symbol, &uncross);
+                outputs.extend(uncross);
+                outputs.extend(self.check_band_breach(symbol));
+            }
+        }
+
+        if state.accepts_orders() {
+            outputs.extend(self.release_queued_orders(symbol));
+        }
+        outputs
+    }
+
+    /// Enter `symbol`'s queued orders in arrival order. They were acked
+    /// when queued, so their `Ack`s are dropped.
+    fn release_queued_orders(&mut self, symbol: &str) -> Vec<OutputMessage> {
+        let mut outputs = Vec::new();
+        for order in self.queued_orders.remove(symbol).unwrap_or_default() {
+            self.order_to_symbol.remove(&(order.user_id, order.user_order_id));
+            let mut released = self.process_new_order(&order);
+            if matches!(released.first(), Some(OutputMessage::Ack(_))) {
+                released.remove(0);
+            }
+            outputs.extend(released);
+        }
+        outputs
+    }
+
+    /// Follow each output with the execution reports of the orders it
+    /// changes. `new_order` is the request's `(key, quantity)` if it was
+    /// a new order.
+    fn add_execution_reports(
+        &mut self,
+        outputs: Vec<OutputMessage>,
+        new_order: Option<((u32, u32), u32)>,
+    ) -> Vec<OutputMessage> {
+        let now = self.clock.now_ns();
+        let order_to_symbol = &self.order_to_symbol;
+        let mut with_reports = Vec::with_capacity(outputs.len() * 2);
+        for out in outputs {
+            let reports = self
+                .executions
+                .reports(&out, new_order, |key| order_to_symbol.contains_key(&key), now);
+            with_reports.push(out);
+            with_reports.extend(reports.into_iter().map(OutputMessage::ExecutionReport));
+        }
+        with_reports
+    }
+
+    /// If the last request on `symbol`'s book breached its volatility
+    /// band, interrupt the symbol: `VolatilityInterruption`, then the
+    /// move to `Auction` or `Halted` until `now + duration`.
+    fn check_band_breach(&mut self, symbol: &str) -> Vec<OutputMessage> {
+        let breach = self.order_books.get_mut(symbol).and_then(OrderBook::take_band_breach);
+        let (Some((reference_price, trigger_price)), Some(bands)) = (breach, self.volatility_bands) else {
+            return Vec::new();
+        };
+
+        let resume_ns = self.clock.now_ns().saturating_add(bands.duration_ns);
+        let mut outputs = vec![OutputMessage::VolatilityInterruption(VolatilityInterruption {
+            symbol: symbol.to_string(),
+            reference_price,
+            trigger_price,
+            resume_ns,
+        })];
+        let state = match bands.action {
+            VolatilityAction::Auction => TradingState::Auction,
+            VolatilityAction::Halt => TradingState::Halted,
+        };
+        outputs.extend(self.change_trading_state(symbol, state));
+        self.volatility_resumes.insert(symbol.to_string(), resume_ns);
+        outputs
+    }
+
+    /// Return symbols whose volatility interruption is over to
+    /// `Continuous`, in order of resume time (then symbol).
+    fn resume_interrupted_symbols(&mut self) -> Vec<OutputMessage> {
+        if self.volatility_resumes.is_empty() {
+            return Vec::new();
+        }
+        let now = self.clock.now_ns();
+        let mut due: Vec<_> = self
+            .volatility_resumes
+            .iter()
+            .filter(|&(_, &resume_ns)| resume_ns <= now)
+            .map(|(symbol, &resume_ns)| (resume_ns, symbol.clone()))
+            .collect();
+        due.sort();
+
+        let mut outputs = Vec::new();
+        for (_, symbol) in due {
+            outputs.extend(self.change_trading_state(&symbol, TradingState::Continuous));
+        }
+        outputs
+    }
+
     /// Process a query for current top-of-book for a given symbol.
     ///
     /// Semantics:
@@ -192,15 +961,141 @@ impl MatchingEngine {
         outputs
     }
 
+    /// Process a depth query: a snapshot `DepthUpdate` with an `Add` per
+    /// level, bids then asks, best price first. An unknown symbol gets an
+    /// empty snapshot.
+    fn process_query_depth(&mut self, query: DepthQuery) -> Vec<OutputMessage> {
+        let depth = self
+            .order_books
+            .get(&query.symbol)
+            .map(|book| book.depth(query.levels as usize))
+            .unwrap_or_default();
+
+        let add = |side: Side| {
+            move |level: DepthLevel| DepthEvent {
+                action: DepthAction::Add,
+                side,
+                price: level.price,
+                quantity: level.quantity,
+            }
+        };
+        let events = depth
+            .bids
+            .into_iter()
+            .map(add(Side::Buy))
+            .chain(depth.asks.into_iter().map(add(Side::Sell)))
+            .collect();
+
+        vec![OutputMessage::DepthUpdate(DepthUpdate {
+            symbol: query.symbol,
+            snapshot: true,
+            events,
+        })]
+    }
+
+    /// Process an instrument query: the symbol's definition (an
+    /// unrestricted one when no registry is set), or a `Reject` with
+    /// zero ids if it isn't listed.
+    fn process_query_instrument(&mut self, query: InstrumentQuery) -> Vec<OutputMessage> {
+        if !Self::is_valid_symbol(&query.symbol) {
+            return vec![OutputMessage::reject(0, 0, String::new(), RejectReason::InvalidSymbol)];
+        }
+        match self.instruments.resolve(&query.symbol) {
+            Some(instrument) => vec![OutputMessage::InstrumentDefinition(instrument)],
+            None => vec![OutputMessage::reject(0, 0, query.symbol, RejectReason::UnknownInstrument)],
+        }
+    }
+
     // -------------------------------------------------------------------------
     // Helpers
     // -------------------------------------------------------------------------
 
+    /// Why a new order breaks the registry's rules, if it does.
+    fn check_instrument(&self, msg: &NewOrder) -> Option<RejectReason> {
+        match self.instruments.get(&msg.symbol) {
+            Some(instrument) => instrument.check_new_order(msg),
+            None if self.instruments.is_empty() => None,
+            None => Some(RejectReason::UnknownInstrument),
+        }
+    }
+
+    /// Why a replace breaks its instrument's rules, if it does. Zero /
+    /// unchanged fields are left to the book.
+    fn check_replace(&self, symbol: &str, msg: &Replace) -> Option<RejectReason> {
+        let instrument = self.instruments.get(symbol)?;
+        let price = msg.new_price.filter(|&p| p > 0).and_then(|p| instrument.check_price(p));
+        let quantity = msg.new_quantity.filter(|&q| q > 0).and_then(|q| instrument.check_quantity(q));
+        price.or(quantity)
+    }
+
+    /// Reject reason for a new order or replace in a state that doesn't
+    /// accept them.
+    fn not_trading_reason(state: TradingState) -> RejectReason {
+        match state {
+            TradingState::Closed => RejectReason::MarketClosed,
+            _ => RejectReason::TradingHalted,
+        }
+    }
+
+    /// A symbol must be non-empty printable ASCII without commas or spaces
+    /// (so it survives the CSV protocol unchanged).
+    fn is_valid_symbol(symbol: &str) -> bool {
+        !symbol.is_empty() && symbol.bytes().all(|b| b.is_ascii_graphic() && b != b',')
+    }
+
+    /// Drop `order_to_symbol` entries for orders in `symbol`'s book that
+    /// traded, expired or were self-trade cancelled in `outputs` and are
+    /// no longer live (fully filled, or a triggered stop that did not
+    /// rest).
+    fn forget_filled_orders(&mut self, symbol: &str, outputs: &[OutputMessage]) {
+        let book = match self.order_books.get(symbol) {
+            Some(book) => book,
+            None => return,
+        };
+
+        for out in outputs {
+            let keys = match out {
+                OutputMessage::Trade(t) => vec![
+                    (t.user_id_buy, t.user_order_id_buy),
+                    (t.user_id_sell, t.user_order_id_sell),
+                ],
+                OutputMessage::Expired(e) => vec![(e.user_id, e.user_order_id)],
+                OutputMessage::SelfTradeCancel(c) => vec![(c.user_id, c.user_order_id)],
+                _ => continue,
+            };
+            for key in keys {
+                if !book.contains_order(key.0, key.1) {
+                    self.order_to_symbol.remove(&key);
+                }
+            }
+        }
+    }
+
     /// Get an existing order book for a symbol or create one if it doesn't exist.
     fn get_or_create_order_book(&mut self, symbol: &str) -> &mut OrderBook {
+        let depth_updates = self.depth_updates;
+        let book_events = self.book_events;
+        let call_phase = self.trading_state(symbol).is_call_phase();
+        let tick_size = self.instruments.tick_size(symbol);
+        let market_protection_ticks = self.instruments.market_protection_ticks(symbol);
+        let band_bps = self.volatility_bands.map_or(0, |b| b.band_bps);
+        let clock = &self.clock;
         self.order_books
             .entry(symbol.to_string())
-            .or_insert_with(|| OrderBook::new(symbol))
+            .or_insert_with(|| {
+                let mut book = OrderBook::with_clock(symbol, Arc::clone(clock));
+                book.set_depth_updates(depth_updates);
+                book.set_book_events(book_events);
+                book.set_tick_size(tick_size);
+                book.set_market_protection(market_protection_ticks);
+                book.set_price_band(band_bps);
+                if call_phase {
+                    // A book (re)created during a call phase starts in
+                    // its auction.
+                    book.start_auction();
+                }
+                book
+            })
     }
 
     /// For tests or admin queries: get immutable access to a book by symbol.
@@ -208,10 +1103,30 @@ impl MatchingEngine {
         self.order_books.get(symbol)
     }
 
+    /// Returns `true` if `(user_id, user_order_id)` is a live order:
+    /// resting, a pending stop, or queued while its symbol is halted.
+    pub fn has_order(&self, user_id: u32, user_order_id: u32) -> bool {
+        self.order_to_symbol.contains_key(&(user_id, user_order_id))
+    }
+
+    /// Look up a resting order by `(user_id, user_order_id)`.
+    pub fn get_order(&self, user_id: u32, user_order_id: u32) -> Option<&Order> {
+        let symbol = self.order_to_symbol.get(&(user_id, user_order_id))?;
+        self.order_books.get(symbol)?.get_order(user_id, user_order_id)
+    }
+
+    /// Look up an order queued while its symbol doesn't accept orders.
+    pub fn get_queued_order(&self, user_id: u32, user_order_id: u32) -> Option<&NewOrder> {
+        let symbol = self.order_to_symbol.get(&(user_id, user_order_id))?;
+        self.queued_orders
+            .get(symbol)?
+            .iter()
+            .find(|o| (o.user_id, o.user_order_id) == (user_id, user_order_id))
+    }
+
     /// For tests or admin queries: number of symbols currently tracked.
     pub fn num_symbols(&self) -> usize {
         self.order_books.len()
     }
-
 }
 
@@ -10,8 +10,19 @@
 //! Note: Binary / CSV encoders live in the `engine-protocol` crate;
 //! this module is purely logical.
 
+use std::fmt;
+
+use crate::auction::AuctionAction;
+use crate::depth::DepthEvent;
+use crate::error::RejectReason;
+use crate::execution::{ExecType, OrdStatus};
+use crate::instrument::Instrument;
+use crate::order_flags::OrderFlags;
 use crate::order_type::OrderType;
+use crate::self_trade_prevention::SelfTradePrevention;
 use crate::side::Side;
+use crate::time_in_force::TimeInForce;
+use crate::trading_state::TradingState;
 
 /// A high-level request into the matching engine.
 ///
@@ -20,17 +31,58 @@ use crate::side::Side;
 /// - Strongly-typed structs instead of `std::variant`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InputMessage {
-    /// New order: market (price = 0) or limit (price > 0).
+    /// New order: market (price = 0) or limit (price > 0), optionally
+    /// held as a stop order until `stop_price` trades.
     NewOrder(NewOrder),
 
     /// Cancel an existing order by `(user_id, user_order_id)`.
     Cancel(Cancel),
 
-    /// Flush all order books and internal state.
-    Flush,
+    /// Amend price and/or quantity of a resting order (cancel-replace).
+    Replace(Replace),
+
+    /// Admin: flush all order books and internal state.
+    Flush(FlushCommand),
 
     /// Query the current top-of-book for a given symbol.
     QueryTopOfBook(TopOfBookQuery),
+
+    /// Query the top `levels` price levels per side for a symbol.
+    QueryDepth(DepthQuery),
+
+    /// Admin: start or uncross a call auction for a symbol.
+    Auction(AuctionCommand),
+
+    /// Admin: move a symbol to another trading state.
+    SetTradingState(SetTradingState),
+
+    /// Query a symbol's instrument definition.
+    QueryInstrument(InstrumentQuery),
+
+    /// Admin: replace a user's pre-trade risk limits. Enforced by the
+    /// server in front of the engine; the engine itself ignores it.
+    SetRiskLimits(SetRiskLimits),
+
+    /// Session: options for the connection it arrives on. Handled by the
+    /// server; the engine ignores it.
+    Logon(Logon),
+
+    /// Session: keeps an idle connection alive. Handled by the server; the
+    /// engine ignores it.
+    Heartbeat,
+
+    /// Session: end the session (and close the connection). Handled by
+    /// the server; the engine ignores it.
+    Logout,
+
+    /// Cancel every order of a user matching the filters.
+    MassCancel(MassCancel),
+
+    /// Admin: block a user (or every user) from entering orders.
+    KillSwitch(KillSwitch),
+
+    /// Admin: lift a `KillSwitch`.
+    EnableTrading(EnableTrading),
 }
 
 /// A high-level event emitted by the matching engine.
@@ -45,11 +97,60 @@ pub enum OutputMessage {
     /// Acknowledgement of a cancel request.
     CancelAck(CancelAck),
 
+    /// Acknowledgement of a replace request.
+    ReplaceAck(ReplaceAck),
+
+    /// A request that was refused; see [`RejectReason`].
+    Reject(Reject),
+
     /// Trade event between a buyer and a seller.
     Trade(Trade),
 
+    /// Anonymized trade print for the public market-data feed.
+    ///
+    /// Not emitted by the engine itself; the server derives it from
+    /// [`Trade`] (see [`PublicTrade::from_trade`]) so counterparties stay
+    /// private.
+    PublicTrade(PublicTrade),
+
     /// Top-of-book change or snapshot.
     TopOfBook(TopOfBook),
+
+    /// Market-by-price level changes, or a depth snapshot.
+    DepthUpdate(DepthUpdate),
+
+    /// Unfilled quantity of an IOC/FOK order was expired instead of resting.
+    Expired(Expired),
+
+    /// A stop order's stop price was reached; it now enters matching.
+    Triggered(Triggered),
+
+    /// Quantity cancelled by self-trade prevention instead of trading.
+    SelfTradeCancel(SelfTradeCancel),
+
+    /// Indicative price and volume of a book's call auction.
+    IndicativeUncross(IndicativeUncross),
+
+    /// A symbol's trading state changed.
+    TradingStatus(TradingStatus),
+
+    /// Instrument definition (the answer to a `QueryInstrument`).
+    InstrumentDefinition(Instrument),
+
+    /// A symbol's volatility band was breached; trading is interrupted.
+    VolatilityInterruption(VolatilityInterruption),
+
+    /// Execution report of an order (only with execution reports on).
+    ExecutionReport(ExecutionReport),
+
+    /// Summary of a `MassCancel`, after its `CancelAck`s.
+    MassCancelAck(MassCancelAck),
+
+    /// A user's trading was disabled or enabled again.
+    UserTradingStatus(UserTradingStatus),
+
+    /// A session logged on or off. Sent by the server, not the engine.
+    SessionStatus(SessionStatus),
 }
 
 /// New order message (input).
@@ -76,16 +177,38 @@ pub struct NewOrder {
 
     /// User-local order identifier (for canceling later).
     pub user_order_id: u32,
+
+    /// What to do with quantity that doesn't fill immediately.
+    pub time_in_force: TimeInForce,
+
+    /// Stop (trigger) price in ticks.
+    /// - `0` => not a stop order
+    /// - `>0` => held until the last trade price reaches it (at or
+    ///   above for a buy, at or below for a sell)
+    pub stop_price: u32,
+
+    /// Iceberg display quantity.
+    /// - `0` => the whole order is displayed
+    /// - `>0` => only this much is shown at a time; each slice that fills
+    ///   is refilled from the reserve and rejoins the back of its level
+    pub display_qty: u32,
+
+    /// Post-only / hidden flags (see [`OrderFlags`]).
+    pub flags: OrderFlags,
+
+    /// What to do instead of trading with the same user's resting orders.
+    pub self_trade_prevention: SelfTradePrevention,
 }
 
 impl NewOrder {
-    /// Helper: returns the corresponding `OrderType`
-    /// (market vs limit) based on price.
+    /// Helper: returns the corresponding `OrderType` based on price
+    /// (market vs limit) and stop price.
     pub fn order_type(&self) -> OrderType {
-        if self.price == 0 {
-            OrderType::Market
-        } else {
-            OrderType::Limit
+        match (self.stop_price, self.price) {
+            (0, 0) => OrderType::Market,
+            (0, _) => OrderType::Limit,
+            (_, 0) => OrderType::StopMarket,
+            (_, _) => OrderType::StopLimit,
         }
     }
 }
@@ -99,6 +222,61 @@ pub struct Cancel {
     pub user_order_id: u32,
 }
 
+/// Mass cancel message (input).
+///
+/// Cancels every live order of `user_id` (resting, pending stop or
+/// queued) that passes all the filters given. The price range is
+/// inclusive and applies to the limit price, or to the stop price of a
+/// stop order not yet triggered; queued market orders have neither and
+/// only pass when neither bound is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MassCancel {
+    pub user_id: u32,
+
+    /// Only this symbol (`None` = every symbol).
+    pub symbol: Option<String>,
+
+    /// Only this side (`None` = both).
+    pub side: Option<Side>,
+
+    /// Lowest limit price (`None` = no lower bound).
+    pub min_price: Option<u32>,
+
+    /// Highest limit price (`None` = no upper bound).
+    pub max_price: Option<u32>,
+}
+
+impl MassCancel {
+    /// Returns `true` if an order with these fields is to be cancelled
+    /// (the symbol is checked by the caller).
+    pub fn matches(&self, user_id: u32, side: Side, price: u32) -> bool {
+        let has_range = self.min_price.is_some() || self.max_price.is_some();
+        user_id == self.user_id
+            && self.side.is_none_or(|s| s == side)
+            && !(has_range && price == 0)
+            && self.min_price.is_none_or(|min| price >= min)
+            && self.max_price.is_none_or(|max| price <= max)
+    }
+}
+
+/// Cancel-replace message (input).
+///
+/// `None` leaves that attribute unchanged. `new_quantity` is the new
+/// total order quantity (including anything already filled), as in FIX
+/// `OrderCancelReplaceRequest`.
+///
+/// Priority rules:
+/// - quantity down, same price: keeps time priority;
+/// - price change or quantity up: loses priority (re-queued at the back,
+///   and may trade if the new price crosses).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replace {
+    pub user_id: u32,
+    pub user_order_id: u32,
+    pub new_price: Option<u32>,
+    pub new_quantity: Option<u32>,
+}
+
 /// Query top-of-book message (input).
 ///
 /// NEW compared to the C++ version:
@@ -109,6 +287,206 @@ pub struct TopOfBookQuery {
     pub symbol: String,
 }
 
+/// Query depth message (input).
+///
+/// Answered with a snapshot [`DepthUpdate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthQuery {
+    pub symbol: String,
+
+    /// Maximum number of levels per side; `0` means all levels.
+    pub levels: u32,
+}
+
+/// Query instrument message (input).
+///
+/// Answered with an `InstrumentDefinition`, or a `Reject` if the symbol
+/// isn't listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstrumentQuery {
+    pub symbol: String,
+}
+
+/// Flush of every book (admin input).
+///
+/// `admin_token` as for [`KillSwitch`].
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct FlushCommand {
+    pub admin_token: String,
+}
+
+impl fmt::Debug for FlushCommand {
+    /// Leaves the admin token out of logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlushCommand").finish_non_exhaustive()
+    }
+}
+
+/// Call auction command (admin input).
+///
+/// `Start` puts the book into its call phase (creating it if needed);
+/// `Uncross` executes the auction and resumes continuous matching. See
+/// [`crate::auction`]. `admin_token` as for [`KillSwitch`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct AuctionCommand {
+    pub symbol: String,
+    pub action: AuctionAction,
+    pub admin_token: String,
+}
+
+impl fmt::Debug for AuctionCommand {
+    /// Leaves the admin token out of logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuctionCommand")
+            .field("symbol", &self.symbol)
+            .field("action", &self.action)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Trading state change (admin input).
+///
+/// See [`crate::trading_state`] for what each state allows.
+/// `admin_token` as for [`KillSwitch`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct SetTradingState {
+    pub symbol: String,
+    pub state: TradingState,
+    pub admin_token: String,
+}
+
+impl fmt::Debug for SetTradingState {
+    /// Leaves the admin token out of logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetTradingState")
+            .field("symbol", &self.symbol)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Pre-trade risk limits of one user.
+///
+/// For every limit, `0` means "no limit". Notional and exposure are in
+/// price ticks times quantity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RiskLimits {
+    /// Largest quantity of a single order.
+    pub max_order_qty: u32,
+
+    /// Largest notional (price x quantity) of a single order.
+    pub max_notional: u64,
+
+    /// Most orders the user may have open at once.
+    pub max_open_orders: u32,
+
+    /// Largest absolute net position per symbol.
+    pub max_position: u64,
+
+    /// Largest gross exposure across symbols: open positions at the last
+    /// trade price plus open orders at their price.
+    pub max_gross_exposure: u64,
+}
+
+/// Session logon (session input).
+///
+/// Sets the options of the connection it is sent on; a later logon
+/// replaces them. When the server has a credentials file, `username`
+/// and `secret` must match an entry, and bind the session to that
+/// entry's user ids.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Logon {
+    /// Cancel the orders this session entered when it disconnects.
+    pub cancel_on_disconnect: bool,
+
+    /// Drop the session if nothing (not even a `Heartbeat`) arrives for
+    /// this many seconds (`0` = never).
+    pub heartbeat_secs: u32,
+
+    /// Credentials (empty when the server doesn't check them).
+    pub username: String,
+    pub secret: String,
+}
+
+impl fmt::Debug for Logon {
+    /// Leaves the secret out of logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logon")
+            .field("cancel_on_disconnect", &self.cancel_on_disconnect)
+            .field("heartbeat_secs", &self.heartbeat_secs)
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Risk limit update (admin input).
+///
+/// `user_id == 0` sets the default limits of every user without limits
+/// of their own.
+///
+/// `admin_token` authenticates the update to the server, which clears it
+/// once checked (empty for limits loaded from the risk limits file).
+#[derive(Clone, PartialEq, Eq)]
+pub struct SetRiskLimits {
+    pub user_id: u32,
+    pub limits: RiskLimits,
+    pub admin_token: String,
+}
+
+impl fmt::Debug for SetRiskLimits {
+    /// Leaves the admin token out of logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetRiskLimits")
+            .field("user_id", &self.user_id)
+            .field("limits", &self.limits)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Kill switch (admin input).
+///
+/// Blocks new orders and replaces of `user_id` (`0` = every user) until
+/// an `EnableTrading`; cancels still go through. With `cancel_orders`
+/// their live orders are cancelled too, on every symbol.
+///
+/// `admin_token` authenticates the command to the server, which clears
+/// it before the engine (and the journal) sees the message.
+#[derive(Clone, PartialEq, Eq)]
+pub struct KillSwitch {
+    pub user_id: u32,
+    pub cancel_orders: bool,
+    pub admin_token: String,
+}
+
+impl fmt::Debug for KillSwitch {
+    /// Leaves the admin token out of logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KillSwitch")
+            .field("user_id", &self.user_id)
+            .field("cancel_orders", &self.cancel_orders)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Lift a kill switch (admin input).
+///
+/// `user_id == 0` lifts the one for every user; users blocked on their
+/// own stay blocked. `admin_token` as for [`KillSwitch`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct EnableTrading {
+    pub user_id: u32,
+    pub admin_token: String,
+}
+
+impl fmt::Debug for EnableTrading {
+    /// Leaves the admin token out of logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnableTrading")
+            .field("user_id", &self.user_id)
+            .finish_non_exhaustive()
+    }
+}
+
 /// Acknowledgement of a new order (output).
 ///
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -127,6 +505,43 @@ pub struct CancelAck {
     pub symbol: String,
 }
 
+/// Summary of a mass cancel (output), after one `CancelAck` per order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MassCancelAck {
+    pub user_id: u32,
+
+    /// The symbol filter (empty = every symbol).
+    pub symbol: String,
+
+    /// How many orders were cancelled.
+    pub cancelled_count: u32,
+}
+
+/// Acknowledgement of a replace request (output).
+///
+/// Carries the order's price and open quantity after the replace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplaceAck {
+    pub user_id: u32,
+    pub user_order_id: u32,
+    pub symbol: String,
+    pub price: u32,
+    pub remaining_qty: u32,
+}
+
+/// Rejection of a new order, cancel or replace (output).
+///
+/// A rejected request has no effect on the book. `symbol` is empty when
+/// it isn't known (e.g. cancel of an unknown order, or a parse error
+/// where `user_id` / `user_order_id` are also `0`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reject {
+    pub user_id: u32,
+    pub user_order_id: u32,
+    pub symbol: String,
+    pub reason: RejectReason,
+}
+
 /// Trade event (output).
 ///
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -142,6 +557,187 @@ pub struct Trade {
 
     pub price: u32,
     pub quantity: u32,
+
+    /// Side of the incoming (liquidity-taking) order.
+    pub aggressor_side: Side,
+
+    /// Execution time (nanoseconds since epoch) from the engine's clock.
+    pub timestamp_ns: u64,
+}
+
+/// Anonymized trade print (output).
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicTrade {
+    pub symbol: String,
+    pub price: u32,
+    pub quantity: u32,
+    pub aggressor_side: Side,
+    pub timestamp_ns: u64,
+}
+
+impl PublicTrade {
+    /// Strip the counterparties from a [`Trade`].
+    pub fn from_trade(trade: &Trade) -> Self {
+        PublicTrade {
+            symbol: trade.symbol.clone(),
+            price: trade.price,
+            quantity: trade.quantity,
+            aggressor_side: trade.aggressor_side,
+            timestamp_ns: trade.timestamp_ns,
+        }
+    }
+}
+
+/// Expiry of an order's unfilled quantity (output).
+///
+/// Sent for an IOC or market order's remainder after matching (the book
+/// ran out, or a market order reached its protection price or the
+/// volatility band), or for a FOK order that could not be filled in full
+/// (in which case nothing traded and `remaining_qty` is the whole order).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expired {
+    pub user_id: u32,
+    pub user_order_id: u32,
+    pub symbol: String,
+
+    /// Quantity that was not filled and has been removed.
+    pub remaining_qty: u32,
+}
+
+/// A stop order was triggered (output).
+///
+/// Sent when the last trade price reaches the order's stop price; the
+/// order then matches as a market (stop price only) or limit order, so
+/// any trades, expiry or resting follow this event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Triggered {
+    pub user_id: u32,
+    pub user_order_id: u32,
+    pub symbol: String,
+    pub stop_price: u32,
+
+    /// Price of the trade that triggered the order.
+    pub last_trade_price: u32,
+}
+
+/// Quantity of an order cancelled by self-trade prevention (output).
+///
+/// Sent for each order touched by a prevented match, incoming or
+/// resting, in place of the `Trade` that would have happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTradeCancel {
+    pub user_id: u32,
+    pub user_order_id: u32,
+    pub symbol: String,
+
+    /// Quantity taken off the order.
+    pub cancelled_qty: u32,
+
+    /// Quantity the order still has; `0` means it is gone.
+    pub remaining_qty: u32,
+}
+
+/// Indicative uncross of a call auction (output).
+///
+/// Broadcast when an auction starts and whenever a change to the book
+/// moves the indicative. `price == 0` means the book doesn't cross (and
+/// `volume` is `0`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndicativeUncross {
+    pub symbol: String,
+    pub price: u32,
+    pub volume: u32,
+
+    /// Quantity that would be left unmatched at `price`.
+    pub imbalance_qty: u32,
+
+    /// Side the unmatched quantity is on (`Buy` when balanced).
+    pub imbalance_side: Side,
+}
+
+/// Trading state announcement (output).
+///
+/// Broadcast whenever a symbol's state changes, before anything the
+/// change causes (indicative, uncross trades, released orders).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradingStatus {
+    pub symbol: String,
+    pub state: TradingState,
+}
+
+/// User trading status (output), after a `KillSwitch` or
+/// `EnableTrading`.
+///
+/// `user_id == 0` is about every user. `enabled` is whether the user
+/// can trade now, so enabling a user while every user is blocked still
+/// reports `false`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserTradingStatus {
+    pub user_id: u32,
+    pub enabled: bool,
+}
+
+/// Session status (output), the server's answer to a `Logon` (and to
+/// a `Logout`, just before it closes the connection).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStatus {
+    /// The username logged on with (empty without credentials).
+    pub username: String,
+    pub logged_on: bool,
+}
+
+/// Execution report (output), modelled on FIX `ExecutionReport`.
+///
+/// Follows each output that changes the order (see
+/// [`crate::execution`]), with the order's state after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionReport {
+    pub user_id: u32,
+    pub user_order_id: u32,
+
+    /// May be empty (a rejected order whose symbol is not known).
+    pub symbol: String,
+
+    /// Engine-wide report id, counting up from `1`.
+    pub exec_id: u64,
+
+    pub exec_type: ExecType,
+    pub order_status: OrdStatus,
+
+    /// Quantity and price of this fill (`0` unless `exec_type` is
+    /// `Trade`).
+    pub last_qty: u32,
+    pub last_price: u32,
+
+    /// Total quantity filled so far.
+    pub cum_qty: u32,
+
+    /// Quantity still open (`0` once the order is done).
+    pub leaves_qty: u32,
+
+    /// Engine time of the event (nanoseconds since epoch).
+    pub timestamp_ns: u64,
+}
+
+/// Volatility interruption (output).
+///
+/// Broadcast when a match would have printed outside the symbol's
+/// volatility band (see [`crate::volatility`]), before the
+/// `TradingStatus` of the auction or halt that follows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolatilityInterruption {
+    pub symbol: String,
+
+    /// Price the band was anchored on (the last trade before the request).
+    pub reference_price: u32,
+
+    /// Price of the match that was stopped.
+    pub trigger_price: u32,
+
+    /// Engine time (nanoseconds since epoch) the symbol goes back to
+    /// continuous trading.
+    pub resume_ns: u64,
 }
 
 /// Top-of-book event (output).
@@ -165,6 +761,20 @@ pub struct TopOfBook {
     pub eliminated: bool,
 }
 
+/// Market-by-price depth event (output).
+///
+/// With `snapshot == false`, `events` are the level changes caused by a
+/// single request, in bid-then-ask, price order. With `snapshot == true`
+/// (the answer to a `QueryDepth`), the client should discard its ladder
+/// for `symbol` and rebuild it from `events`, which are all `Add`s, best
+/// price first per side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthUpdate {
+    pub symbol: String,
+    pub snapshot: bool,
+    pub events: Vec<DepthEvent>,
+}
+
 // -----------------------------------------------------------------------------
 // Convenience constructors (similar spirit to your C++ static helpers)
 // -----------------------------------------------------------------------------
@@ -188,7 +798,89 @@ impl OutputMessage {
         })
     }
 
+    /// Convenience constructor for a ReplaceAck event.
+    pub fn replace_ack(
+        user_id: u32,
+        user_order_id: u32,
+        symbol: impl Into<String>,
+        price: u32,
+        remaining_qty: u32,
+    ) -> Self {
+        OutputMessage::ReplaceAck(ReplaceAck {
+            user_id,
+            user_order_id,
+            symbol: symbol.into(),
+            price,
+            remaining_qty,
+        })
+    }
+
+    /// Convenience constructor for a Reject event.
+    pub fn reject(
+        user_id: u32,
+        user_order_id: u32,
+        symbol: impl Into<String>,
+        reason: RejectReason,
+    ) -> Self {
+        OutputMessage::Reject(Reject {
+            user_id,
+            user_order_id,
+            symbol: symbol.into(),
+            reason,
+        })
+    }
+
+    /// Convenience constructor for an Expired event.
+    pub fn expired(
+        user_id: u32,
+        user_order_id: u32,
+        symbol: impl Into<String>,
+        remaining_qty: u32,
+    ) -> Self {
+        OutputMessage::Expired(Expired {
+            user_id,
+            user_order_id,
+            symbol: symbol.into(),
+            remaining_qty,
+        })
+    }
+
+    /// Convenience constructor for a Triggered event.
+    pub fn triggered(
+        user_id: u32,
+        user_order_id: u32,
+        symbol: impl Into<String>,
+        stop_price: u32,
+        last_trade_price: u32,
+    ) -> Self {
+        OutputMessage::Triggered(Triggered {
+            user_id,
+            user_order_id,
+            symbol: symbol.into(),
+            stop_price,
+            last_trade_price,
+        })
+    }
+
+    /// Convenience constructor for a SelfTradeCancel event.
+    pub fn self_trade_cancel(
+        user_id: u32,
+        user_order_id: u32,
+        symbol: impl Into<String>,
+        cancelled_qty: u32,
+        remaining_qty: u32,
+    ) -> Self {
+        OutputMessage::SelfTradeCancel(SelfTradeCancel {
+            user_id,
+            user_order_id,
+            symbol: symbol.into(),
+            cancelled_qty,
+            remaining_qty,
+        })
+    }
+
     /// Convenience constructor for a Trade event.
+    #[allow(clippy::too_many_arguments)]
     pub fn trade(
         symbol: impl Into<String>,
         user_id_buy: u32,
@@ -197,6 +889,8 @@ impl OutputMessage {
         user_order_id_sell: u32,
         price: u32,
         quantity: u32,
+        aggressor_side: Side,
+        timestamp_ns: u64,
     ) -> Self {
         OutputMessage::Trade(Trade {
             symbol: symbol.into(),
@@ -206,6 +900,8 @@ impl OutputMessage {
             user_order_id_sell,
             price,
             quantity,
+            aggressor_side,
+            timestamp_ns,
         })
     }
 
@@ -3,17 +3,24 @@
 //! Mirrors your C++ `Order` struct with:
 //! - `user_id`, `user_order_id`, `symbol`
 //! - `price`, `quantity`, `remaining_qty`
-//! - `side`, `type` (market vs limit)
-//! - `timestamp` in nanoseconds since epoch
+//! - `side`, `type` (market vs limit, or a stop variant)
+//! - `stop_price` for stop orders
+//! - `display_qty` / `visible_qty` for iceberg orders
+//! - `flags` (post-only, hidden)
+//! - `self_trade_prevention` mode
+//! - `time_in_force` (Day/GTC, IOC, FOK)
+//! - `timestamp` in nanoseconds since epoch, taken from the book's `Clock`
+//! - `exchange_order_id`, the public id assigned when it rests
 //!
 //! This type is **not** exposed over the wire; it's purely internal
 //! to the engine-core crate.
 
-use std::time::{SystemTime, UNIX_EPOCH};
-
 use crate::messages::NewOrder;
+use crate::order_flags::OrderFlags;
 use crate::order_type::OrderType;
+use crate::self_trade_prevention::SelfTradePrevention;
 use crate::side::Side;
+use crate::time_in_force::TimeInForce;
 
 /// A single order in the book.
 ///
@@ -44,9 +51,22 @@ pub struct Order {
     pub remaining_qty: u32, // remaining unfilled quantity
     pub side: Side,
     pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    pub stop_price: u32, // 0 = not a stop order
+
+    // Iceberg orders: only a slice of `display_qty` is shown at a time.
+    pub display_qty: u32, // 0 = fully displayed
+    pub visible_qty: u32, // what is left of the current slice (iceberg only)
+
+    pub flags: OrderFlags,
+    pub self_trade_prevention: SelfTradePrevention,
 
     // Time priority (nanoseconds since epoch)
     pub timestamp_ns: u64,
+
+    /// Public id assigned by the book when the order rests (`0` until
+    /// then); a replace that loses priority gets a new one.
+    pub exchange_order_id: u64,
 }
 
 impl Order {
@@ -77,15 +97,53 @@ impl Order {
             remaining_qty: msg.quantity,
             side: msg.side,
             order_type,
+            time_in_force: msg.time_in_force,
+            stop_price: msg.stop_price,
+            display_qty: msg.display_qty,
+            visible_qty: 0,
+            flags: msg.flags,
+            self_trade_prevention: msg.self_trade_prevention,
             timestamp_ns,
+            exchange_order_id: 0,
         }
     }
 
-    /// Helper to construct from a `NewOrder` using the current time
-    /// as the timestamp (nanoseconds since epoch).
-    pub fn from_new_order_now(msg: &NewOrder) -> Self {
-        let ts = Self::current_timestamp_ns();
-        Self::from_new_order(msg, ts)
+    /// Returns `true` for an iceberg (reserve) order.
+    pub fn is_iceberg(&self) -> bool {
+        self.display_qty > 0
+    }
+
+    /// Returns `true` for a hidden order.
+    pub fn is_hidden(&self) -> bool {
+        self.flags.is_hidden()
+    }
+
+    /// Quantity that can trade before the order has to be refreshed: the
+    /// current slice of an iceberg order, otherwise everything that
+    /// remains.
+    pub fn tradable_qty(&self) -> u32 {
+        if self.is_iceberg() {
+            self.visible_qty.min(self.remaining_qty)
+        } else {
+            self.remaining_qty
+        }
+    }
+
+    /// Quantity shown in the book: nothing for a hidden order, otherwise
+    /// the tradable quantity.
+    pub fn displayed_qty(&self) -> u32 {
+        if self.is_hidden() {
+            0
+        } else {
+            self.tradable_qty()
+        }
+    }
+
+    /// Show a fresh slice of an iceberg order (no-op otherwise).
+    pub fn refill_display(&mut self) {
+        if self.is_iceberg() {
+            self.visible_qty = self.display_qty.min(self.remaining_qty);
+        }
     }
 
     /// Returns `true` if the order is fully filled.
@@ -106,23 +164,13 @@ impl Order {
     ///     return filled;
     /// }
     /// ```
+    ///
+    /// An iceberg order's visible slice shrinks by the same amount.
     pub fn fill(&mut self, qty: u32) -> u32 {
         let filled = qty.min(self.remaining_qty);
         self.remaining_qty -= filled;
+        self.visible_qty = self.visible_qty.saturating_sub(filled);
         filled
     }
-
-    /// Get the current timestamp in nanoseconds since the Unix epoch.
-    ///
-    /// This is analogous to your C++ `getCurrentTimestamp()` which uses
-    /// `std::chrono::high_resolution_clock`.
-    pub fn current_timestamp_ns() -> u64 {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default();
-        now.as_secs()
-            .saturating_mul(1_000_000_000)
-            .saturating_add(now.subsec_nanos() as u64)
-    }
 }
 
@@ -1,79 +1,308 @@
 //! Single-symbol order book with price-time priority.
 //!
-//!" This is the Rust analogue of your C++ `OrderBook`:
+//! This is the Rust analogue of your C++ `OrderBook`:
 //! - One instance per symbol.
 //! - Bids: descending by price (best = highest).
 //! - Asks: ascending by price (best = lowest).
 //! - FIFO (time-priority) within each price level.
 //!
-//! Differences vs the C++ implementation:
-//! - For simplicity and safety, cancellation currently does a linear
-//!   search over the relevant side instead of storing raw iterators
-//!   (which are tricky to model safely in Rust without arenas/unsafe).
-//!   Semantics are the same; complexity is slightly higher for cancels.
+//! Like the C++ book (which keeps `std::list` iterators in a hash map),
+//! cancels are O(1): every resting order lives in a stable arena slot,
+//! price levels are intrusive FIFO lists over those slots, and an index
+//! maps `(user_id, user_order_id)` to the slot (see `price_level`).
+//!
+//! Full depth is available via [`OrderBook::depth`]. When depth updates
+//! are enabled, every operation that changes a price level also emits a
+//! `DepthUpdate` (after any top-of-book events) listing the levels that
+//! were added, changed or deleted.
+//!
+//! Iceberg orders show only their display quantity; when a displayed
+//! slice fills, the next one is taken from the reserve and joins the
+//! back of its level with a new exchange order id. Hidden orders trade
+//! in normal time priority but are not shown at all: not in top-of-book,
+//! depth or the L3 feed. Post-only orders are checked against the best
+//! opposite price (hidden orders included) before they are acked.
+//!
+//! Each order that rests is given a public exchange order id. When book
+//! events are enabled, order-level changes are also recorded as
+//! [`OrderBookEvent`]s, drained with [`OrderBook::take_book_events`].
+//!
+//! Stop orders wait in a trigger book (see `stop_book`) until the last
+//! trade price reaches their stop price. Triggers are checked after
+//! every operation that traded; a triggered order is matched like a new
+//! one, and its own trades can trigger further stops.
+//!
+//! A book can also be put into a call auction (see [`crate::auction`]):
+//! orders then rest without matching, stops are held, and every change
+//! that moves the indicative uncross publishes an `IndicativeUncross`
+//! (after any top-of-book events). [`OrderBook::uncross`] executes the
+//! auction and returns the book to continuous matching.
+//!
+//! With a volatility band set, a request may only trade within the band
+//! around the last trade price as it stood when the request arrived. A
+//! match that would print outside it stops matching and puts the book
+//! into a call auction (see [`OrderBook::take_band_breach`]).
+//!
+//! With market order protection set, a market order only sweeps a fixed
+//! number of ticks through the touch it meets on arrival; what it can't
+//! fill there is expired, like any other unfilled market remainder.
+//!
+//! Order priority and trade timestamps come from the book's [`Clock`]
+//! (wall time unless one is supplied with [`OrderBook::with_clock`]).
 
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
-use crate::messages::{NewOrder, OutputMessage};
+use crate::auction::{self, Uncross};
+use crate::book_event::OrderBookEvent;
+use crate::clock::{Clock, SystemClock};
+use crate::depth::{BookDepth, DepthAction, DepthEvent, DepthLevel};
+use crate::error::RejectReason;
+use crate::messages::{DepthUpdate, IndicativeUncross, MassCancel, NewOrder, OutputMessage};
 use crate::order::Order;
+use crate::order_flags::OrderFlags;
 use crate::order_type::OrderType;
+use crate::price_level::{OrderArena, PriceLevel};
+use crate::self_trade_prevention::SelfTradePrevention;
 use crate::side::Side;
+use crate::snapshot::BookState;
+use crate::stop_book::StopBook;
+use crate::time_in_force::TimeInForce;
 use crate::top_of_book::TopOfBookSnapshot;
+use crate::user_index::UserIndex;
 
 /// Single-symbol order book.
 #[derive(Debug)]
 pub struct OrderBook {
     symbol: String,
 
-    /// Bids: price -> FIFO queue of orders at that price.
+    /// Source of order priority and trade timestamps.
+    clock: Arc<dyn Clock>,
+
+    /// Bids: price -> FIFO level of orders at that price.
     ///
     /// We use `BTreeMap` so keys are sorted ascending; we treat the
     /// highest key as best bid.
-    bids: BTreeMap<u32, VecDeque<Order>>,
+    bids: BTreeMap<u32, PriceLevel>,
 
-    /// Asks: price -> FIFO queue of orders at that price.
+    /// Asks: price -> FIFO level of orders at that price.
     ///
     /// We use `BTreeMap` so keys are sorted ascending; we treat the
     /// lowest key as best ask.
-    asks: BTreeMap<u32, VecDeque<Order>>,
+    asks: BTreeMap<u32, PriceLevel>,
+
+    /// Storage for every resting order; levels link through its slots.
+    orders: OrderArena,
+
+    /// `(user_id, user_order_id)` -> arena slot of the resting order.
+    ///
+    /// This mirrors the C++ `order_lookup_` iterator map, keyed by user
+    /// first so a mass cancel only visits that user's orders.
+    order_index: UserIndex<usize>,
+
+    /// Pending stop orders.
+    stops: StopBook,
+
+    /// Price of the most recent trade (`0` before the first one); drives
+    /// stop triggers.
+    last_trade_price: u32,
 
     /// Cache of previous top-of-book for change detection.
     prev_best_bid_price: u32,
     prev_best_bid_qty: u32,
     prev_best_ask_price: u32,
     prev_best_ask_qty: u32,
+
+    /// Emit `DepthUpdate` events after each operation.
+    depth_updates: bool,
+
+    /// Levels touched by the current operation -> quantity before it.
+    /// Only populated while `depth_updates` is on.
+    depth_dirty: BTreeMap<(u8, u32), u32>,
+
+    /// Next exchange order id to hand out (ids start at 1).
+    next_exchange_order_id: u64,
+
+    /// Record `OrderBookEvent`s for the L3 feed.
+    book_events: bool,
+
+    /// L3 events not yet taken by the caller.
+    pending_book_events: Vec<OrderBookEvent>,
+
+    /// In a call auction: orders rest without matching.
+    in_auction: bool,
+
+    /// Indicative uncross last published in the current auction.
+    published_indicative: Uncross,
+
+    /// Price increment, used when repricing post-only orders.
+    tick_size: u32,
+
+    /// Volatility band half-width in basis points of the reference price
+    /// (`0` = no band).
+    band_bps: u32,
+
+    /// Reference price of the band for the current request: the last
+    /// trade price when it arrived.
+    band_reference: u32,
+
+    /// `(reference, price)` of a match the band stopped, until taken.
+    band_breach: Option<(u32, u32)>,
+
+    /// How many ticks through the touch a market order may trade (`0` =
+    /// no limit).
+    market_protection_ticks: u32,
 }
 
 impl OrderBook {
-    /// Create a new order book for the given symbol.
+    /// Create a new order book for the given symbol, timestamped with
+    /// the system clock.
     pub fn new(symbol: impl Into<String>) -> Self {
+        Self::with_clock(symbol, Arc::new(SystemClock))
+    }
+
+    /// Create a new order book that reads time from `clock`.
+    pub fn with_clock(symbol: impl Into<String>, clock: Arc<dyn Clock>) -> Self {
         OrderBook {
             symbol: symbol.into(),
+            clock,
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
+            orders: OrderArena::default(),
+            order_index: UserIndex::default(),
+            stops: StopBook::default(),
+            last_trade_price: 0,
             prev_best_bid_price: 0,
             prev_best_bid_qty: 0,
             prev_best_ask_price: 0,
             prev_best_ask_qty: 0,
+            depth_updates: false,
+            depth_dirty: BTreeMap::new(),
+            next_exchange_order_id: 1,
+            book_events: false,
+            pending_book_events: Vec::new(),
+            in_auction: false,
+            published_indicative: Uncross::NONE,
+            tick_size: 1,
+            band_bps: 0,
+            band_reference: 0,
+            band_breach: None,
+            market_protection_ticks: 0,
         }
     }
 
+    /// Turn per-operation `DepthUpdate` events on or off (default off, so
+    /// the output stream matches the C++ engine).
+    pub fn set_depth_updates(&mut self, enabled: bool) {
+        self.depth_updates = enabled;
+        self.depth_dirty.clear();
+    }
+
+    /// Turn recording of L3 [`OrderBookEvent`]s on or off (default off).
+    pub fn set_book_events(&mut self, enabled: bool) {
+        self.book_events = enabled;
+        self.pending_book_events.clear();
+    }
+
+    /// Set the instrument's tick size (default `1`): a repriced post-only
+    /// order rests one tick behind the opposite best.
+    pub fn set_tick_size(&mut self, tick_size: u32) {
+        self.tick_size = tick_size.max(1);
+    }
+
+    /// Set the volatility band half-width in basis points of the last
+    /// trade price (`0`, the default, turns it off).
+    pub fn set_price_band(&mut self, band_bps: u32) {
+        self.band_bps = band_bps;
+    }
+
+    /// Set how many ticks through the touch a market order may trade
+    /// (`0`, the default, lets it sweep the whole book).
+    pub fn set_market_protection(&mut self, ticks: u32) {
+        self.market_protection_ticks = ticks;
+    }
+
+    /// Take the band breach of the last request, if its matching was
+    /// stopped by the volatility band: `(reference_price, price)`, where
+    /// `price` is the level it would have traded at. The book is then in
+    /// a call auction.
+    pub fn take_band_breach(&mut self) -> Option<(u32, u32)> {
+        self.band_breach.take()
+    }
+
+    /// Replace the clock used for new orders, replaces and trades.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Take the L3 events recorded since the last call, oldest first.
+    pub fn take_book_events(&mut self) -> Vec<OrderBookEvent> {
+        std::mem::take(&mut self.pending_book_events)
+    }
+
     /// Returns the symbol of this book.
     pub fn symbol(&self) -> &str {
         &self.symbol
     }
 
     /// Process a new order, returning output messages:
+    /// - Reject (zero quantity, duplicate live id, market order with no
+    ///   liquidity, bad flags, post-only order that would trade) and
+    ///   nothing else, or:
     /// - Ack
-    /// - Trades
+    /// - Triggered, if it is a stop order whose stop price has already
+    ///   traded (otherwise a stop order is held and nothing else follows)
+    /// - Trades (or SelfTradeCancels, see `match_order`)
+    /// - Expired (IOC or market remainder, or a FOK order that can't
+    ///   fully fill)
+    /// - Triggered + its outputs, for each stop the trades set off
     /// - Top-of-book changes
     ///
-    /// This matches the behavior of your C++ `addOrder`.
+    /// During a call auction an accepted order is acked and rests without
+    /// matching (followed by top-of-book changes and the indicative, if it
+    /// moved), a stop order is held even if its stop price has traded,
+    /// and market, IOC and FOK orders are rejected (`InvalidInAuction`).
+    ///
+    /// If the volatility band stops matching, the remainder rests in the
+    /// auction that starts (or, if it can't rest, is expired), no further
+    /// stops trigger, and no indicative is emitted.
+    ///
+    /// This matches the behavior of your C++ `addOrder`, extended with
+    /// time-in-force handling and stop orders.
     pub fn add_order(&mut self, msg: &NewOrder) -> Vec<OutputMessage> {
         let mut outputs = Vec::new();
 
+        if let Some(reason) = self.validate_new_order(msg) {
+            outputs.push(OutputMessage::reject(
+                msg.user_id,
+                msg.user_order_id,
+                self.symbol.clone(),
+                reason,
+            ));
+            return outputs;
+        }
+
         // Create an internal order with timestamp.
-        let mut order = Order::from_new_order_now(msg);
+        let now = self.clock.now_ns();
+        let mut order = Order::from_new_order(msg, now);
+        self.band_reference = self.last_trade_price;
+
+        // Post-only: never take liquidity, either reject or reprice one
+        // tick behind the opposite best. Nothing trades in an auction.
+        if order.flags.is_post_only() && !self.in_auction {
+            match self.post_only_price(&order) {
+                Some(price) => order.price = price,
+                None => {
+                    outputs.push(OutputMessage::reject(
+                        msg.user_id,
+                        msg.user_order_id,
+                        self.symbol.clone(),
+                        RejectReason::PostOnlyWouldTrade,
+                    ));
+                    return outputs;
+                }
+            }
+        }
 
         // Ack.
         outputs.push(OutputMessage::ack(
@@ -82,29 +311,44 @@ impl OrderBook {
             self.symbol.clone(),
         ));
 
-        // Match against the opposing side.
-        let trade_outputs = self.match_order(&mut order);
-        outputs.extend(trade_outputs);
+        if order.order_type.is_stop() {
+            let triggered = StopBook::is_triggered(order.side, order.stop_price, self.last_trade_price);
+            if !triggered || self.in_auction {
+                // Held off-book: nothing visible changes.
+                self.stops.insert(order);
+                return outputs;
+            }
+            self.trigger(&mut order, &mut outputs);
+        }
 
-        // If there's remaining quantity and it's a limit order, add to book.
-        if order.remaining_qty > 0 && order.order_type == OrderType::Limit {
-            self.add_to_book(order);
+        if self.in_auction {
+            self.add_to_book(order, None);
+        } else {
+            self.execute(order, now, &mut outputs);
+            self.run_triggers(now, &mut outputs);
         }
 
         // Emit top-of-book changes (if any).
         let tob_outputs = self.check_top_of_book_changes();
         outputs.extend(tob_outputs);
+        outputs.extend(self.take_indicative_unless_breached());
+        outputs.extend(self.take_depth_update());
 
         outputs
     }
 
     /// Cancel an order by `(user_id, user_order_id)`.
     ///
-    /// Semantics mirror C++ `cancelOrder`:
     /// - If the order exists, remove it from the book and emit:
     ///   - CancelAck
     ///   - Top-of-book changes (if affected).
-    /// - If it doesn't exist, still emit a CancelAck.
+    /// - A pending stop order is removed from the trigger book (CancelAck
+    ///   only).
+    /// - If it doesn't exist, emit a Reject (`UnknownOrder`). The C++
+    ///   engine acked these too, which hid failures from clients.
+    ///
+    /// The lookup goes through the order index, so this is O(1) in the
+    /// number of resting orders (plus the `BTreeMap` level lookup).
     ///
     /// Note: We don't get the symbol here; the `MatchingEngine` routes
     /// cancel to the correct `OrderBook` based on its own mapping, just
@@ -112,61 +356,243 @@ impl OrderBook {
     pub fn cancel_order(&mut self, user_id: u32, user_order_id: u32) -> Vec<OutputMessage> {
         let mut outputs = Vec::new();
 
-        // Helper lambda: try to remove from a side (bids or asks).
-        fn remove_from_side(
-            _side: Side,
-            _book_symbol: &str,
-            levels: &mut BTreeMap<u32, VecDeque<Order>>,
-            user_id: u32,
-            user_order_id: u32,
-        ) -> bool {
-            // Iterate over all price levels; in practice the depth is usually small.
-            let mut empty_prices = Vec::new();
-
-            for (price, orders) in levels.iter_mut() {
-                let mut idx = 0;
-                while idx < orders.len() {
-                    if let Some(o) = orders.get(idx) {
-                        if o.user_id == user_id && o.user_order_id == user_order_id {
-                            // Found the order; remove it.
-                            orders.remove(idx);
-                            if orders.is_empty() {
-                                empty_prices.push(*price);
-                            }
-                            return true;
-                        }
-                    }
-                    idx += 1;
-                }
-            }
-
-            // Clean up any now-empty price levels.
-            for p in empty_prices {
-                levels.remove(&p);
-            }
-
-            false
+        if let Some(_stop) = self.stops.remove(user_id, user_order_id) {
+            return vec![OutputMessage::cancel_ack(
+                user_id,
+                user_order_id,
+                self.symbol.clone(),
+            )];
         }
 
-        // Try bids then asks.
-        let mut found = remove_from_side(Side::Buy, &self.symbol, &mut self.bids, user_id, user_order_id);
-        if !found {
-            found = remove_from_side(Side::Sell, &self.symbol, &mut self.asks, user_id, user_order_id);
+        let removed = match self.remove_order(user_id, user_order_id) {
+            Some(order) => order,
+            None => {
+                outputs.push(OutputMessage::reject(
+                    user_id,
+                    user_order_id,
+                    self.symbol.clone(),
+                    RejectReason::UnknownOrder,
+                ));
+                return outputs;
+            }
+        };
+        if !removed.is_hidden() {
+            self.record_cancel(removed.exchange_order_id, removed.displayed_qty(), 0);
         }
 
-        // Always emit CancelAck, even if not found (matches your C++ behavior).
         outputs.push(OutputMessage::cancel_ack(
             user_id,
             user_order_id,
             self.symbol.clone(),
         ));
 
-        // If we actually removed something, TOB may have changed.
-        if found {
-            let tob_outputs = self.check_top_of_book_changes();
-            outputs.extend(tob_outputs);
+        // We removed something, so TOB may have changed.
+        let tob_outputs = self.check_top_of_book_changes();
+        outputs.extend(tob_outputs);
+        outputs.extend(self.take_indicative());
+        outputs.extend(self.take_depth_update());
+
+        outputs
+    }
+
+    /// Cancel every order in this book that `request` matches (its symbol
+    /// is not checked here): resting orders, best price first on each
+    /// side, then pending stops, which are matched on their stop price.
+    ///
+    /// Only the request's user's orders are visited.
+    /// Emits a CancelAck per order, then one round of TOB / depth changes.
+    pub fn mass_cancel(&mut self, request: &MassCancel) -> Vec<OutputMessage> {
+        let mut resting: Vec<&Order> = self
+            .order_index
+            .user_orders(request.user_id)
+            .map(|(_, &slot)| self.orders.get(slot))
+            .filter(|o| request.matches(o.user_id, o.side, o.price))
+            .collect();
+        // Queue order: exchange ids grow with every order joining a level.
+        resting.sort_by_key(|o| match o.side {
+            Side::Buy => (0, u32::MAX - o.price, o.exchange_order_id),
+            Side::Sell => (1, o.price, o.exchange_order_id),
+        });
+        let resting: Vec<(u32, u32)> = resting.iter().map(|o| (o.user_id, o.user_order_id)).collect();
+        let stops: Vec<(u32, u32)> = self
+            .stops
+            .user_orders(request.user_id, |side, stop_price| request.matches(request.user_id, side, stop_price))
+            .into_iter()
+            .map(|o| (o.user_id, o.user_order_id))
+            .collect();
+
+        let mut outputs = Vec::with_capacity(resting.len() + stops.len());
+        for (user_id, user_order_id) in resting {
+            if let Some(removed) = self.remove_order(user_id, user_order_id) {
+                if !removed.is_hidden() {
+                    self.record_cancel(removed.exchange_order_id, removed.displayed_qty(), 0);
+                }
+                outputs.push(OutputMessage::cancel_ack(user_id, user_order_id, self.symbol.clone()));
+            }
+        }
+        for (user_id, user_order_id) in stops {
+            if self.stops.remove(user_id, user_order_id).is_some() {
+                outputs.push(OutputMessage::cancel_ack(user_id, user_order_id, self.symbol.clone()));
+            }
         }
 
+        if !outputs.is_empty() {
+            outputs.extend(self.check_top_of_book_changes());
+            outputs.extend(self.take_indicative());
+            outputs.extend(self.take_depth_update());
+        }
+        outputs
+    }
+
+    /// Amend a resting order's price and/or total quantity.
+    ///
+    /// - Quantity decrease at the same price: updated in place, keeping
+    ///   its queue position.
+    /// - Price change or quantity increase: the order is pulled, gets a
+    ///   fresh timestamp, is matched if the new price crosses, and any
+    ///   remainder rests at the back of its (new) level.
+    ///
+    /// Emits ReplaceAck (+ Trades, + triggered stops) + TOB changes, or a
+    /// Reject with `UnknownOrder` if the order isn't live here, or
+    /// `InvalidReplace` if it is a pending stop order (cancel and re-enter
+    /// instead), the request changes nothing, the price would make it a
+    /// market order, or the new quantity doesn't exceed what has already
+    /// filled, or `PostOnlyWouldTrade` if a post-only order's new price
+    /// would trade (replaces are never repriced). During a call auction
+    /// nothing matches: the re-entered order just rests.
+    pub fn replace_order(
+        &mut self,
+        user_id: u32,
+        user_order_id: u32,
+        new_price: Option<u32>,
+        new_quantity: Option<u32>,
+    ) -> Vec<OutputMessage> {
+        let mut outputs = Vec::new();
+
+        let slot = match self.order_index.get(user_id, user_order_id) {
+            Some(&slot) => slot,
+            None => {
+                let reason = if self.stops.contains(user_id, user_order_id) {
+                    RejectReason::InvalidReplace
+                } else {
+                    RejectReason::UnknownOrder
+                };
+                outputs.push(OutputMessage::reject(
+                    user_id,
+                    user_order_id,
+                    self.symbol.clone(),
+                    reason,
+                ));
+                return outputs;
+            }
+        };
+
+        let (price, quantity, remaining_qty, shown_qty, side, flags) = {
+            let o = self.orders.get(slot);
+            (o.price, o.quantity, o.remaining_qty, o.displayed_qty(), o.side, o.flags)
+        };
+        let filled_qty = quantity - remaining_qty;
+        let target_price = new_price.unwrap_or(price);
+        let target_quantity = new_quantity.unwrap_or(quantity);
+
+        let is_noop = target_price == price && target_quantity == quantity;
+        if is_noop || target_price == 0 || target_quantity <= filled_qty {
+            outputs.push(OutputMessage::reject(
+                user_id,
+                user_order_id,
+                self.symbol.clone(),
+                RejectReason::InvalidReplace,
+            ));
+            return outputs;
+        }
+
+        if flags.is_post_only() && !self.in_auction && self.takes_liquidity(side, target_price) {
+            outputs.push(OutputMessage::reject(
+                user_id,
+                user_order_id,
+                self.symbol.clone(),
+                RejectReason::PostOnlyWouldTrade,
+            ));
+            return outputs;
+        }
+
+        let target_remaining = target_quantity - filled_qty;
+
+        if target_price == price && target_quantity < quantity {
+            // Size down in place: priority is kept. An iceberg only
+            // shows less once the reserve is used up.
+            self.note_level(side, price);
+            let order = self.orders.get_mut(slot);
+            order.quantity = target_quantity;
+            order.remaining_qty = target_remaining;
+            let new_shown_qty = order.displayed_qty();
+            let exchange_id = order.exchange_order_id;
+            let levels = match side {
+                Side::Buy => &mut self.bids,
+                Side::Sell => &mut self.asks,
+            };
+            if let Some(level) = levels.get_mut(&price) {
+                level.reduce_quantity(shown_qty - new_shown_qty);
+            }
+            if new_shown_qty < shown_qty {
+                self.record_cancel(exchange_id, shown_qty - new_shown_qty, new_shown_qty);
+            }
+
+            outputs.push(OutputMessage::replace_ack(
+                user_id,
+                user_order_id,
+                self.symbol.clone(),
+                target_price,
+                target_remaining,
+            ));
+        } else {
+            // Price change or size up: re-enter as a fresh order.
+            let mut order = match self.remove_order(user_id, user_order_id) {
+                Some(o) => o,
+                None => return outputs,
+            };
+            order.price = target_price;
+            order.quantity = target_quantity;
+            order.remaining_qty = target_remaining;
+            let now = self.clock.now_ns();
+            order.timestamp_ns = now;
+            let old_exchange_id = order.exchange_order_id;
+            self.band_reference = self.last_trade_price;
+
+            outputs.push(OutputMessage::replace_ack(
+                user_id,
+                user_order_id,
+                self.symbol.clone(),
+                target_price,
+                target_remaining,
+            ));
+
+            // Only a replace that rests untouched is a `Replace` on the L3
+            // feed; one that trades deletes the old id first.
+            let will_trade = !self.in_auction && self.matchable_quantity(&order) > 0;
+            if will_trade && !order.is_hidden() {
+                self.record_cancel(old_exchange_id, shown_qty, 0);
+            }
+
+            if !self.in_auction {
+                let trade_outputs = self.match_order(&mut order, now);
+                outputs.extend(trade_outputs);
+            }
+
+            if order.remaining_qty > 0 {
+                let replaces = (!will_trade).then_some(old_exchange_id);
+                self.add_to_book(order, replaces);
+            }
+            if !self.in_auction {
+                self.run_triggers(now, &mut outputs);
+            }
+        }
+
+        let tob_outputs = self.check_top_of_book_changes();
+        outputs.extend(tob_outputs);
+        outputs.extend(self.take_indicative_unless_breached());
+        outputs.extend(self.take_depth_update());
+
         outputs
     }
 
@@ -178,8 +604,8 @@ impl OrderBook {
         let mut outputs = Vec::new();
 
         // Cancel acks for all bid orders
-        for (_price, orders) in &self.bids {
-            for order in orders {
+        for level in self.bids.values() {
+            for order in level.iter(&self.orders) {
                 outputs.push(OutputMessage::cancel_ack(
                     order.user_id,
                     order.user_order_id,
@@ -189,8 +615,8 @@ impl OrderBook {
         }
 
         // Cancel acks for all ask orders
-        for (_price, orders) in &self.asks {
-            for order in orders {
+        for level in self.asks.values() {
+            for order in level.iter(&self.orders) {
                 outputs.push(OutputMessage::cancel_ack(
                     order.user_id,
                     order.user_order_id,
@@ -199,74 +625,226 @@ impl OrderBook {
             }
         }
 
-        // Top-of-book eliminated messages if either side was non-empty
-        if !self.bids.is_empty() {
+        // Cancel acks for pending stop orders
+        for order in self.stops.iter() {
+            outputs.push(OutputMessage::cancel_ack(
+                order.user_id,
+                order.user_order_id,
+                self.symbol.clone(),
+            ));
+        }
+
+        // Top-of-book eliminated messages if either side was showing
+        if self.best_bid_price() != 0 {
             outputs.push(OutputMessage::top_of_book_eliminated(
                 self.symbol.clone(),
                 Side::Buy,
             ));
         }
-        if !self.asks.is_empty() {
+        if self.best_ask_price() != 0 {
             outputs.push(OutputMessage::top_of_book_eliminated(
                 self.symbol.clone(),
                 Side::Sell,
             ));
         }
 
+        // Every order is deleted from the L3 feed.
+        if self.book_events {
+            let resting: Vec<(u64, u32)> = self
+                .bids
+                .values()
+                .chain(self.asks.values())
+                .flat_map(|level| level.iter(&self.orders))
+                .filter(|o| !o.is_hidden())
+                .map(|o| (o.exchange_order_id, o.displayed_qty()))
+                .collect();
+            for (order_id, qty) in resting {
+                self.record_cancel(order_id, qty, 0);
+            }
+        }
+
+        // Every level disappears.
+        if self.depth_updates {
+            let levels: Vec<(Side, u32)> = self
+                .bids
+                .keys()
+                .map(|&p| (Side::Buy, p))
+                .chain(self.asks.keys().map(|&p| (Side::Sell, p)))
+                .collect();
+            for (side, price) in levels {
+                self.note_level(side, price);
+            }
+        }
+
         // Now clear internal state
         self.bids.clear();
         self.asks.clear();
+        self.orders.clear();
+        self.order_index.clear();
+        self.stops.clear();
+        self.last_trade_price = 0;
         self.prev_best_bid_price = 0;
         self.prev_best_bid_qty = 0;
         self.prev_best_ask_price = 0;
         self.prev_best_ask_qty = 0;
+        self.in_auction = false;
+        self.published_indicative = Uncross::NONE;
+        outputs.extend(self.take_depth_update());
+
+        outputs
+    }
+
+    /// Put the book into a call auction. Emits the current indicative
+    /// uncross (also when the book already was in an auction, as a
+    /// re-publish).
+    pub fn start_auction(&mut self) -> Vec<OutputMessage> {
+        self.in_auction = true;
+        self.published_indicative = self.indicative_uncross();
+        vec![self.indicative_message()]
+    }
+
+    /// Execute the call auction and resume continuous matching.
+    ///
+    /// Every crossing order trades at the single clearing price (see
+    /// [`crate::auction`]): the oldest buy and sell orders are paired
+    /// first, each pair trading the smaller of their full remaining
+    /// quantities (iceberg reserves included). A trade's aggressor is the
+    /// later of its two orders. Pairs of the same user go through the
+    /// later order's self-trade prevention mode, as in continuous
+    /// matching.
+    ///
+    /// Emits the Trades (and SelfTradeCancels), then outputs of any stops
+    /// the clearing price triggers, then top-of-book changes. Nothing if
+    /// the book is not in an auction. The clearing price is the reference
+    /// for the volatility band of the triggered stops.
+    pub fn uncross(&mut self) -> Vec<OutputMessage> {
+        let mut outputs = Vec::new();
+        if !self.in_auction {
+            return outputs;
+        }
+
+        let now = self.clock.now_ns();
+        let price = self.indicative_uncross().price;
+        if price != 0 {
+            self.execute_auction(price, now, &mut outputs);
+        }
+        self.in_auction = false;
+        self.published_indicative = Uncross::NONE;
+        self.band_reference = self.last_trade_price;
+        self.run_triggers(now, &mut outputs);
+
+        let tob_outputs = self.check_top_of_book_changes();
+        outputs.extend(tob_outputs);
+        outputs.extend(self.take_depth_update());
 
         outputs
     }
 
-    /// Get best bid price (0 if none).
+    /// Leave the call auction without executing it, as when the symbol
+    /// is halted: orders stay where they are and no indicative is
+    /// published. Whatever the auction left crossing trades on
+    /// [`resume`](Self::resume).
+    pub fn suspend_auction(&mut self) {
+        self.in_auction = false;
+        self.published_indicative = Uncross::NONE;
+    }
+
+    /// Resume continuous matching after a call auction or a halt: the
+    /// book is uncrossed as at the end of an auction (see
+    /// [`uncross`](Self::uncross)), which does nothing to a book that
+    /// doesn't cross and has no stops waiting.
+    pub fn resume(&mut self) -> Vec<OutputMessage> {
+        self.in_auction = true;
+        self.uncross()
+    }
+
+    /// Returns `true` while the book is in a call auction.
+    pub fn is_in_auction(&self) -> bool {
+        self.in_auction
+    }
+
+    /// Price, volume and imbalance an uncross would have right now,
+    /// counting every resting order in full (hidden and iceberg reserve
+    /// quantity included), with the last trade price as reference.
+    pub fn indicative_uncross(&self) -> Uncross {
+        let remaining = |levels: &BTreeMap<u32, PriceLevel>| -> Vec<(u32, u32)> {
+            levels
+                .iter()
+                .map(|(&price, level)| {
+                    let qty = level.iter(&self.orders).map(|o| o.remaining_qty).sum();
+                    (price, qty)
+                })
+                .collect()
+        };
+        auction::find_uncross(&remaining(&self.bids), &remaining(&self.asks), self.last_trade_price)
+    }
+
+    /// Look up a resting order by `(user_id, user_order_id)` in O(1).
+    pub fn get_order(&self, user_id: u32, user_order_id: u32) -> Option<&Order> {
+        self.order_index
+            .get(user_id, user_order_id)
+            .map(|&slot| self.orders.get(slot))
+    }
+
+    /// Returns `true` if `(user_id, user_order_id)` is live in this book:
+    /// resting, or a pending stop order.
+    pub fn contains_order(&self, user_id: u32, user_order_id: u32) -> bool {
+        self.order_index.contains(user_id, user_order_id)
+            || self.stops.contains(user_id, user_order_id)
+    }
+
+    /// Number of orders currently resting in this book.
+    pub fn order_count(&self) -> usize {
+        self.order_index.len()
+    }
+
+    /// Number of stop orders waiting to be triggered.
+    pub fn stop_order_count(&self) -> usize {
+        self.stops.len()
+    }
+
+    /// Price of the last trade in this book (`0` if none yet).
+    pub fn last_trade_price(&self) -> u32 {
+        self.last_trade_price
+    }
+
+    /// Get best displayed bid price (0 if none). Levels holding only
+    /// hidden orders are skipped.
     pub fn best_bid_price(&self) -> u32 {
-        self.bids
-            .keys()
-            .next_back()
-            .copied()
-            .unwrap_or(0)
+        self.best_shown_bid().map_or(0, |(&price, _)| price)
     }
 
-    /// Get best ask price (0 if none).
+    /// Get best displayed ask price (0 if none). Levels holding only
+    /// hidden orders are skipped.
     pub fn best_ask_price(&self) -> u32 {
-        self.asks
-            .keys()
-            .next()
-            .copied()
-            .unwrap_or(0)
+        self.best_shown_ask().map_or(0, |(&price, _)| price)
     }
 
-    /// Get total quantity at best bid (0 if none).
+    /// Get displayed quantity at best bid (0 if none).
     pub fn best_bid_quantity(&self) -> u32 {
-        match self.bids.keys().next_back().copied() {
-            Some(price) => {
-                if let Some(orders) = self.bids.get(&price) {
-                    Self::total_quantity_at_price(orders)
-                } else {
-                    0
-                }
-            }
-            None => 0,
-        }
+        self.best_shown_bid()
+            .map_or(0, |(_, level)| level.total_quantity())
     }
 
-    /// Get total quantity at best ask (0 if none).
+    /// Get displayed quantity at best ask (0 if none).
     pub fn best_ask_quantity(&self) -> u32 {
-        match self.asks.keys().next().copied() {
-            Some(price) => {
-                if let Some(orders) = self.asks.get(&price) {
-                    Self::total_quantity_at_price(orders)
-                } else {
-                    0
-                }
-            }
-            None => 0,
+        self.best_shown_ask()
+            .map_or(0, |(_, level)| level.total_quantity())
+    }
+
+    /// Aggregated displayed quantity for the best `levels` prices on each
+    /// side (`0` = all levels), best price first. Levels holding only
+    /// hidden orders are left out.
+    pub fn depth(&self, levels: usize) -> BookDepth {
+        let take = if levels == 0 { usize::MAX } else { levels };
+        let shown = |(_, level): &(&u32, &PriceLevel)| level.total_quantity() > 0;
+        let to_level = |(&price, level): (&u32, &PriceLevel)| DepthLevel {
+            price,
+            quantity: level.total_quantity(),
+        };
+        BookDepth {
+            bids: self.bids.iter().rev().filter(shown).take(take).map(to_level).collect(),
+            asks: self.asks.iter().filter(shown).take(take).map(to_level).collect(),
         }
     }
 
@@ -280,153 +858,785 @@ impl OrderBook {
         )
     }
 
+    /// Capture this book's state for a snapshot.
+    pub(crate) fn to_state(&self) -> BookState {
+        let orders = self
+            .bids
+            .values()
+            .rev()
+            .chain(self.asks.values())
+            .flat_map(|level| level.iter(&self.orders))
+            .cloned()
+            .collect();
+        BookState {
+            symbol: self.symbol.clone(),
+            next_exchange_order_id: self.next_exchange_order_id,
+            last_trade_price: self.last_trade_price,
+            prev_top_of_book: TopOfBookSnapshot::new(
+                self.prev_best_bid_price,
+                self.prev_best_bid_qty,
+                self.prev_best_ask_price,
+                self.prev_best_ask_qty,
+            ),
+            orders,
+            stop_orders: self.stops.iter().cloned().collect(),
+            in_auction: self.in_auction,
+            published_indicative: self.published_indicative,
+        }
+    }
+
+    /// Rebuild a book from snapshot state, keeping every order's
+    /// exchange id and queue position.
+    pub(crate) fn from_state(state: BookState, clock: Arc<dyn Clock>) -> Self {
+        let mut book = OrderBook::with_clock(state.symbol, clock);
+        book.next_exchange_order_id = state.next_exchange_order_id;
+        book.last_trade_price = state.last_trade_price;
+        book.prev_best_bid_price = state.prev_top_of_book.bid_price;
+        book.prev_best_bid_qty = state.prev_top_of_book.bid_quantity;
+        book.prev_best_ask_price = state.prev_top_of_book.ask_price;
+        book.prev_best_ask_qty = state.prev_top_of_book.ask_quantity;
+        book.in_auction = state.in_auction;
+        book.published_indicative = state.published_indicative;
+
+        for order in state.orders {
+            let (user_id, user_order_id) = (order.user_id, order.user_order_id);
+            let levels = match order.side {
+                Side::Buy => &mut book.bids,
+                Side::Sell => &mut book.asks,
+            };
+            let level = levels.entry(order.price).or_default();
+            let slot = book.orders.insert(order);
+            level.push_back(&mut book.orders, slot);
+            book.order_index.insert(user_id, user_order_id, slot);
+        }
+        for order in state.stop_orders {
+            book.stops.insert(order);
+        }
+        book
+    }
+
     // -------------------------------------------------------------------------
     // Internal helpers
     // -------------------------------------------------------------------------
 
+    /// Match an acked (or just triggered) order, then rest or expire what
+    /// is left:
+    /// - FOK: expire the whole order unless it can fill completely.
+    /// - Limit remainder: rest (Day/GTC) or expire (IOC).
+    /// - Market remainder (book exhausted, or past the market order
+    ///   protection or the volatility band): expire, so its owner hears
+    ///   about it. The C++ engine dropped it silently.
+    fn execute(&mut self, mut order: Order, now: u64, outputs: &mut Vec<OutputMessage>) {
+        // Fill-or-kill: check liquidity up front and do nothing unless the
+        // whole order can trade.
+        if order.time_in_force == TimeInForce::Fok
+            && self.matchable_quantity(&order) < order.remaining_qty
+        {
+            outputs.push(OutputMessage::expired(
+                order.user_id,
+                order.user_order_id,
+                self.symbol.clone(),
+                order.remaining_qty,
+            ));
+            return;
+        }
+
+        // Match against the opposing side.
+        let trade_outputs = self.match_order(&mut order, now);
+        outputs.extend(trade_outputs);
+
+        if order.remaining_qty == 0 {
+            return;
+        }
+        if order.order_type == OrderType::Limit && order.time_in_force.can_rest() {
+            self.add_to_book(order, None);
+        } else {
+            outputs.push(OutputMessage::expired(
+                order.user_id,
+                order.user_order_id,
+                self.symbol.clone(),
+                order.remaining_qty,
+            ));
+        }
+    }
+
+    /// Turn a stop order into the market/limit order it wraps, emitting
+    /// `Triggered`.
+    fn trigger(&mut self, order: &mut Order, outputs: &mut Vec<OutputMessage>) {
+        outputs.push(OutputMessage::triggered(
+            order.user_id,
+            order.user_order_id,
+            self.symbol.clone(),
+            order.stop_price,
+            self.last_trade_price,
+        ));
+        order.order_type = order.order_type.triggered();
+    }
+
+    /// Execute every stop the last trade price has reached, one at a time
+    /// in trigger order, until none is left: trades by a triggered order
+    /// move the price and may trigger more (cascade).
+    ///
+    /// Triggered orders take their time priority from `now`.
+    ///
+    /// Stops when a volatility band breach puts the book into an auction.
+    fn run_triggers(&mut self, now: u64, outputs: &mut Vec<OutputMessage>) {
+        while self.band_breach.is_none() {
+            let Some(mut order) = self.stops.pop_triggered(self.last_trade_price) else {
+                break;
+            };
+            self.trigger(&mut order, outputs);
+            order.timestamp_ns = now;
+            self.execute(order, now, outputs);
+        }
+    }
+
     /// Match an incoming active order against the opposite side of the book.
     ///
-    /// Fills generate Trade events. Any remaining quantity is left in the
-    /// `order` object for the caller to potentially add to the book.
-    fn match_order(&mut self, order: &mut Order) -> Vec<OutputMessage> {
+    /// Fills generate Trade events stamped `now`; a resting order of the
+    /// same user is handled by the incoming order's self-trade prevention
+    /// mode instead (`SelfTradeCancel` events), after which matching
+    /// carries on through the level. Any remaining quantity is left in
+    /// the `order` object for the caller to potentially add to the book.
+    ///
+    /// A market order stops at its protection price. Reaching a level
+    /// outside the volatility band records a band breach and puts the
+    /// book into its call auction instead.
+    fn match_order(&mut self, order: &mut Order, now: u64) -> Vec<OutputMessage> {
         let mut outputs = Vec::new();
+        let protection_price = self.market_protection_price(order);
 
-        match order.side {
-            Side::Buy => {
-                // Buy order: match against asks (ascending price).
-                loop {
-                    if order.remaining_qty == 0 || self.asks.is_empty() {
-                        break;
-                    }
+        loop {
+            if order.remaining_qty == 0 {
+                break;
+            }
+
+            // Buy orders walk the asks upwards, sell orders walk the bids
+            // downwards.
+            let best_price_opt = match order.side {
+                Side::Buy => self.asks.keys().next().copied(),
+                Side::Sell => self.bids.keys().next_back().copied(),
+            };
+            let best_price = match best_price_opt {
+                Some(p) => p,
+                None => break,
+            };
 
-                    // Clone key to satisfy borrow rules.
-                    let best_ask_price_opt = self.asks.keys().next().copied();
-                    let best_ask_price = match best_ask_price_opt {
-                        Some(p) => p,
+            // Can we match?
+            if !Self::crosses(order, best_price) || Self::beyond(order.side, best_price, protection_price) {
+                break;
+            }
+            if self.outside_band(best_price) {
+                // Volatility interruption: nothing more trades, the rest
+                // of the order is left to the (now) auction.
+                self.band_breach = Some((self.band_reference, best_price));
+                self.in_auction = true;
+                break;
+            }
+
+            let levels = match order.side {
+                Side::Buy => &mut self.asks,
+                Side::Sell => &mut self.bids,
+            };
+
+            // Match against all orders at this price level FIFO.
+            if self.depth_updates {
+                let key = (side_key(order.side.opposite()), best_price);
+                let before = levels.get(&best_price).map_or(0, PriceLevel::total_quantity);
+                self.depth_dirty.entry(key).or_insert(before);
+            }
+            if let Some(level) = levels.get_mut(&best_price) {
+                while order.remaining_qty > 0 {
+                    let slot = match level.front() {
+                        Some(s) => s,
                         None => break,
                     };
+                    let passive_order = self.orders.get_mut(slot);
+                    let shown_before = passive_order.displayed_qty();
+                    let stp = order.self_trade_prevention;
 
-                    // Can we match?
-                    let can_match = match order.order_type {
-                        OrderType::Market => true,
-                        OrderType::Limit => order.price >= best_ask_price,
-                    };
-                    if !can_match {
-                        break;
-                    }
+                    if stp.is_enabled() && passive_order.user_id == order.user_id {
+                        // Self-trade: cancel quantity instead of trading.
+                        let (incoming_qty, resting_qty) =
+                            Self::self_trade_quantities(stp, order, passive_order);
+                        passive_order.fill(resting_qty);
+                        order.fill(incoming_qty);
+                        level.reduce_quantity(shown_before - passive_order.displayed_qty());
 
-                    // Match against all orders at this price level FIFO.
-                    if let Some(ask_orders) = self.asks.get_mut(&best_ask_price) {
-                        while order.remaining_qty > 0 && !ask_orders.is_empty() {
-                            // Safe: we only hold a mutable borrow of ask_orders.
-                            if let Some(passive_order) = ask_orders.front_mut() {
-                                let trade_qty = order.remaining_qty.min(passive_order.remaining_qty);
-
-                                // Trade price is passive (best_ask_price).
-                                outputs.push(OutputMessage::trade(
-                                    self.symbol.clone(),
-                                    order.user_id,
-                                    order.user_order_id,
-                                    passive_order.user_id,
-                                    passive_order.user_order_id,
-                                    best_ask_price,
-                                    trade_qty,
-                                ));
-
-                                order.fill(trade_qty);
-                                passive_order.fill(trade_qty);
-
-                                if passive_order.is_filled() {
-                                    ask_orders.pop_front();
-                                }
-                            } else {
-                                break;
+                        if resting_qty > 0 {
+                            outputs.push(OutputMessage::self_trade_cancel(
+                                passive_order.user_id,
+                                passive_order.user_order_id,
+                                self.symbol.clone(),
+                                resting_qty,
+                                passive_order.remaining_qty,
+                            ));
+                            if self.book_events && !passive_order.is_hidden() {
+                                self.pending_book_events.push(OrderBookEvent::Cancel {
+                                    symbol: self.symbol.clone(),
+                                    order_id: passive_order.exchange_order_id,
+                                    cancelled_qty: shown_before - passive_order.displayed_qty(),
+                                    remaining_qty: passive_order.displayed_qty(),
+                                });
                             }
                         }
+                        if incoming_qty > 0 {
+                            outputs.push(OutputMessage::self_trade_cancel(
+                                order.user_id,
+                                order.user_order_id,
+                                self.symbol.clone(),
+                                incoming_qty,
+                                order.remaining_qty,
+                            ));
+                        }
+                    } else {
+                        // Only the displayed slice of an iceberg trades
+                        // before it is refreshed.
+                        let trade_qty = order.remaining_qty.min(passive_order.tradable_qty());
+
+                        // Trade price is the passive (resting) price.
+                        let (buy, sell) = match order.side {
+                            Side::Buy => (&*order, &*passive_order),
+                            Side::Sell => (&*passive_order, &*order),
+                        };
+                        outputs.push(OutputMessage::trade(
+                            self.symbol.clone(),
+                            buy.user_id,
+                            buy.user_order_id,
+                            sell.user_id,
+                            sell.user_order_id,
+                            best_price,
+                            trade_qty,
+                            order.side,
+                            now,
+                        ));
+
+                        order.fill(trade_qty);
+                        passive_order.fill(trade_qty);
+                        level.reduce_quantity(shown_before - passive_order.displayed_qty());
+                        self.last_trade_price = best_price;
+
+                        if self.book_events && !passive_order.is_hidden() {
+                            self.pending_book_events.push(OrderBookEvent::Execute {
+                                symbol: self.symbol.clone(),
+                                order_id: passive_order.exchange_order_id,
+                                price: best_price,
+                                executed_qty: trade_qty,
+                                remaining_qty: passive_order.displayed_qty(),
+                            });
+                        }
                     }
 
-                    // Remove empty price level.
-                    if let Some(ask_orders) = self.asks.get(&best_ask_price) {
-                        if ask_orders.is_empty() {
-                            self.asks.remove(&best_ask_price);
+                    if passive_order.is_filled() {
+                        level.unlink(&mut self.orders, slot);
+                        let filled = self.orders.remove(slot);
+                        self.order_index.remove(filled.user_id, filled.user_order_id);
+                    } else if passive_order.tradable_qty() == 0 {
+                        // Iceberg slice used up: the next slice from the
+                        // reserve joins the back of the level, as a new
+                        // order on the L3 feed.
+                        level.unlink(&mut self.orders, slot);
+                        let refreshed = self.orders.get_mut(slot);
+                        refreshed.refill_display();
+                        refreshed.timestamp_ns = now;
+                        refreshed.exchange_order_id = self.next_exchange_order_id;
+                        self.next_exchange_order_id += 1;
+                        if self.book_events {
+                            self.pending_book_events.push(OrderBookEvent::Add {
+                                symbol: self.symbol.clone(),
+                                order_id: refreshed.exchange_order_id,
+                                side: refreshed.side,
+                                price: best_price,
+                                quantity: refreshed.displayed_qty(),
+                            });
                         }
+                        level.push_back(&mut self.orders, slot);
                     }
                 }
+
+                // Remove empty price level.
+                if level.is_empty() {
+                    levels.remove(&best_price);
+                }
             }
-            Side::Sell => {
-                // Sell order: match against bids (descending price).
-                loop {
-                    if order.remaining_qty == 0 || self.bids.is_empty() {
-                        break;
-                    }
+        }
 
-                    // Best bid is the highest key.
-                    let best_bid_price_opt = self.bids.keys().next_back().copied();
-                    let best_bid_price = match best_bid_price_opt {
-                        Some(p) => p,
-                        None => break,
-                    };
+        outputs
+    }
 
-                    let can_match = match order.order_type {
-                        OrderType::Market => true,
-                        OrderType::Limit => order.price <= best_bid_price,
-                    };
-                    if !can_match {
-                        break;
-                    }
+    /// Trade every crossing order at the clearing `price`, pairing the
+    /// front orders of the best bid and ask levels until one side no
+    /// longer crosses it.
+    fn execute_auction(&mut self, price: u32, now: u64, outputs: &mut Vec<OutputMessage>) {
+        loop {
+            let bid = self.bids.range(price..).next_back().and_then(|(&p, level)| Some((p, level.front()?)));
+            let ask = self.asks.range(..=price).next().and_then(|(&p, level)| Some((p, level.front()?)));
+            let (Some((bid_price, bid_slot)), Some((ask_price, ask_slot))) = (bid, ask) else {
+                break;
+            };
 
-                    if let Some(bid_orders) = self.bids.get_mut(&best_bid_price) {
-                        while order.remaining_qty > 0 && !bid_orders.is_empty() {
-                            if let Some(passive_order) = bid_orders.front_mut() {
-                                let trade_qty = order.remaining_qty.min(passive_order.remaining_qty);
-
-                                // Trade price is passive (best_bid_price).
-                                outputs.push(OutputMessage::trade(
-                                    self.symbol.clone(),
-                                    passive_order.user_id,
-                                    passive_order.user_order_id,
-                                    order.user_id,
-                                    order.user_order_id,
-                                    best_bid_price,
-                                    trade_qty,
-                                ));
-
-                                order.fill(trade_qty);
-                                passive_order.fill(trade_qty);
-
-                                if passive_order.is_filled() {
-                                    bid_orders.pop_front();
-                                }
-                            } else {
-                                break;
-                            }
-                        }
-                    }
+            let (buy, sell) = (self.orders.get(bid_slot), self.orders.get(ask_slot));
+            // The later order is the one that "arrived" into the cross.
+            let buy_is_newer = buy.timestamp_ns >= sell.timestamp_ns;
+            let (newer, older) = if buy_is_newer { (buy, sell) } else { (sell, buy) };
+            let stp = newer.self_trade_prevention;
 
-                    if let Some(bid_orders) = self.bids.get(&best_bid_price) {
-                        if bid_orders.is_empty() {
-                            self.bids.remove(&best_bid_price);
-                        }
+            if stp.is_enabled() && newer.user_id == older.user_id {
+                let (newer_qty, older_qty) = Self::self_trade_quantities(stp, newer, older);
+                let (newer_slot, newer_price, older_slot, older_price) = if buy_is_newer {
+                    (bid_slot, bid_price, ask_slot, ask_price)
+                } else {
+                    (ask_slot, ask_price, bid_slot, bid_price)
+                };
+                for (slot, level_price, qty) in [(older_slot, older_price, older_qty), (newer_slot, newer_price, newer_qty)] {
+                    if qty == 0 {
+                        continue;
                     }
+                    let order = self.orders.get(slot);
+                    outputs.push(OutputMessage::self_trade_cancel(
+                        order.user_id,
+                        order.user_order_id,
+                        self.symbol.clone(),
+                        qty,
+                        order.remaining_qty - qty,
+                    ));
+                    self.reduce_resting(slot, level_price, qty, None, now);
                 }
+                continue;
             }
+
+            let quantity = buy.remaining_qty.min(sell.remaining_qty);
+            outputs.push(OutputMessage::trade(
+                self.symbol.clone(),
+                buy.user_id,
+                buy.user_order_id,
+                sell.user_id,
+                sell.user_order_id,
+                price,
+                quantity,
+                if buy_is_newer { Side::Buy } else { Side::Sell },
+                now,
+            ));
+            self.last_trade_price = price;
+            self.reduce_resting(bid_slot, bid_price, quantity, Some(price), now);
+            self.reduce_resting(ask_slot, ask_price, quantity, Some(price), now);
         }
+    }
 
-        outputs
+    /// Take `qty` off the resting order in `slot` (at level `price`),
+    /// traded at `traded_at` or cancelled (`None`). A filled order leaves
+    /// the book; a used-up iceberg slice is refreshed to the back of its
+    /// level.
+    fn reduce_resting(&mut self, slot: usize, price: u32, qty: u32, traded_at: Option<u32>, now: u64) {
+        let side = self.orders.get(slot).side;
+        self.note_level(side, price);
+
+        let order = self.orders.get_mut(slot);
+        let shown_before = order.displayed_qty();
+        order.fill(qty);
+        let shown_after = order.displayed_qty();
+        let (order_id, hidden) = (order.exchange_order_id, order.is_hidden());
+        let (filled, slice_used) = (order.is_filled(), order.tradable_qty() == 0);
+
+        // The feed only ever saw the displayed part of an iceberg.
+        if self.book_events && !hidden {
+            let event = match traded_at {
+                Some(trade_price) => OrderBookEvent::Execute {
+                    symbol: self.symbol.clone(),
+                    order_id,
+                    price: trade_price,
+                    executed_qty: shown_before - shown_after,
+                    remaining_qty: shown_after,
+                },
+                None => OrderBookEvent::Cancel {
+                    symbol: self.symbol.clone(),
+                    order_id,
+                    cancelled_qty: shown_before - shown_after,
+                    remaining_qty: shown_after,
+                },
+            };
+            self.pending_book_events.push(event);
+        }
+
+        let levels = match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+        let Some(level) = levels.get_mut(&price) else {
+            return;
+        };
+        level.reduce_quantity(shown_before - shown_after);
+
+        if filled {
+            level.unlink(&mut self.orders, slot);
+            if level.is_empty() {
+                levels.remove(&price);
+            }
+            let removed = self.orders.remove(slot);
+            self.order_index.remove(removed.user_id, removed.user_order_id);
+        } else if slice_used {
+            level.unlink(&mut self.orders, slot);
+            let refreshed = self.orders.get_mut(slot);
+            refreshed.refill_display();
+            refreshed.timestamp_ns = now;
+            refreshed.exchange_order_id = self.next_exchange_order_id;
+            self.next_exchange_order_id += 1;
+            let quantity = refreshed.displayed_qty();
+            level.push_back(&mut self.orders, slot);
+            if self.book_events {
+                self.pending_book_events.push(OrderBookEvent::Add {
+                    symbol: self.symbol.clone(),
+                    order_id: self.orders.get(slot).exchange_order_id,
+                    side,
+                    price,
+                    quantity,
+                });
+            }
+        }
+    }
+
+    /// `IndicativeUncross` for the current indicative, if the book is in
+    /// an auction and it differs from the one last published.
+    fn take_indicative(&mut self) -> Option<OutputMessage> {
+        if !self.in_auction {
+            return None;
+        }
+        let current = self.indicative_uncross();
+        if current == self.published_indicative {
+            return None;
+        }
+        self.published_indicative = current;
+        Some(self.indicative_message())
+    }
+
+    /// Like `take_indicative`, except after a band breach: the auction it
+    /// starts is announced by the engine, indicative included.
+    fn take_indicative_unless_breached(&mut self) -> Option<OutputMessage> {
+        if self.band_breach.is_some() {
+            return None;
+        }
+        self.take_indicative()
+    }
+
+    /// Would a trade at `price` be outside the volatility band?
+    fn outside_band(&self, price: u32) -> bool {
+        if self.band_bps == 0 || self.band_reference == 0 {
+            return false;
+        }
+        let reference = u64::from(self.band_reference);
+        let width = reference * u64::from(self.band_bps) / 10_000;
+        let price = u64::from(price);
+        price < reference.saturating_sub(width) || price > reference + width
+    }
+
+    /// `IndicativeUncross` message for the last published indicative.
+    fn indicative_message(&self) -> OutputMessage {
+        let uncross = self.published_indicative;
+        OutputMessage::IndicativeUncross(IndicativeUncross {
+            symbol: self.symbol.clone(),
+            price: uncross.price,
+            volume: uncross.volume,
+            imbalance_qty: uncross.imbalance_qty,
+            imbalance_side: uncross.imbalance_side,
+        })
+    }
+
+    /// Book-level checks for a new order; `Some(reason)` means reject.
+    fn validate_new_order(&self, msg: &NewOrder) -> Option<RejectReason> {
+        if msg.quantity == 0 {
+            return Some(RejectReason::ZeroQuantity);
+        }
+        let flags = msg.flags;
+        let both_post_only = flags.contains(OrderFlags::POST_ONLY | OrderFlags::POST_ONLY_REPRICE);
+        if both_post_only
+            || (flags.is_post_only() && msg.order_type() != OrderType::Limit)
+            || (flags.is_hidden() && msg.display_qty > 0)
+        {
+            return Some(RejectReason::InvalidFlags);
+        }
+        if self.contains_order(msg.user_id, msg.user_order_id) {
+            return Some(RejectReason::DuplicateOrderId);
+        }
+        let can_rest = msg.order_type() == OrderType::Limit && msg.time_in_force.can_rest();
+        if self.in_auction && !can_rest && msg.stop_price == 0 {
+            return Some(RejectReason::InvalidInAuction);
+        }
+        let opposite_empty = match msg.side {
+            Side::Buy => self.asks.is_empty(),
+            Side::Sell => self.bids.is_empty(),
+        };
+        if msg.order_type() == OrderType::Market && opposite_empty {
+            return Some(RejectReason::NoLiquidity);
+        }
+        None
+    }
+
+    /// Can `order` trade against a resting order at `price`?
+    fn crosses(order: &Order, price: u32) -> bool {
+        match (order.order_type, order.side) {
+            (OrderType::Market, _) => true,
+            (OrderType::Limit, Side::Buy) => order.price >= price,
+            (OrderType::Limit, Side::Sell) => order.price <= price,
+            // Pending stops never match.
+            (OrderType::StopMarket | OrderType::StopLimit, _) => false,
+        }
+    }
+
+    /// Furthest price a market order may trade at: the market order
+    /// protection's number of ticks through the touch it meets now.
+    /// `None` for limit orders, without protection, or on an empty side.
+    fn market_protection_price(&self, order: &Order) -> Option<u32> {
+        if order.order_type != OrderType::Market || self.market_protection_ticks == 0 {
+            return None;
+        }
+        let touch = self.best_opposite_price(order.side)?;
+        let distance = self.market_protection_ticks.saturating_mul(self.tick_size);
+        Some(match order.side {
+            Side::Buy => touch.saturating_add(distance),
+            Side::Sell => touch.saturating_sub(distance),
+        })
+    }
+
+    /// Is `price` past `limit` for an order on `side` (never, without a
+    /// limit)?
+    fn beyond(side: Side, price: u32, limit: Option<u32>) -> bool {
+        match (limit, side) {
+            (None, _) => false,
+            (Some(limit), Side::Buy) => price > limit,
+            (Some(limit), Side::Sell) => price < limit,
+        }
     }
 
-    /// Add a remaining limit order to the appropriate side of the book.
-    fn add_to_book(&mut self, order: Order) {
+    /// Best opposite price (hidden orders included) a new order on `side`
+    /// would meet, if any.
+    fn best_opposite_price(&self, side: Side) -> Option<u32> {
+        match side {
+            Side::Buy => self.asks.keys().next().copied(),
+            Side::Sell => self.bids.keys().next_back().copied(),
+        }
+    }
+
+    /// Would a limit order on `side` at `price` trade on arrival?
+    fn takes_liquidity(&self, side: Side, price: u32) -> bool {
+        self.best_opposite_price(side).is_some_and(|best| match side {
+            Side::Buy => price >= best,
+            Side::Sell => price <= best,
+        })
+    }
+
+    /// Price a post-only limit order may rest at: its own if it doesn't
+    /// cross, one tick behind the opposite best if it does and may be
+    /// repriced, `None` if it must be rejected.
+    fn post_only_price(&self, order: &Order) -> Option<u32> {
+        if !self.takes_liquidity(order.side, order.price) {
+            return Some(order.price);
+        }
+        if !order.flags.contains(OrderFlags::POST_ONLY_REPRICE) {
+            return None;
+        }
+        let best = self.best_opposite_price(order.side)?;
         match order.side {
-            Side::Buy => {
-                let entry = self.bids.entry(order.price).or_insert_with(VecDeque::new);
-                entry.push_back(order);
+            Side::Buy => best.checked_sub(self.tick_size).filter(|&p| p > 0),
+            Side::Sell => best.checked_add(self.tick_size),
+        }
+    }
+
+    /// Best bid level with displayed quantity.
+    fn best_shown_bid(&self) -> Option<(&u32, &PriceLevel)> {
+        self.bids.iter().rev().find(|(_, level)| level.total_quantity() > 0)
+    }
+
+    /// Best ask level with displayed quantity.
+    fn best_shown_ask(&self) -> Option<(&u32, &PriceLevel)> {
+        self.asks.iter().find(|(_, level)| level.total_quantity() > 0)
+    }
+
+    /// Quantities `(incoming, resting)` to cancel when `incoming` meets a
+    /// resting order of the same user under `stp`.
+    fn self_trade_quantities(stp: SelfTradePrevention, incoming: &Order, resting: &Order) -> (u32, u32) {
+        match stp {
+            SelfTradePrevention::None => (0, 0),
+            SelfTradePrevention::CancelNewest => (incoming.remaining_qty, 0),
+            SelfTradePrevention::CancelOldest => (0, resting.remaining_qty),
+            SelfTradePrevention::CancelBoth => (incoming.remaining_qty, resting.remaining_qty),
+            SelfTradePrevention::DecrementAndCancel => {
+                let qty = incoming.remaining_qty.min(resting.remaining_qty);
+                (qty, qty)
             }
-            Side::Sell => {
-                let entry = self.asks.entry(order.price).or_insert_with(VecDeque::new);
-                entry.push_back(order);
+        }
+    }
+
+    /// Quantity on the opposite side that `order` could trade against right
+    /// now, given its limit (or protection) price and the volatility
+    /// band, including iceberg reserves. Stops counting once the order's remaining
+    /// quantity is covered.
+    ///
+    /// With self-trade prevention on, the order's own resting orders don't
+    /// count: they are skipped (`CancelOldest`) or end the count, since
+    /// reaching them cancels quantity of the incoming order.
+    fn matchable_quantity(&self, order: &Order) -> u32 {
+        let levels: Box<dyn Iterator<Item = (&u32, &PriceLevel)>> = match order.side {
+            Side::Buy => Box::new(self.asks.iter()),
+            Side::Sell => Box::new(self.bids.iter().rev()),
+        };
+
+        let protection_price = self.market_protection_price(order);
+        let mut available: u32 = 0;
+        for (&price, level) in levels {
+            if !Self::crosses(order, price)
+                || Self::beyond(order.side, price, protection_price)
+                || self.outside_band(price)
+                || available >= order.remaining_qty
+            {
+                break;
             }
+            for resting in level.iter(&self.orders) {
+                let stp = order.self_trade_prevention;
+                if stp.is_enabled() && resting.user_id == order.user_id {
+                    if stp == SelfTradePrevention::CancelOldest {
+                        continue;
+                    }
+                    return available;
+                }
+                available = available.saturating_add(resting.remaining_qty);
+            }
+        }
+        available
+    }
+
+    /// Add a remaining limit order to the appropriate side of the book,
+    /// assigning it the next exchange order id.
+    ///
+    /// `replaces` is the exchange id of the order this one replaces
+    /// without having traded, if any (reported as an L3 `Replace`
+    /// instead of an `Add`).
+    fn add_to_book(&mut self, mut order: Order, replaces: Option<u64>) {
+        order.exchange_order_id = self.next_exchange_order_id;
+        self.next_exchange_order_id += 1;
+        order.refill_display();
+
+        if self.book_events && !order.is_hidden() {
+            let event = match replaces {
+                Some(old_order_id) => OrderBookEvent::Replace {
+                    symbol: self.symbol.clone(),
+                    old_order_id,
+                    new_order_id: order.exchange_order_id,
+                    price: order.price,
+                    quantity: order.displayed_qty(),
+                },
+                None => OrderBookEvent::Add {
+                    symbol: self.symbol.clone(),
+                    order_id: order.exchange_order_id,
+                    side: order.side,
+                    price: order.price,
+                    quantity: order.displayed_qty(),
+                },
+            };
+            self.pending_book_events.push(event);
+        }
+
+        self.note_level(order.side, order.price);
+        let (user_id, user_order_id) = (order.user_id, order.user_order_id);
+        let levels = match order.side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+        let level = levels.entry(order.price).or_default();
+
+        let slot = self.orders.insert(order);
+        level.push_back(&mut self.orders, slot);
+        self.order_index.insert(user_id, user_order_id, slot);
+    }
+
+    /// Unlink and remove a resting order by id. Returns the removed order,
+    /// if any.
+    ///
+    /// Like the linear search this replaced, an emptied level is left in
+    /// place (quoted with zero quantity) until matching reaches it.
+    fn remove_order(&mut self, user_id: u32, user_order_id: u32) -> Option<Order> {
+        let slot = self.order_index.remove(user_id, user_order_id)?;
+
+        let (side, price) = {
+            let order = self.orders.get(slot);
+            (order.side, order.price)
+        };
+        self.note_level(side, price);
+        let levels = match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+
+        if let Some(level) = levels.get_mut(&price) {
+            level.unlink(&mut self.orders, slot);
+        }
+
+        Some(self.orders.remove(slot))
+    }
+
+    /// Record an L3 `Cancel` (`remaining_qty == 0` for a delete).
+    fn record_cancel(&mut self, order_id: u64, cancelled_qty: u32, remaining_qty: u32) {
+        if !self.book_events {
+            return;
+        }
+        self.pending_book_events.push(OrderBookEvent::Cancel {
+            symbol: self.symbol.clone(),
+            order_id,
+            cancelled_qty,
+            remaining_qty,
+        });
+    }
+
+    /// Remember a level's quantity before the current operation changes it.
+    fn note_level(&mut self, side: Side, price: u32) {
+        if !self.depth_updates {
+            return;
         }
+        let levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        let before = levels.get(&price).map_or(0, PriceLevel::total_quantity);
+        self.depth_dirty.entry((side_key(side), price)).or_insert(before);
+    }
+
+    /// Turn the levels touched by the current operation into a
+    /// `DepthUpdate`, if any of them actually changed.
+    fn take_depth_update(&mut self) -> Option<OutputMessage> {
+        if self.depth_dirty.is_empty() {
+            return None;
+        }
+
+        let dirty = std::mem::take(&mut self.depth_dirty);
+        let mut events = Vec::new();
+        for ((side_code, price), before) in dirty {
+            let (side, levels) = if side_code == 0 {
+                (Side::Buy, &self.bids)
+            } else {
+                (Side::Sell, &self.asks)
+            };
+            let after = levels.get(&price).map_or(0, PriceLevel::total_quantity);
+            let action = match (before, after) {
+                (b, a) if b == a => continue,
+                (0, _) => DepthAction::Add,
+                (_, 0) => DepthAction::Delete,
+                _ => DepthAction::Change,
+            };
+            events.push(DepthEvent {
+                action,
+                side,
+                price,
+                quantity: after,
+            });
+        }
+
+        if events.is_empty() {
+            return None;
+        }
+        Some(OutputMessage::DepthUpdate(DepthUpdate {
+            symbol: self.symbol.clone(),
+            snapshot: false,
+            events,
+        }))
     }
 
     /// Check for top-of-book changes and emit appropriate events.
@@ -484,10 +1694,12 @@ impl OrderBook {
 
         outputs
     }
+}
 
-    /// Sum of remaining_qty across all orders at one price level.
-    fn total_quantity_at_price(orders: &VecDeque<Order>) -> u32 {
-        orders.iter().map(|o| o.remaining_qty).sum()
+/// Sort key for a side in `depth_dirty` (bids before asks).
+fn side_key(side: Side) -> u8 {
+    match side {
+        Side::Buy => 0,
+        Side::Sell => 1,
     }
 }
-
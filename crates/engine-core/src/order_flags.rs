@@ -0,0 +1,81 @@
+//! Execution flags for new orders.
+//!
+//! - `POST_ONLY`: the order must add liquidity. If it would trade on
+//!   arrival it is rejected.
+//! - `POST_ONLY_REPRICE`: like `POST_ONLY`, but a crossing order is
+//!   repriced one tick behind the opposite best price and rests there.
+//! - `HIDDEN`: the order rests and trades normally (keeping its time
+//!   priority) but is left out of top-of-book, depth and the L3 feed.
+//!
+//! Post-only flags apply to plain limit orders only, and at most one of
+//! them may be set; a hidden order can't also be an iceberg.
+
+use std::ops::BitOr;
+
+/// Set of order flags; the bit values are part of the binary wire
+/// protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct OrderFlags(u8);
+
+impl OrderFlags {
+    /// No flags.
+    pub const NONE: OrderFlags = OrderFlags(0);
+    /// Reject instead of taking liquidity.
+    pub const POST_ONLY: OrderFlags = OrderFlags(0x01);
+    /// Reprice one tick away instead of taking liquidity.
+    pub const POST_ONLY_REPRICE: OrderFlags = OrderFlags(0x02);
+    /// Don't show the resting order.
+    pub const HIDDEN: OrderFlags = OrderFlags(0x04);
+
+    const ALL: u8 = 0x07;
+
+    /// Flags from their wire bits; `None` if an unknown bit is set.
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        (bits & !Self::ALL == 0).then_some(OrderFlags(bits))
+    }
+
+    /// Wire bits.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Returns `true` if every flag in `other` is set.
+    pub fn contains(self, other: OrderFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if either post-only flag is set.
+    pub fn is_post_only(self) -> bool {
+        self.0 & (Self::POST_ONLY.0 | Self::POST_ONLY_REPRICE.0) != 0
+    }
+
+    /// Returns `true` for a hidden order.
+    pub fn is_hidden(self) -> bool {
+        self.contains(Self::HIDDEN)
+    }
+
+    /// Parse the CSV form: `-` for none, otherwise flag names joined by
+    /// `|` (`POST_ONLY`, `POST_ONLY_REPRICE`, `HIDDEN`).
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        if s == "-" {
+            return Some(Self::NONE);
+        }
+        s.split('|').try_fold(Self::NONE, |flags, name| {
+            let flag = match name {
+                "POST_ONLY" => Self::POST_ONLY,
+                "POST_ONLY_REPRICE" => Self::POST_ONLY_REPRICE,
+                "HIDDEN" => Self::HIDDEN,
+                _ => return None,
+            };
+            Some(flags | flag)
+        })
+    }
+}
+
+impl BitOr for OrderFlags {
+    type Output = OrderFlags;
+
+    fn bitor(self, rhs: OrderFlags) -> OrderFlags {
+        OrderFlags(self.0 | rhs.0)
+    }
+}
@@ -1,4 +1,4 @@
-//! Order type (Market vs Limit).
+//! Order type (Market vs Limit, plus their stop variants).
 //!
 //! Mirrors your C++ `OrderType`:
 //! ```cpp
@@ -7,10 +7,34 @@
 //!     LIMIT    // price > 0
 //! };
 //! ```
+//!
+//! Stop orders (`stop_price > 0`) wait in the book's trigger book until
+//! the last trade price reaches their stop price, then enter matching as
+//! the plain market or limit order they wrap.
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum OrderType {
     Market,
     Limit,
+    /// Becomes a `Market` order when triggered (price = 0).
+    StopMarket,
+    /// Becomes a `Limit` order when triggered (price > 0).
+    StopLimit,
 }
 
+impl OrderType {
+    /// Returns `true` for orders that wait for a trigger.
+    pub fn is_stop(self) -> bool {
+        matches!(self, OrderType::StopMarket | OrderType::StopLimit)
+    }
+
+    /// The type an order has once its stop is triggered (unchanged for
+    /// non-stop types).
+    pub fn triggered(self) -> Self {
+        match self {
+            OrderType::StopMarket => OrderType::Market,
+            OrderType::StopLimit => OrderType::Limit,
+            other => other,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Order storage for the order book: a slot arena plus intrusive
+//! FIFO price levels.
+//!
+//! The C++ book keeps `std::list` iterators in a hash map so a cancel
+//! can unlink an order without searching. We get the same effect
+//! safely by storing every resting order in an [`OrderArena`] slot
+//! that never moves while the order is live, and threading each price
+//! level through those slots as a doubly-linked list:
+//!
+//! - `OrderArena`: `Vec` of slots + free list; slot indices are stable.
+//! - `PriceLevel`: head/tail slot indices, order count and cached
+//!   aggregate quantity for one price.
+//!
+//! Given a slot index, unlinking from its level is O(1).
+//!
+//! Level quantities count only what is displayed, so the hidden reserve
+//! of an iceberg order never shows up in top-of-book or depth.
+
+use crate::order::Order;
+
+/// A resting order plus its links within its price level.
+#[derive(Debug, Clone)]
+struct OrderNode {
+    order: Order,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// Slot arena holding every resting order of one book.
+///
+/// Slot indices stay valid until the order is removed, after which the
+/// slot is recycled for the next insert.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct OrderArena {
+    nodes: Vec<Option<OrderNode>>,
+    free: Vec<usize>,
+}
+
+impl OrderArena {
+    /// Store an (unlinked) order and return its slot index.
+    pub(crate) fn insert(&mut self, order: Order) -> usize {
+        let node = OrderNode {
+            order,
+            prev: None,
+            next: None,
+        };
+        match self.free.pop() {
+            Some(slot) => {
+                self.nodes[slot] = Some(node);
+                slot
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Remove the order at `slot`, freeing the slot.
+    ///
+    /// The order must already have been unlinked from its level.
+    pub(crate) fn remove(&mut self, slot: usize) -> Order {
+        let node = self.nodes[slot].take().expect("removing an empty arena slot");
+        self.free.push(slot);
+        node.order
+    }
+
+    pub(crate) fn get(&self, slot: usize) -> &Order {
+        &self.node(slot).order
+    }
+
+    pub(crate) fn get_mut(&mut self, slot: usize) -> &mut Order {
+        &mut self.node_mut(slot).order
+    }
+
+    /// Drop every stored order.
+    pub(crate) fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+    }
+
+    fn node(&self, slot: usize) -> &OrderNode {
+        self.nodes[slot].as_ref().expect("dangling arena slot")
+    }
+
+    fn node_mut(&mut self, slot: usize) -> &mut OrderNode {
+        self.nodes[slot].as_mut().expect("dangling arena slot")
+    }
+}
+
+/// FIFO queue of orders resting at a single price.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct PriceLevel {
+    head: Option<usize>,
+    tail: Option<usize>,
+    len: usize,
+    /// Sum of `displayed_qty()` over the level, kept up to date on
+    /// push / unlink / fill.
+    total_qty: u32,
+}
+
+impl PriceLevel {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Slot index of the oldest order at this price.
+    pub(crate) fn front(&self) -> Option<usize> {
+        self.head
+    }
+
+    /// Aggregate displayed quantity at this price.
+    pub(crate) fn total_quantity(&self) -> u32 {
+        self.total_qty
+    }
+
+    /// Append the order at `slot` to the back of the queue.
+    pub(crate) fn push_back(&mut self, arena: &mut OrderArena, slot: usize) {
+        let old_tail = self.tail;
+        {
+            let node = arena.node_mut(slot);
+            node.prev = old_tail;
+            node.next = None;
+            self.total_qty += node.order.displayed_qty();
+        }
+        match old_tail {
+            Some(t) => arena.node_mut(t).next = Some(slot),
+            None => self.head = Some(slot),
+        }
+        self.tail = Some(slot);
+        self.len += 1;
+    }
+
+    /// Unlink the order at `slot` from this level (O(1)).
+    ///
+    /// The order stays in the arena; the caller decides whether to
+    /// remove it or relink it elsewhere.
+    pub(crate) fn unlink(&mut self, arena: &mut OrderArena, slot: usize) {
+        let (prev, next, qty) = {
+            let node = arena.node_mut(slot);
+            let links = (node.prev, node.next, node.order.displayed_qty());
+            node.prev = None;
+            node.next = None;
+            links
+        };
+        match prev {
+            Some(p) => arena.node_mut(p).next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(n) => arena.node_mut(n).prev = prev,
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+        self.total_qty -= qty;
+    }
+
+    /// Account for `qty` of displayed quantity having been filled or
+    /// cancelled from an order at this level.
+    pub(crate) fn reduce_quantity(&mut self, qty: u32) {
+        self.total_qty -= qty;
+    }
+
+    /// Iterate the orders at this level in time priority.
+    pub(crate) fn iter<'a>(&self, arena: &'a OrderArena) -> impl Iterator<Item = &'a Order> + 'a {
+        let mut cursor = self.head;
+        std::iter::from_fn(move || {
+            let slot = cursor?;
+            let node = arena.node(slot);
+            cursor = node.next;
+            Some(&node.order)
+        })
+    }
+}
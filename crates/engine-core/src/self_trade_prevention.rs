@@ -0,0 +1,73 @@
+//! Self-trade prevention (STP).
+//!
+//! When an incoming order would trade with a resting order of the same
+//! `user_id`, the incoming order's mode decides what happens instead of
+//! a trade:
+//! - `CancelNewest`: cancel the rest of the incoming order.
+//! - `CancelOldest`: cancel the resting order and keep matching.
+//! - `CancelBoth`: cancel both.
+//! - `DecrementAndCancel`: take the smaller quantity off both; whichever
+//!   order is left with nothing is cancelled, the other carries on.
+//!
+//! Every prevented match is reported with `SelfTradeCancel` outputs, one
+//! per order touched, never with a `Trade`.
+
+/// Self-trade prevention mode of an order.
+///
+/// The numeric values are part of the binary wire protocol.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SelfTradePrevention {
+    /// No prevention (or: use the user's default, if one is configured).
+    #[default]
+    None = 0,
+    /// Cancel the incoming order.
+    CancelNewest = 1,
+    /// Cancel the resting order.
+    CancelOldest = 2,
+    /// Cancel both orders.
+    CancelBoth = 3,
+    /// Reduce both orders by the smaller quantity.
+    DecrementAndCancel = 4,
+}
+
+impl SelfTradePrevention {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(SelfTradePrevention::None),
+            1 => Some(SelfTradePrevention::CancelNewest),
+            2 => Some(SelfTradePrevention::CancelOldest),
+            3 => Some(SelfTradePrevention::CancelBoth),
+            4 => Some(SelfTradePrevention::DecrementAndCancel),
+            _ => None,
+        }
+    }
+
+    /// Text code used by the CSV protocol and server configuration.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SelfTradePrevention::None => "NONE",
+            SelfTradePrevention::CancelNewest => "CANCEL_NEWEST",
+            SelfTradePrevention::CancelOldest => "CANCEL_OLDEST",
+            SelfTradePrevention::CancelBoth => "CANCEL_BOTH",
+            SelfTradePrevention::DecrementAndCancel => "DECREMENT",
+        }
+    }
+
+    /// Parse the text code (case-sensitive).
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        match s {
+            "NONE" => Some(SelfTradePrevention::None),
+            "CANCEL_NEWEST" => Some(SelfTradePrevention::CancelNewest),
+            "CANCEL_OLDEST" => Some(SelfTradePrevention::CancelOldest),
+            "CANCEL_BOTH" => Some(SelfTradePrevention::CancelBoth),
+            "DECREMENT" => Some(SelfTradePrevention::DecrementAndCancel),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` unless this is `None`.
+    pub fn is_enabled(self) -> bool {
+        self != SelfTradePrevention::None
+    }
+}
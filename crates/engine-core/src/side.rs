@@ -25,6 +25,14 @@ impl Side {
         }
     }
 
+    /// The other side of the book.
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+
     /// Try to parse from a char (`'B'` / `'S'`, case-sensitive).
     pub fn from_char(c: char) -> Option<Self> {
         match c {
@@ -0,0 +1,497 @@
+//! Versioned binary snapshots of engine state.
+//!
+//! A snapshot holds everything needed to continue exactly where the
+//! engine left off: every book's resting orders in time priority (with
+//! `timestamp_ns` and exchange order ids), its pending stop orders and
+//! last trade price, each book's id counter and top-of-book cache, its
+//! auction state and last published indicative uncross, the
+//! `order_to_symbol` map, each symbol's trading state and queued
+//! orders, when interrupted symbols resume, the execution report
+//! state (next exec id, each live order's filled and open quantity),
+//! and which users a kill switch blocks. It also records the
+//! journal sequence number it covers, so recovery is "restore snapshot,
+//! then replay journal records after that sequence".
+//!
+//! Layout (all integers big-endian, strings as u8 length + UTF-8):
+//!
+//! ```text
+//! magic          b"MESNAP"
+//! version        u16 (SNAPSHOT_VERSION)
+//! journal_seq    u64
+//! book_count     u32, then per book (sorted by symbol):
+//!   symbol
+//!   next_exchange_order_id u64
+//!   prev TOB     u32 bid_price, bid_qty, ask_price, ask_qty
+//!   last_trade_price u32 (0 = none)
+//!   in_auction   u8 (0 / 1)
+//!   indicative   u32 price, volume, imbalance_qty, u8 imbalance_side
+//!   order_count  u32, then per order (bids best-first, then asks,
+//!                FIFO within a level):
+//!     user_id u32, user_order_id u32, price u32, quantity u32,
+//!     remaining_qty u32, side u8, time_in_force u8,
+//!     timestamp_ns u64, exchange_order_id u64,
+//!     display_qty u32 (0 = not an iceberg), visible_qty u32, flags u8,
+//!     self_trade_prevention u8
+//!   stop_count   u32, then per pending stop (in trigger order):
+//!     the same fields as an order, then stop_price u32
+//! map_count      u32, then per entry (sorted):
+//!   user_id u32, user_order_id u32, symbol
+//! state_count    u32, then per symbol not `Continuous` (sorted):
+//!   symbol, trading_state u8
+//! queued_count   u32, then per queued order (by symbol, then arrival):
+//!   symbol, user_id u32, user_order_id u32, price u32, quantity u32,
+//!   side u8, time_in_force u8, stop_price u32, display_qty u32,
+//!   flags u8, self_trade_prevention u8
+//! resume_count   u32, then per symbol in a volatility interruption
+//!                (sorted):
+//!   symbol, resume_ns u64
+//! next_exec_id   u64
+//! progress_count u32, then per live order with execution reports
+//!                (sorted):
+//!   user_id u32, user_order_id u32, cum_qty u32, leaves_qty u32
+//! all_disabled   u8 (0 / 1; every user blocked)
+//! disabled_count u32, then per blocked user (sorted):
+//!   user_id u32
+//! ```
+
+use std::io::{self, Read, Write};
+
+use crate::auction::Uncross;
+use crate::execution::OrderProgress;
+use crate::messages::NewOrder;
+use crate::order::Order;
+use crate::order_flags::OrderFlags;
+use crate::order_type::OrderType;
+use crate::self_trade_prevention::SelfTradePrevention;
+use crate::side::Side;
+use crate::time_in_force::TimeInForce;
+use crate::top_of_book::TopOfBookSnapshot;
+use crate::trading_state::TradingState;
+
+/// Leading bytes of every snapshot file.
+pub const SNAPSHOT_MAGIC: &[u8; 6] = b"MESNAP";
+
+/// Current snapshot format version.
+///
+/// History:
+/// - 1: initial layout.
+/// - 2: last trade price and pending stop orders per book.
+/// - 3: iceberg display and visible quantities per order.
+/// - 4: order flags (post-only, hidden) per order.
+/// - 5: self-trade prevention mode per order.
+/// - 6: auction state and published indicative uncross per book.
+/// - 7: trading states and queued orders.
+/// - 8: volatility interruption resume times.
+/// - 9: execution report state.
+/// - 10: kill switches.
+pub const SNAPSHOT_VERSION: u16 = 10;
+
+/// Everything needed to rebuild one `OrderBook`.
+#[derive(Debug, Clone)]
+pub(crate) struct BookState {
+    pub(crate) symbol: String,
+    pub(crate) next_exchange_order_id: u64,
+    pub(crate) prev_top_of_book: TopOfBookSnapshot,
+    pub(crate) last_trade_price: u32,
+    /// Resting orders, bids best-first then asks best-first, FIFO
+    /// within a level.
+    pub(crate) orders: Vec<Order>,
+    /// Pending stop orders in trigger order.
+    pub(crate) stop_orders: Vec<Order>,
+    pub(crate) in_auction: bool,
+    pub(crate) published_indicative: Uncross,
+}
+
+/// Decoded snapshot contents.
+#[derive(Debug, Clone)]
+pub(crate) struct EngineState {
+    pub(crate) journal_seq: u64,
+    pub(crate) books: Vec<BookState>,
+    pub(crate) order_to_symbol: Vec<((u32, u32), String)>,
+    pub(crate) trading_states: Vec<(String, TradingState)>,
+    /// Queued new orders, by symbol then arrival.
+    pub(crate) queued_orders: Vec<NewOrder>,
+    /// Symbol -> engine time its volatility interruption ends.
+    pub(crate) volatility_resumes: Vec<(String, u64)>,
+    pub(crate) next_exec_id: u64,
+    /// Filled and open quantity per live order, sorted.
+    pub(crate) order_progress: Vec<((u32, u32), OrderProgress)>,
+    pub(crate) all_users_disabled: bool,
+    /// Users blocked by a kill switch of their own, sorted.
+    pub(crate) disabled_users: Vec<u32>,
+}
+
+pub(crate) fn write_state<W: Write>(state: &EngineState, mut w: W) -> io::Result<()> {
+    w.write_all(SNAPSHOT_MAGIC)?;
+    w.write_all(&SNAPSHOT_VERSION.to_be_bytes())?;
+    w.write_all(&state.journal_seq.to_be_bytes())?;
+
+    write_len(&mut w, state.books.len())?;
+    for book in &state.books {
+        write_str(&mut w, &book.symbol)?;
+        w.write_all(&book.next_exchange_order_id.to_be_bytes())?;
+        let tob = &book.prev_top_of_book;
+        for v in [tob.bid_price, tob.bid_quantity, tob.ask_price, tob.ask_quantity] {
+            w.write_all(&v.to_be_bytes())?;
+        }
+        w.write_all(&book.last_trade_price.to_be_bytes())?;
+        w.write_all(&[book.in_auction as u8])?;
+        let indicative = &book.published_indicative;
+        for v in [indicative.price, indicative.volume, indicative.imbalance_qty] {
+            w.write_all(&v.to_be_bytes())?;
+        }
+        w.write_all(&[side_to_u8(indicative.imbalance_side)])?;
+
+        write_len(&mut w, book.orders.len())?;
+        for o in &book.orders {
+            write_order(&mut w, o)?;
+        }
+
+        write_len(&mut w, book.stop_orders.len())?;
+        for o in &book.stop_orders {
+            write_order(&mut w, o)?;
+            w.write_all(&o.stop_price.to_be_bytes())?;
+        }
+    }
+
+    write_len(&mut w, state.order_to_symbol.len())?;
+    for ((user_id, user_order_id), symbol) in &state.order_to_symbol {
+        w.write_all(&user_id.to_be_bytes())?;
+        w.write_all(&user_order_id.to_be_bytes())?;
+        write_str(&mut w, symbol)?;
+    }
+
+    write_len(&mut w, state.trading_states.len())?;
+    for (symbol, trading_state) in &state.trading_states {
+        write_str(&mut w, symbol)?;
+        w.write_all(&[*trading_state as u8])?;
+    }
+
+    write_len(&mut w, state.queued_orders.len())?;
+    for o in &state.queued_orders {
+        write_str(&mut w, &o.symbol)?;
+        for v in [o.user_id, o.user_order_id, o.price, o.quantity] {
+            w.write_all(&v.to_be_bytes())?;
+        }
+        w.write_all(&[side_to_u8(o.side), tif_to_u8(o.time_in_force)])?;
+        w.write_all(&o.stop_price.to_be_bytes())?;
+        w.write_all(&o.display_qty.to_be_bytes())?;
+        w.write_all(&[o.flags.bits(), o.self_trade_prevention as u8])?;
+    }
+
+    write_len(&mut w, state.volatility_resumes.len())?;
+    for (symbol, resume_ns) in &state.volatility_resumes {
+        write_str(&mut w, symbol)?;
+        w.write_all(&resume_ns.to_be_bytes())?;
+    }
+
+    w.write_all(&state.next_exec_id.to_be_bytes())?;
+    write_len(&mut w, state.order_progress.len())?;
+    for ((user_id, user_order_id), progress) in &state.order_progress {
+        for v in [*user_id, *user_order_id, progress.cum_qty, progress.leaves_qty] {
+            w.write_all(&v.to_be_bytes())?;
+        }
+    }
+
+    w.write_all(&[state.all_users_disabled as u8])?;
+    write_len(&mut w, state.disabled_users.len())?;
+    for user_id in &state.disabled_users {
+        w.write_all(&user_id.to_be_bytes())?;
+    }
+
+    w.flush()
+}
+
+pub(crate) fn read_state<R: Read>(mut r: R) -> io::Result<EngineState> {
+    let mut magic = [0u8; 6];
+    r.read_exact(&mut magic)?;
+    if &magic != SNAPSHOT_MAGIC {
+        return Err(invalid("not a snapshot file"));
+    }
+    let version = u16::from_be_bytes(read_array(&mut r)?);
+    if version != SNAPSHOT_VERSION {
+        return Err(invalid(&format!("unsupported snapshot version {}", version)));
+    }
+    let journal_seq = read_u64(&mut r)?;
+
+    let book_count = read_u32(&mut r)?;
+    let mut books = Vec::new();
+    for _ in 0..book_count {
+        let symbol = read_str(&mut r)?;
+        let next_exchange_order_id = read_u64(&mut r)?;
+        let prev_top_of_book = TopOfBookSnapshot::new(
+            read_u32(&mut r)?,
+            read_u32(&mut r)?,
+            read_u32(&mut r)?,
+            read_u32(&mut r)?,
+        );
+        let last_trade_price = read_u32(&mut r)?;
+        let in_auction = match read_array(&mut r)? {
+            [0] => false,
+            [1] => true,
+            _ => return Err(invalid("bad auction state")),
+        };
+        let published_indicative = Uncross {
+            price: read_u32(&mut r)?,
+            volume: read_u32(&mut r)?,
+            imbalance_qty: read_u32(&mut r)?,
+            imbalance_side: side_from_u8(read_array::<_, 1>(&mut r)?[0])?,
+        };
+
+        let order_count = read_u32(&mut r)?;
+        let mut orders = Vec::new();
+        for _ in 0..order_count {
+            let order = read_order(&mut r, &symbol)?;
+            if order.price == 0 {
+                return Err(invalid("resting order without a price"));
+            }
+            orders.push(order);
+        }
+
+        let stop_count = read_u32(&mut r)?;
+        let mut stop_orders = Vec::new();
+        for _ in 0..stop_count {
+            let mut order = read_order(&mut r, &symbol)?;
+            order.stop_price = read_u32(&mut r)?;
+            if order.stop_price == 0 {
+                return Err(invalid("stop order without a stop price"));
+            }
+            order.order_type = if order.price == 0 {
+                OrderType::StopMarket
+            } else {
+                OrderType::StopLimit
+            };
+            stop_orders.push(order);
+        }
+
+        books.push(BookState {
+            symbol,
+            next_exchange_order_id,
+            prev_top_of_book,
+            last_trade_price,
+            orders,
+            stop_orders,
+            in_auction,
+            published_indicative,
+        });
+    }
+
+    let map_count = read_u32(&mut r)?;
+    let mut order_to_symbol = Vec::new();
+    for _ in 0..map_count {
+        let key = (read_u32(&mut r)?, read_u32(&mut r)?);
+        order_to_symbol.push((key, read_str(&mut r)?));
+    }
+
+    let state_count = read_u32(&mut r)?;
+    let mut trading_states = Vec::new();
+    for _ in 0..state_count {
+        let symbol = read_str(&mut r)?;
+        let [state] = read_array(&mut r)?;
+        let state = TradingState::from_u8(state).ok_or_else(|| invalid("unknown trading state"))?;
+        trading_states.push((symbol, state));
+    }
+
+    let queued_count = read_u32(&mut r)?;
+    let mut queued_orders = Vec::new();
+    for _ in 0..queued_count {
+        queued_orders.push(read_new_order(&mut r)?);
+    }
+
+    let resume_count = read_u32(&mut r)?;
+    let mut volatility_resumes = Vec::new();
+    for _ in 0..resume_count {
+        let symbol = read_str(&mut r)?;
+        volatility_resumes.push((symbol, read_u64(&mut r)?));
+    }
+
+    let next_exec_id = read_u64(&mut r)?;
+    let progress_count = read_u32(&mut r)?;
+    let mut order_progress = Vec::new();
+    for _ in 0..progress_count {
+        let key = (read_u32(&mut r)?, read_u32(&mut r)?);
+        let progress = OrderProgress {
+            cum_qty: read_u32(&mut r)?,
+            leaves_qty: read_u32(&mut r)?,
+        };
+        order_progress.push((key, progress));
+    }
+
+    let all_users_disabled = match read_array(&mut r)? {
+        [0] => false,
+        [1] => true,
+        _ => return Err(invalid("bad kill switch state")),
+    };
+    let disabled_count = read_u32(&mut r)?;
+    let mut disabled_users = Vec::new();
+    for _ in 0..disabled_count {
+        disabled_users.push(read_u32(&mut r)?);
+    }
+
+    Ok(EngineState {
+        journal_seq,
+        books,
+        order_to_symbol,
+        trading_states,
+        queued_orders,
+        volatility_resumes,
+        next_exec_id,
+        order_progress,
+        all_users_disabled,
+        disabled_users,
+    })
+}
+
+// -----------------------------------------------------------------------------
+// Helpers
+// -----------------------------------------------------------------------------
+
+/// Write the fields shared by resting and stop orders.
+fn write_order<W: Write>(w: &mut W, o: &Order) -> io::Result<()> {
+    for v in [o.user_id, o.user_order_id, o.price, o.quantity, o.remaining_qty] {
+        w.write_all(&v.to_be_bytes())?;
+    }
+    w.write_all(&[side_to_u8(o.side), tif_to_u8(o.time_in_force)])?;
+    w.write_all(&o.timestamp_ns.to_be_bytes())?;
+    w.write_all(&o.exchange_order_id.to_be_bytes())?;
+    w.write_all(&o.display_qty.to_be_bytes())?;
+    w.write_all(&o.visible_qty.to_be_bytes())?;
+    w.write_all(&[o.flags.bits(), o.self_trade_prevention as u8])
+}
+
+/// Read the fields written by `write_order` as a limit order.
+fn read_order<R: Read>(r: &mut R, symbol: &str) -> io::Result<Order> {
+    let user_id = read_u32(r)?;
+    let user_order_id = read_u32(r)?;
+    let price = read_u32(r)?;
+    let quantity = read_u32(r)?;
+    let remaining_qty = read_u32(r)?;
+    let [side, tif] = read_array(r)?;
+    let timestamp_ns = read_u64(r)?;
+    let exchange_order_id = read_u64(r)?;
+    let display_qty = read_u32(r)?;
+    let visible_qty = read_u32(r)?;
+    let [flags, stp] = read_array(r)?;
+    let flags = OrderFlags::from_bits(flags).ok_or_else(|| invalid("unknown order flags"))?;
+    let self_trade_prevention = SelfTradePrevention::from_u8(stp)
+        .ok_or_else(|| invalid("unknown self-trade prevention mode"))?;
+
+    if remaining_qty == 0 || remaining_qty > quantity || visible_qty > remaining_qty {
+        return Err(invalid("order with impossible quantity"));
+    }
+    Ok(Order {
+        user_id,
+        user_order_id,
+        symbol: symbol.to_string(),
+        price,
+        quantity,
+        remaining_qty,
+        side: side_from_u8(side)?,
+        order_type: OrderType::Limit,
+        time_in_force: tif_from_u8(tif)?,
+        stop_price: 0,
+        display_qty,
+        visible_qty,
+        flags,
+        self_trade_prevention,
+        timestamp_ns,
+        exchange_order_id,
+    })
+}
+
+/// Read a queued new order.
+fn read_new_order<R: Read>(r: &mut R) -> io::Result<NewOrder> {
+    let symbol = read_str(r)?;
+    let user_id = read_u32(r)?;
+    let user_order_id = read_u32(r)?;
+    let price = read_u32(r)?;
+    let quantity = read_u32(r)?;
+    let [side, tif] = read_array(r)?;
+    let stop_price = read_u32(r)?;
+    let display_qty = read_u32(r)?;
+    let [flags, stp] = read_array(r)?;
+    Ok(NewOrder {
+        user_id,
+        symbol,
+        price,
+        quantity,
+        side: side_from_u8(side)?,
+        user_order_id,
+        time_in_force: tif_from_u8(tif)?,
+        stop_price,
+        display_qty,
+        flags: OrderFlags::from_bits(flags).ok_or_else(|| invalid("unknown order flags"))?,
+        self_trade_prevention: SelfTradePrevention::from_u8(stp)
+            .ok_or_else(|| invalid("unknown self-trade prevention mode"))?,
+    })
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("corrupt snapshot: {}", what))
+}
+
+fn write_len<W: Write>(w: &mut W, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| invalid("too many entries"))?;
+    w.write_all(&len.to_be_bytes())
+}
+
+fn write_str<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    let len = u8::try_from(s.len()).map_err(|_| invalid("symbol too long"))?;
+    w.write_all(&[len])?;
+    w.write_all(s.as_bytes())
+}
+
+fn read_array<R: Read, const N: usize>(r: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    Ok(u32::from_be_bytes(read_array(r)?))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    Ok(u64::from_be_bytes(read_array(r)?))
+}
+
+fn read_str<R: Read>(r: &mut R) -> io::Result<String> {
+    let [len] = read_array(r)?;
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| invalid("symbol is not UTF-8"))
+}
+
+fn side_to_u8(side: Side) -> u8 {
+    match side {
+        Side::Buy => 0,
+        Side::Sell => 1,
+    }
+}
+
+fn side_from_u8(v: u8) -> io::Result<Side> {
+    match v {
+        0 => Ok(Side::Buy),
+        1 => Ok(Side::Sell),
+        _ => Err(invalid("bad side")),
+    }
+}
+
+fn tif_to_u8(tif: TimeInForce) -> u8 {
+    match tif {
+        TimeInForce::Day => 0,
+        TimeInForce::Gtc => 1,
+        TimeInForce::Ioc => 2,
+        TimeInForce::Fok => 3,
+    }
+}
+
+fn tif_from_u8(v: u8) -> io::Result<TimeInForce> {
+    match v {
+        0 => Ok(TimeInForce::Day),
+        1 => Ok(TimeInForce::Gtc),
+        2 => Ok(TimeInForce::Ioc),
+        3 => Ok(TimeInForce::Fok),
+        _ => Err(invalid("bad time-in-force")),
+    }
+}
@@ -0,0 +1,154 @@
+//! Trigger book: stop orders waiting for the market to reach them.
+//!
+//! - Buy stops trigger when the last trade price rises to (or above)
+//!   their stop price; the lowest stop price triggers first.
+//! - Sell stops trigger when the last trade price falls to (or below)
+//!   their stop price; the highest stop price triggers first.
+//! - Stops with the same stop price trigger in arrival order.
+//!
+//! Pending stops are not part of the visible book: they don't count
+//! toward top-of-book, depth or the L3 feed until triggered.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::order::Order;
+use crate::side::Side;
+use crate::user_index::UserIndex;
+
+/// Pending stop orders of one book.
+#[derive(Debug, Default)]
+pub(crate) struct StopBook {
+    /// Stop price -> buy stops in arrival order.
+    buys: BTreeMap<u32, VecDeque<Order>>,
+    /// Stop price -> sell stops in arrival order.
+    sells: BTreeMap<u32, VecDeque<Order>>,
+    /// `(user_id, user_order_id)` -> (side, stop price).
+    index: UserIndex<(Side, u32)>,
+}
+
+impl StopBook {
+    /// Add a pending stop order at the back of its stop price.
+    pub(crate) fn insert(&mut self, order: Order) {
+        self.index
+            .insert(order.user_id, order.user_order_id, (order.side, order.stop_price));
+        self.side_mut(order.side)
+            .entry(order.stop_price)
+            .or_default()
+            .push_back(order);
+    }
+
+    /// Remove a pending stop order by id.
+    pub(crate) fn remove(&mut self, user_id: u32, user_order_id: u32) -> Option<Order> {
+        let (side, stop_price) = self.index.remove(user_id, user_order_id)?;
+        let stops = self.side_mut(side);
+        let queue = stops.get_mut(&stop_price)?;
+        let pos = queue
+            .iter()
+            .position(|o| o.user_id == user_id && o.user_order_id == user_order_id)?;
+        let order = queue.remove(pos);
+        if queue.is_empty() {
+            stops.remove(&stop_price);
+        }
+        order
+    }
+
+    /// Returns `true` if `(user_id, user_order_id)` is a pending stop.
+    pub(crate) fn contains(&self, user_id: u32, user_order_id: u32) -> bool {
+        self.index.contains(user_id, user_order_id)
+    }
+
+    /// Number of pending stop orders.
+    pub(crate) fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns `true` if a stop on `side` at `stop_price` would trigger
+    /// at `last_trade_price` (`0` = nothing has traded yet).
+    pub(crate) fn is_triggered(side: Side, stop_price: u32, last_trade_price: u32) -> bool {
+        last_trade_price != 0
+            && match side {
+                Side::Buy => last_trade_price >= stop_price,
+                Side::Sell => last_trade_price <= stop_price,
+            }
+    }
+
+    /// Remove and return the next stop triggered by `last_trade_price`.
+    ///
+    /// When both sides have a triggered stop, the one that arrived first
+    /// goes first (buy on a tie).
+    pub(crate) fn pop_triggered(&mut self, last_trade_price: u32) -> Option<Order> {
+        let buy = self
+            .buys
+            .iter()
+            .next()
+            .filter(|(&stop, _)| Self::is_triggered(Side::Buy, stop, last_trade_price))
+            .map(|(&stop, queue)| (stop, queue[0].timestamp_ns));
+        let sell = self
+            .sells
+            .iter()
+            .next_back()
+            .filter(|(&stop, _)| Self::is_triggered(Side::Sell, stop, last_trade_price))
+            .map(|(&stop, queue)| (stop, queue[0].timestamp_ns));
+
+        let (side, stop_price) = match (buy, sell) {
+            (Some((b, b_ts)), Some((_, s_ts))) if b_ts <= s_ts => (Side::Buy, b),
+            (_, Some((s, _))) => (Side::Sell, s),
+            (Some((b, _)), None) => (Side::Buy, b),
+            (None, None) => return None,
+        };
+
+        let stops = self.side_mut(side);
+        let queue = stops.get_mut(&stop_price)?;
+        let order = queue.pop_front()?;
+        if queue.is_empty() {
+            stops.remove(&stop_price);
+        }
+        self.index.remove(order.user_id, order.user_order_id);
+        Some(order)
+    }
+
+    /// Every pending stop: buys in trigger order, then sells in trigger
+    /// order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Order> {
+        self.buys
+            .values()
+            .flatten()
+            .chain(self.sells.values().rev().flatten())
+    }
+
+    /// The pending stops of `user_id` that `filter` accepts (given side
+    /// and stop price), in [`iter`](Self::iter) order. Only the stop
+    /// prices the user has stops at are visited.
+    pub(crate) fn user_orders(&self, user_id: u32, filter: impl Fn(Side, u32) -> bool) -> Vec<&Order> {
+        let mut buys = BTreeSet::new();
+        let mut sells = BTreeSet::new();
+        for (_, &(side, stop_price)) in self.index.user_orders(user_id) {
+            if filter(side, stop_price) {
+                match side {
+                    Side::Buy => buys.insert(stop_price),
+                    Side::Sell => sells.insert(stop_price),
+                };
+            }
+        }
+        let buys = buys.into_iter().filter_map(|stop_price| self.buys.get(&stop_price));
+        let sells = sells.into_iter().rev().filter_map(|stop_price| self.sells.get(&stop_price));
+        buys.chain(sells)
+            .flatten()
+            .filter(|o| o.user_id == user_id)
+            .collect()
+    }
+
+    /// Drop every pending stop.
+    pub(crate) fn clear(&mut self) {
+        self.buys.clear();
+        self.sells.clear();
+        self.index.clear();
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<u32, VecDeque<Order>> {
+        match side {
+            Side::Buy => &mut self.buys,
+            Side::Sell => &mut self.sells,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Time-in-force for new orders.
+//!
+//! Controls what happens to quantity that does not fill immediately:
+//! - `Day` / `Gtc`: rest on the book (limit orders only).
+//! - `Ioc` (immediate-or-cancel): fill what you can, expire the rest.
+//! - `Fok` (fill-or-kill): fill the whole order immediately or do nothing.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TimeInForce {
+    /// Rest until cancelled or the end of the trading day.
+    #[default]
+    Day,
+    /// Good-till-cancelled. Behaves like `Day` until sessions exist.
+    Gtc,
+    /// Immediate-or-cancel.
+    Ioc,
+    /// Fill-or-kill.
+    Fok,
+}
+
+impl TimeInForce {
+    /// Short text code used by the CSV protocol (`DAY`, `GTC`, `IOC`, `FOK`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeInForce::Day => "DAY",
+            TimeInForce::Gtc => "GTC",
+            TimeInForce::Ioc => "IOC",
+            TimeInForce::Fok => "FOK",
+        }
+    }
+
+    /// Parse the CSV text code (case-sensitive).
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        match s {
+            "DAY" => Some(TimeInForce::Day),
+            "GTC" => Some(TimeInForce::Gtc),
+            "IOC" => Some(TimeInForce::Ioc),
+            "FOK" => Some(TimeInForce::Fok),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if unfilled quantity may rest on the book.
+    pub fn can_rest(self) -> bool {
+        matches!(self, TimeInForce::Day | TimeInForce::Gtc)
+    }
+}
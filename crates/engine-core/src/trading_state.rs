@@ -0,0 +1,77 @@
+//! Per-symbol trading session states.
+//!
+//! - `PreOpen` / `Auction`: orders are collected without matching (the
+//!   book is in its call phase, see [`crate::auction`]).
+//! - `Continuous`: normal matching. Symbols start here.
+//! - `Halted` / `Closed`: new orders and replaces are refused (or new
+//!   orders queued, if the engine is configured to) until the symbol
+//!   reopens.
+//!
+//! Cancels are accepted in every state. Moving from a call phase to
+//! `Continuous` uncrosses the book.
+
+/// Trading state of a symbol.
+///
+/// The numeric values are part of the binary wire protocol.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TradingState {
+    /// Before the open: order entry, no matching.
+    PreOpen = 0,
+    /// Call auction: order entry, no matching, indicative published.
+    Auction = 1,
+    /// Continuous matching.
+    #[default]
+    Continuous = 2,
+    /// Trading stopped intraday.
+    Halted = 3,
+    /// After the close.
+    Closed = 4,
+}
+
+impl TradingState {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(TradingState::PreOpen),
+            1 => Some(TradingState::Auction),
+            2 => Some(TradingState::Continuous),
+            3 => Some(TradingState::Halted),
+            4 => Some(TradingState::Closed),
+            _ => None,
+        }
+    }
+
+    /// Text code used by the CSV protocol.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TradingState::PreOpen => "PRE_OPEN",
+            TradingState::Auction => "AUCTION",
+            TradingState::Continuous => "CONTINUOUS",
+            TradingState::Halted => "HALTED",
+            TradingState::Closed => "CLOSED",
+        }
+    }
+
+    /// Parse the text code (case-sensitive).
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        match s {
+            "PRE_OPEN" => Some(TradingState::PreOpen),
+            "AUCTION" => Some(TradingState::Auction),
+            "CONTINUOUS" => Some(TradingState::Continuous),
+            "HALTED" => Some(TradingState::Halted),
+            "CLOSED" => Some(TradingState::Closed),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if new orders are accepted in this state.
+    pub fn accepts_orders(self) -> bool {
+        !matches!(self, TradingState::Halted | TradingState::Closed)
+    }
+
+    /// Returns `true` for the states in which the book is in its call
+    /// phase.
+    pub fn is_call_phase(self) -> bool {
+        matches!(self, TradingState::PreOpen | TradingState::Auction)
+    }
+}
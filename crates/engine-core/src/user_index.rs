@@ -0,0 +1,68 @@
+//! Per-user lookup of an order book's live orders.
+//!
+//! Both the resting orders and the pending stops of a book are found by
+//! `(user_id, user_order_id)`, which is all a cancel or replace needs. A
+//! mass cancel or kill switch needs every order of one user instead:
+//! keying by user first gives it those without visiting anyone else's.
+
+use std::collections::HashMap;
+
+/// `user_id` -> `user_order_id` -> `T`.
+#[derive(Debug, Clone)]
+pub(crate) struct UserIndex<T> {
+    by_user: HashMap<u32, HashMap<u32, T>>,
+    len: usize,
+}
+
+impl<T> Default for UserIndex<T> {
+    fn default() -> Self {
+        UserIndex {
+            by_user: HashMap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> UserIndex<T> {
+    pub(crate) fn insert(&mut self, user_id: u32, user_order_id: u32, value: T) {
+        if self.by_user.entry(user_id).or_default().insert(user_order_id, value).is_none() {
+            self.len += 1;
+        }
+    }
+
+    pub(crate) fn remove(&mut self, user_id: u32, user_order_id: u32) -> Option<T> {
+        let orders = self.by_user.get_mut(&user_id)?;
+        let value = orders.remove(&user_order_id)?;
+        if orders.is_empty() {
+            self.by_user.remove(&user_id);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub(crate) fn get(&self, user_id: u32, user_order_id: u32) -> Option<&T> {
+        self.by_user.get(&user_id)?.get(&user_order_id)
+    }
+
+    pub(crate) fn contains(&self, user_id: u32, user_order_id: u32) -> bool {
+        self.get(user_id, user_order_id).is_some()
+    }
+
+    /// Every order of `user_id`, in no particular order.
+    pub(crate) fn user_orders(&self, user_id: u32) -> impl Iterator<Item = (u32, &T)> {
+        self.by_user
+            .get(&user_id)
+            .into_iter()
+            .flatten()
+            .map(|(&user_order_id, value)| (user_order_id, value))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.by_user.clear();
+        self.len = 0;
+    }
+}
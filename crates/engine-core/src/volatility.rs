@@ -0,0 +1,56 @@
+//! Dynamic volatility bands (circuit breakers).
+//!
+//! Every request may only trade within `band_bps` basis points of the
+//! symbol's last trade price as it stood when the request arrived. A
+//! match that would print outside the band is not executed: the symbol
+//! is interrupted instead, going into a call auction or a halt for a
+//! fixed duration, and then back to continuous trading (uncrossing the
+//! orders collected meanwhile).
+//!
+//! The resume time is taken from the engine's clock, and the resume
+//! itself happens at the first state-changing input processed at or
+//! after it, so a replay of the same inputs with the same timestamps
+//! breaches and resumes at exactly the same points.
+
+/// What a symbol does when its volatility band is breached.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum VolatilityAction {
+    /// Volatility auction: orders are collected and uncrossed on resume.
+    #[default]
+    Auction,
+    /// Halt: new orders are refused (or queued) until the resume.
+    Halt,
+}
+
+impl VolatilityAction {
+    /// Text code (`AUCTION`, `HALT`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VolatilityAction::Auction => "AUCTION",
+            VolatilityAction::Halt => "HALT",
+        }
+    }
+
+    /// Parse the text code (case-sensitive).
+    pub fn from_str_code(s: &str) -> Option<Self> {
+        match s {
+            "AUCTION" => Some(VolatilityAction::Auction),
+            "HALT" => Some(VolatilityAction::Halt),
+            _ => None,
+        }
+    }
+}
+
+/// Volatility band settings, applied to every symbol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VolatilityBands {
+    /// Band half-width in basis points of the reference price (e.g.
+    /// `500` = ±5%).
+    pub band_bps: u32,
+
+    /// How long an interruption lasts, in nanoseconds.
+    pub duration_ns: u64,
+
+    /// Auction or halt.
+    pub action: VolatilityAction,
+}
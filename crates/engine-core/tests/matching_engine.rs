@@ -0,0 +1,361 @@
+// crates/engine-core/tests/matching_engine.rs
+use std::sync::Arc;
+
+use engine_core::{
+    Cancel, EnableTrading, ExecType, ExecutionReport, InputMessage, KillSwitch, ManualClock, MassCancel,
+    MassCancelAck, MatchingEngine, NewOrder, OrdStatus, OrderFlags, OutputMessage, RejectReason,
+    Replace, SelfTradePrevention, Side, TimeInForce, UserTradingStatus,
+};
+
+fn new_order(
+    user_id: u32,
+    user_order_id: u32,
+    symbol: &str,
+    price: u32,
+    quantity: u32,
+    side: Side,
+) -> InputMessage {
+    InputMessage::NewOrder(NewOrder {
+        user_id,
+        symbol: symbol.to_string(),
+        price,
+        quantity,
+        side,
+        user_order_id,
+        time_in_force: TimeInForce::Day,
+        stop_price: 0,
+        display_qty: 0,
+        flags: OrderFlags::NONE,
+        self_trade_prevention: SelfTradePrevention::None,
+    })
+}
+
+fn cancel(user_id: u32, user_order_id: u32) -> InputMessage {
+    InputMessage::Cancel(Cancel {
+        user_id,
+        user_order_id,
+    })
+}
+
+#[test]
+fn invalid_new_orders_are_rejected_with_reason() {
+    let mut engine = MatchingEngine::new();
+
+    assert_eq!(
+        engine.process_message(new_order(1, 1, "IBM", 10, 0, Side::Buy)),
+        vec![OutputMessage::reject(1, 1, "IBM", RejectReason::ZeroQuantity)]
+    );
+    assert_eq!(
+        engine.process_message(new_order(1, 2, "", 10, 100, Side::Buy)),
+        vec![OutputMessage::reject(1, 2, "", RejectReason::InvalidSymbol)]
+    );
+    assert_eq!(
+        engine.process_message(new_order(1, 3, "IBM", 0, 100, Side::Buy)),
+        vec![OutputMessage::reject(1, 3, "IBM", RejectReason::NoLiquidity)]
+    );
+
+    // Same id while the first order is still live, even on another symbol.
+    engine.process_message(new_order(1, 4, "IBM", 10, 100, Side::Buy));
+    assert_eq!(
+        engine.process_message(new_order(1, 4, "MSFT", 10, 100, Side::Buy)),
+        vec![OutputMessage::reject(1, 4, "IBM", RejectReason::DuplicateOrderId)]
+    );
+}
+
+#[test]
+fn cancel_of_unknown_or_filled_order_is_rejected() {
+    let mut engine = MatchingEngine::new();
+
+    assert_eq!(
+        engine.process_message(cancel(1, 1)),
+        vec![OutputMessage::reject(1, 1, "", RejectReason::UnknownOrder)]
+    );
+
+    engine.process_message(new_order(1, 1, "IBM", 10, 100, Side::Buy));
+    engine.process_message(new_order(2, 1, "IBM", 10, 100, Side::Sell));
+    assert_eq!(
+        engine.process_message(cancel(1, 1)),
+        vec![OutputMessage::reject(1, 1, "", RejectReason::UnknownOrder)]
+    );
+
+    // A fully filled id may be reused.
+    let outputs = engine.process_message(new_order(1, 1, "IBM", 10, 100, Side::Buy));
+    assert_eq!(outputs[0], OutputMessage::ack(1, 1, "IBM"));
+}
+
+#[test]
+fn trade_records_aggressor_and_filled_orders_are_no_longer_live() {
+    let clock = ManualClock::new(5_000);
+    let mut engine = MatchingEngine::with_clock(Arc::new(clock.clone()));
+    engine.process_message(new_order(1, 1, "IBM", 10, 100, Side::Buy));
+    assert!(engine.has_order(1, 1));
+    assert_eq!(engine.get_book("IBM").unwrap().get_order(1, 1).unwrap().timestamp_ns, 5_000);
+
+    clock.advance(250);
+    let outputs = engine.process_message(new_order(2, 1, "IBM", 10, 100, Side::Sell));
+    assert_eq!(
+        outputs[1],
+        OutputMessage::trade("IBM", 1, 1, 2, 1, 10, 100, Side::Sell, 5_250)
+    );
+    assert!(!engine.has_order(1, 1));
+    assert!(!engine.has_order(2, 1));
+}
+
+#[test]
+fn user_default_self_trade_prevention_applies_to_orders_without_one() {
+    let mut engine = MatchingEngine::new();
+    engine.set_self_trade_prevention(1, SelfTradePrevention::CancelOldest);
+    engine.process_message(new_order(1, 1, "IBM", 10, 100, Side::Sell));
+
+    let outputs = engine.process_message(new_order(1, 2, "IBM", 10, 40, Side::Buy));
+    assert_eq!(outputs[1], OutputMessage::self_trade_cancel(1, 1, "IBM", 100, 0));
+    assert!(!outputs.iter().any(|o| matches!(o, OutputMessage::Trade(_))));
+    assert!(!engine.has_order(1, 1));
+    assert!(engine.has_order(1, 2));
+
+    // Other users still trade with themselves.
+    engine.process_message(new_order(2, 1, "IBM", 11, 10, Side::Sell));
+    let outputs = engine.process_message(new_order(2, 2, "IBM", 11, 10, Side::Buy));
+    assert!(outputs.iter().any(|o| matches!(o, OutputMessage::Trade(_))));
+}
+
+#[test]
+fn execution_reports_track_cumulative_and_leaves_quantity() {
+    let clock = ManualClock::new(7_000);
+    let mut engine = MatchingEngine::with_clock(Arc::new(clock));
+    engine.set_execution_reports(true);
+
+    let reports = |outputs: Vec<OutputMessage>| -> Vec<ExecutionReport> {
+        outputs
+            .into_iter()
+            .filter_map(|o| match o {
+                OutputMessage::ExecutionReport(r) => Some(r),
+                _ => None,
+            })
+            .collect()
+    };
+    let report = |user_id, user_order_id, exec_id, exec, last: (u32, u32), cum_qty, leaves_qty| {
+        let (exec_type, order_status) = exec;
+        ExecutionReport {
+            user_id,
+            user_order_id,
+            symbol: "IBM".to_string(),
+            exec_id,
+            exec_type,
+            order_status,
+            last_qty: last.0,
+            last_price: last.1,
+            cum_qty,
+            leaves_qty,
+            timestamp_ns: 7_000,
+        }
+    };
+
+    let outputs = engine.process_message(new_order(1, 1, "IBM", 10, 100, Side::Buy));
+    assert_eq!(reports(outputs), vec![report(1, 1, 1, (ExecType::New, OrdStatus::New), (0, 0), 0, 100)]);
+
+    let outputs = engine.process_message(new_order(2, 1, "IBM", 10, 60, Side::Sell));
+    assert_eq!(
+        reports(outputs),
+        vec![
+            report(2, 1, 2, (ExecType::New, OrdStatus::New), (0, 0), 0, 60),
+            report(1, 1, 3, (ExecType::Trade, OrdStatus::PartiallyFilled), (60, 10), 60, 40),
+            report(2, 1, 4, (ExecType::Trade, OrdStatus::Filled), (60, 10), 60, 0),
+        ]
+    );
+
+    let outputs = engine.process_message(cancel(1, 1));
+    assert_eq!(
+        reports(outputs),
+        vec![report(1, 1, 5, (ExecType::Canceled, OrdStatus::Canceled), (0, 0), 60, 0)]
+    );
+
+    // A rejected new order is reported; a new order rejected for reusing
+    // a live order's id is only a Reject (the live order is unchanged).
+    let outputs = engine.process_message(new_order(3, 1, "IBM", 10, 0, Side::Buy));
+    assert_eq!(
+        reports(outputs),
+        vec![report(3, 1, 6, (ExecType::Rejected, OrdStatus::Rejected), (0, 0), 0, 0)]
+    );
+    engine.process_message(new_order(3, 2, "IBM", 10, 5, Side::Buy));
+    let outputs = engine.process_message(new_order(3, 2, "IBM", 10, 5, Side::Buy));
+    assert_eq!(outputs.len(), 1);
+}
+
+#[test]
+fn mass_cancel_filters_by_symbol_side_and_price_range() {
+    let mut engine = MatchingEngine::new();
+    engine.process_message(new_order(1, 1, "IBM", 10, 100, Side::Buy));
+    engine.process_message(new_order(1, 2, "IBM", 11, 100, Side::Buy));
+    engine.process_message(new_order(1, 3, "IBM", 12, 100, Side::Buy));
+    engine.process_message(new_order(1, 4, "IBM", 15, 100, Side::Sell));
+    engine.process_message(new_order(1, 5, "MSFT", 11, 100, Side::Buy));
+    engine.process_message(new_order(2, 1, "IBM", 11, 100, Side::Buy));
+
+    let cancels = |outputs: Vec<OutputMessage>| -> Vec<OutputMessage> {
+        outputs
+            .into_iter()
+            .filter(|o| matches!(o, OutputMessage::CancelAck(_) | OutputMessage::MassCancelAck(_)))
+            .collect()
+    };
+
+    let outputs = engine.process_message(InputMessage::MassCancel(MassCancel {
+        user_id: 1,
+        symbol: Some("IBM".to_string()),
+        side: Some(Side::Buy),
+        min_price: Some(11),
+        max_price: Some(12),
+    }));
+    assert_eq!(
+        cancels(outputs),
+        vec![
+            OutputMessage::cancel_ack(1, 3, "IBM"),
+            OutputMessage::cancel_ack(1, 2, "IBM"),
+            OutputMessage::MassCancelAck(MassCancelAck {
+                user_id: 1,
+                symbol: "IBM".to_string(),
+                cancelled_count: 2,
+            }),
+        ]
+    );
+    assert!(engine.has_order(1, 1));
+    assert!(!engine.has_order(1, 2));
+
+    // No filters: the user's remaining orders on every symbol.
+    let outputs = engine.process_message(InputMessage::MassCancel(MassCancel {
+        user_id: 1,
+        symbol: None,
+        side: None,
+        min_price: None,
+        max_price: None,
+    }));
+    assert_eq!(
+        cancels(outputs),
+        vec![
+            OutputMessage::cancel_ack(1, 1, "IBM"),
+            OutputMessage::cancel_ack(1, 4, "IBM"),
+            OutputMessage::cancel_ack(1, 5, "MSFT"),
+            OutputMessage::MassCancelAck(MassCancelAck {
+                user_id: 1,
+                symbol: String::new(),
+                cancelled_count: 3,
+            }),
+        ]
+    );
+    assert!(engine.has_order(2, 1));
+}
+
+#[test]
+fn mass_cancel_price_range_applies_to_the_stop_price_of_pending_stops() {
+    let mut engine = MatchingEngine::new();
+    let stop = |user_order_id, price, stop_price, side| {
+        let InputMessage::NewOrder(mut order) = new_order(1, user_order_id, "IBM", price, 100, side) else {
+            unreachable!()
+        };
+        order.stop_price = stop_price;
+        InputMessage::NewOrder(order)
+    };
+    engine.process_message(new_order(1, 1, "IBM", 10, 100, Side::Buy));
+    engine.process_message(new_order(2, 1, "IBM", 10, 100, Side::Buy));
+    engine.process_message(new_order(1, 2, "IBM", 10, 100, Side::Buy));
+    // Stop-market buy at 20, stop-limit sell at 9 (limit 5).
+    engine.process_message(stop(3, 0, 20, Side::Buy));
+    engine.process_message(stop(4, 5, 9, Side::Sell));
+
+    let mut mass_cancel = |min_price, max_price| -> Vec<u32> {
+        engine
+            .process_message(InputMessage::MassCancel(MassCancel {
+                user_id: 1,
+                symbol: None,
+                side: None,
+                min_price,
+                max_price,
+            }))
+            .into_iter()
+            .filter_map(|o| match o {
+                OutputMessage::CancelAck(c) => Some(c.user_order_id),
+                _ => None,
+            })
+            .collect()
+    };
+    assert_eq!(mass_cancel(Some(15), Some(25)), vec![3]);
+    assert_eq!(mass_cancel(None, Some(9)), vec![4]);
+    // Time priority within the level, around another user's order.
+    assert_eq!(mass_cancel(None, None), vec![1, 2]);
+    assert!(engine.has_order(2, 1));
+}
+
+#[test]
+fn kill_switch_blocks_new_orders_until_enabled_and_survives_a_snapshot() {
+    let kill = |user_id, cancel_orders| {
+        InputMessage::KillSwitch(KillSwitch {
+            user_id,
+            cancel_orders,
+            admin_token: String::new(),
+        })
+    };
+    let enable = |user_id| {
+        InputMessage::EnableTrading(EnableTrading {
+            user_id,
+            admin_token: String::new(),
+        })
+    };
+    let status = |user_id, enabled| OutputMessage::UserTradingStatus(UserTradingStatus { user_id, enabled });
+
+    let mut engine = MatchingEngine::new();
+    engine.process_message(new_order(1, 1, "IBM", 10, 100, Side::Buy));
+    engine.process_message(new_order(1, 2, "MSFT", 20, 100, Side::Sell));
+    engine.process_message(new_order(2, 1, "IBM", 9, 100, Side::Buy));
+
+    // Without cancelling, user 1's orders stay; new ones and replaces don't.
+    assert_eq!(engine.process_message(kill(1, false)), vec![status(1, false)]);
+    assert!(!engine.is_trading_enabled(1));
+    assert_eq!(
+        engine.process_message(new_order(1, 3, "IBM", 10, 100, Side::Buy)),
+        vec![OutputMessage::reject(1, 3, "IBM", RejectReason::TradingDisabled)]
+    );
+    let replace = InputMessage::Replace(Replace {
+        user_id: 1,
+        user_order_id: 1,
+        new_price: Some(11),
+        new_quantity: None,
+    });
+    assert_eq!(
+        engine.process_message(replace),
+        vec![OutputMessage::reject(1, 1, "IBM", RejectReason::TradingDisabled)]
+    );
+    assert!(engine.has_order(1, 1));
+    assert_eq!(
+        engine.process_message(new_order(2, 2, "IBM", 9, 100, Side::Buy))[0],
+        OutputMessage::ack(2, 2, "IBM")
+    );
+
+    // Every user, cancelling: one mass cancel per user with orders.
+    let outputs = engine.process_message(kill(0, true));
+    assert_eq!(outputs[0], status(0, false));
+    let summaries: Vec<_> = outputs
+        .iter()
+        .filter_map(|o| match o {
+            OutputMessage::MassCancelAck(m) => Some((m.user_id, m.cancelled_count)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(summaries, vec![(1, 2), (2, 2)]);
+    assert!(!engine.has_order(2, 1));
+
+    // The switches are engine state, so they survive a snapshot.
+    let mut snapshot = Vec::new();
+    engine.snapshot(7, &mut snapshot).unwrap();
+    let (mut engine, _) = MatchingEngine::restore(&snapshot[..]).unwrap();
+    assert!(!engine.is_trading_enabled(2));
+
+    // Lifting the switch for everyone leaves user 1's own in place.
+    assert_eq!(engine.process_message(enable(0)), vec![status(0, true)]);
+    assert!(engine.is_trading_enabled(2));
+    assert!(!engine.is_trading_enabled(1));
+    assert_eq!(engine.process_message(enable(1)), vec![status(1, true)]);
+    assert_eq!(
+        engine.process_message(new_order(1, 3, "IBM", 10, 100, Side::Buy))[0],
+        OutputMessage::ack(1, 3, "IBM")
+    );
+}
@@ -0,0 +1,428 @@
+// crates/engine-core/tests/order_book.rs
+use std::sync::Arc;
+
+use engine_core::{
+    DepthAction, DepthEvent, DepthLevel, DepthUpdate, ManualClock, NewOrder, OrderBook,
+    OrderBookEvent, OrderFlags, OutputMessage, RejectReason, SelfTradePrevention, Side,
+    TimeInForce,
+};
+use engine_protocol::{decode_book_event, encode_book_event};
+
+/// Time shown by the clock of books built with `ibm_book`.
+const T0: u64 = 1_000_000;
+
+fn ibm_book() -> OrderBook {
+    OrderBook::with_clock("IBM", Arc::new(ManualClock::new(T0)))
+}
+
+fn limit(user_id: u32, user_order_id: u32, side: Side, price: u32, quantity: u32) -> NewOrder {
+    NewOrder {
+        user_id,
+        symbol: "IBM".to_string(),
+        price,
+        quantity,
+        side,
+        user_order_id,
+        time_in_force: TimeInForce::Day,
+        stop_price: 0,
+        display_qty: 0,
+        flags: OrderFlags::NONE,
+        self_trade_prevention: SelfTradePrevention::None,
+    }
+}
+
+fn with_tif(mut order: NewOrder, time_in_force: TimeInForce) -> NewOrder {
+    order.time_in_force = time_in_force;
+    order
+}
+
+fn with_stop(mut order: NewOrder, stop_price: u32) -> NewOrder {
+    order.stop_price = stop_price;
+    order
+}
+
+fn with_display(mut order: NewOrder, display_qty: u32) -> NewOrder {
+    order.display_qty = display_qty;
+    order
+}
+
+fn with_stp(mut order: NewOrder, mode: SelfTradePrevention) -> NewOrder {
+    order.self_trade_prevention = mode;
+    order
+}
+
+#[test]
+fn cancel_from_middle_of_level_keeps_time_priority() {
+    let mut book = ibm_book();
+    book.add_order(&limit(1, 1, Side::Sell, 10, 100));
+    book.add_order(&limit(2, 1, Side::Sell, 10, 100));
+    book.add_order(&limit(3, 1, Side::Sell, 10, 100));
+
+    assert!(book.contains_order(2, 1));
+    let outputs = book.cancel_order(2, 1);
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::cancel_ack(2, 1, "IBM"),
+            OutputMessage::top_of_book("IBM", Side::Sell, 10, 200),
+        ]
+    );
+    assert!(!book.contains_order(2, 1));
+    assert_eq!(book.order_count(), 2);
+
+    // The remaining two orders still fill oldest-first.
+    let outputs = book.add_order(&limit(9, 1, Side::Buy, 10, 150));
+    assert_eq!(outputs[1], OutputMessage::trade("IBM", 9, 1, 1, 1, 10, 100, Side::Buy, T0));
+    assert_eq!(outputs[2], OutputMessage::trade("IBM", 9, 1, 3, 1, 10, 50, Side::Buy, T0));
+    assert_eq!(book.get_order(3, 1).map(|o| o.remaining_qty), Some(50));
+}
+
+#[test]
+fn ioc_remainder_is_expired_not_rested() {
+    let mut book = ibm_book();
+    book.add_order(&limit(1, 1, Side::Sell, 10, 100));
+
+    let outputs = book.add_order(&with_tif(limit(2, 1, Side::Buy, 10, 150), TimeInForce::Ioc));
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(2, 1, "IBM"),
+            OutputMessage::trade("IBM", 2, 1, 1, 1, 10, 100, Side::Buy, T0),
+            OutputMessage::expired(2, 1, "IBM", 50),
+            OutputMessage::top_of_book_eliminated("IBM", Side::Sell),
+        ]
+    );
+    assert!(!book.contains_order(2, 1));
+}
+
+#[test]
+fn fok_fills_completely_or_does_nothing() {
+    let mut book = ibm_book();
+    book.add_order(&limit(1, 1, Side::Sell, 10, 100));
+    book.add_order(&limit(1, 2, Side::Sell, 11, 100));
+
+    // Only 100 available at or below 10: killed, book untouched.
+    let outputs = book.add_order(&with_tif(limit(2, 1, Side::Buy, 10, 150), TimeInForce::Fok));
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(2, 1, "IBM"),
+            OutputMessage::expired(2, 1, "IBM", 150),
+        ]
+    );
+    assert_eq!(book.order_count(), 2);
+
+    // 200 available at or below 11: fills across both levels.
+    let outputs = book.add_order(&with_tif(limit(2, 2, Side::Buy, 11, 150), TimeInForce::Fok));
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(2, 2, "IBM"),
+            OutputMessage::trade("IBM", 2, 2, 1, 1, 10, 100, Side::Buy, T0),
+            OutputMessage::trade("IBM", 2, 2, 1, 2, 11, 50, Side::Buy, T0),
+            OutputMessage::top_of_book("IBM", Side::Sell, 11, 50),
+        ]
+    );
+}
+
+#[test]
+fn replace_size_down_keeps_priority_size_up_loses_it() {
+    let mut book = ibm_book();
+    book.add_order(&limit(1, 1, Side::Buy, 10, 100));
+    book.add_order(&limit(2, 1, Side::Buy, 10, 100));
+
+    // Size down: stays ahead of user 2.
+    let outputs = book.replace_order(1, 1, None, Some(60));
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::replace_ack(1, 1, "IBM", 10, 60),
+            OutputMessage::top_of_book("IBM", Side::Buy, 10, 160),
+        ]
+    );
+    let outputs = book.add_order(&limit(9, 1, Side::Sell, 10, 10));
+    assert_eq!(outputs[1], OutputMessage::trade("IBM", 1, 1, 9, 1, 10, 10, Side::Sell, T0));
+
+    // Size up: goes behind user 2.
+    book.replace_order(1, 1, None, Some(200));
+    let outputs = book.add_order(&limit(9, 2, Side::Sell, 10, 10));
+    assert_eq!(outputs[1], OutputMessage::trade("IBM", 2, 1, 9, 2, 10, 10, Side::Sell, T0));
+    assert_eq!(book.get_order(1, 1).map(|o| o.remaining_qty), Some(190));
+}
+
+#[test]
+fn replace_to_crossing_price_trades_and_bad_replace_is_rejected() {
+    let mut book = ibm_book();
+    book.add_order(&limit(1, 1, Side::Buy, 10, 100));
+    book.add_order(&limit(2, 1, Side::Sell, 12, 50));
+
+    let outputs = book.replace_order(1, 1, Some(12), None);
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::replace_ack(1, 1, "IBM", 12, 100),
+            OutputMessage::trade("IBM", 1, 1, 2, 1, 12, 50, Side::Buy, T0),
+            OutputMessage::top_of_book("IBM", Side::Buy, 12, 50),
+            OutputMessage::top_of_book_eliminated("IBM", Side::Sell),
+        ]
+    );
+
+    // 50 already filled, so a new total of 50 leaves nothing open.
+    assert_eq!(
+        book.replace_order(1, 1, None, Some(50)),
+        vec![OutputMessage::reject(1, 1, "IBM", RejectReason::InvalidReplace)]
+    );
+    assert_eq!(
+        book.replace_order(7, 7, Some(11), None),
+        vec![OutputMessage::reject(7, 7, "IBM", RejectReason::UnknownOrder)]
+    );
+}
+
+#[test]
+fn depth_lists_levels_and_updates_report_level_changes() {
+    let mut book = ibm_book();
+    book.add_order(&limit(1, 1, Side::Buy, 10, 100));
+    book.add_order(&limit(1, 2, Side::Buy, 9, 50));
+    book.add_order(&limit(1, 3, Side::Buy, 9, 25));
+    book.add_order(&limit(2, 1, Side::Sell, 12, 40));
+
+    let depth = book.depth(0);
+    assert_eq!(
+        depth.bids,
+        vec![
+            DepthLevel { price: 10, quantity: 100 },
+            DepthLevel { price: 9, quantity: 75 },
+        ]
+    );
+    assert_eq!(depth.asks, vec![DepthLevel { price: 12, quantity: 40 }]);
+    assert_eq!(book.depth(1).bids.len(), 1);
+
+    // A sell sweeping the 10 level and part of 9 deletes one level and
+    // changes the other.
+    book.set_depth_updates(true);
+    let outputs = book.add_order(&limit(3, 1, Side::Sell, 9, 120));
+    let event = |action, price, quantity| DepthEvent {
+        action,
+        side: Side::Buy,
+        price,
+        quantity,
+    };
+    assert_eq!(
+        outputs.last(),
+        Some(&OutputMessage::DepthUpdate(DepthUpdate {
+            symbol: "IBM".to_string(),
+            snapshot: false,
+            events: vec![
+                event(DepthAction::Change, 9, 55),
+                event(DepthAction::Delete, 10, 0),
+            ],
+        }))
+    );
+
+    // A rejected request leaves the book alone and emits no update.
+    assert!(book
+        .cancel_order(9, 9)
+        .iter()
+        .all(|m| !matches!(m, OutputMessage::DepthUpdate(_))));
+}
+
+#[test]
+fn book_event_feed_rebuilds_the_book() {
+    let mut book = ibm_book();
+    book.set_book_events(true);
+
+    book.add_order(&limit(1, 1, Side::Buy, 10, 100));
+    book.add_order(&limit(1, 2, Side::Buy, 10, 50));
+    book.add_order(&limit(2, 1, Side::Sell, 12, 80));
+    book.add_order(&limit(3, 1, Side::Sell, 10, 30)); // partial execute of 1/1
+    book.replace_order(1, 2, None, Some(40)); // reduce in place
+    book.replace_order(2, 1, Some(13), None); // replace, no trade
+    book.replace_order(1, 1, Some(13), None); // replace that trades
+    book.cancel_order(1, 2);
+
+    let events = book.take_book_events();
+    assert_eq!(
+        events[0],
+        OrderBookEvent::Add {
+            symbol: "IBM".to_string(),
+            order_id: 1,
+            side: Side::Buy,
+            price: 10,
+            quantity: 100,
+        }
+    );
+    assert!(events.iter().any(|e| matches!(
+        e,
+        OrderBookEvent::Replace { old_order_id: 3, new_order_id: 4, .. }
+    )));
+
+    // Rebuild from the (binary round-tripped) feed alone.
+    let mut rebuilt: Vec<(u64, Side, u32, u32)> = Vec::new();
+    for event in &events {
+        let mut buf = Vec::new();
+        encode_book_event(event, &mut buf).unwrap();
+        match decode_book_event(&buf).unwrap() {
+            OrderBookEvent::Add { order_id, side, price, quantity, .. } => {
+                rebuilt.push((order_id, side, price, quantity))
+            }
+            OrderBookEvent::Execute { order_id, remaining_qty, .. }
+            | OrderBookEvent::Cancel { order_id, remaining_qty, .. } => {
+                let i = rebuilt.iter().position(|o| o.0 == order_id).unwrap();
+                if remaining_qty == 0 {
+                    rebuilt.remove(i);
+                } else {
+                    rebuilt[i].3 = remaining_qty;
+                }
+            }
+            OrderBookEvent::Replace { old_order_id, new_order_id, price, quantity, .. } => {
+                let i = rebuilt.iter().position(|o| o.0 == old_order_id).unwrap();
+                let side = rebuilt.remove(i).1;
+                rebuilt.push((new_order_id, side, price, quantity));
+            }
+        }
+    }
+
+    let expected: Vec<(u64, Side, u32, u32)> = [(1, 1), (2, 1), (3, 1)]
+        .iter()
+        .filter_map(|&(u, o)| book.get_order(u, o))
+        .map(|o| (o.exchange_order_id, o.side, o.price, o.remaining_qty))
+        .collect();
+    rebuilt.sort_by_key(|o| o.0);
+    assert_eq!(rebuilt, expected);
+}
+
+#[test]
+fn stop_orders_wait_off_book_then_trigger_in_a_cascade() {
+    let mut book = ibm_book();
+    book.add_order(&limit(1, 1, Side::Sell, 10, 100));
+    book.add_order(&limit(1, 2, Side::Sell, 11, 100));
+    book.add_order(&limit(1, 3, Side::Sell, 12, 100));
+
+    // Nothing has traded yet, so both stops are held without touching TOB.
+    let stop_market = with_stop(limit(2, 1, Side::Buy, 0, 100), 10);
+    assert_eq!(book.add_order(&stop_market), vec![OutputMessage::ack(2, 1, "IBM")]);
+    let stop_limit = with_stop(limit(3, 1, Side::Buy, 12, 50), 11);
+    assert_eq!(book.add_order(&stop_limit), vec![OutputMessage::ack(3, 1, "IBM")]);
+    assert_eq!(book.stop_order_count(), 2);
+    assert!(book.contains_order(2, 1) && book.get_order(2, 1).is_none());
+
+    // A trade at 10 triggers the stop at 10, whose trade at 11 triggers
+    // the stop at 11.
+    let outputs = book.add_order(&limit(4, 1, Side::Buy, 10, 100));
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(4, 1, "IBM"),
+            OutputMessage::trade("IBM", 4, 1, 1, 1, 10, 100, Side::Buy, T0),
+            OutputMessage::triggered(2, 1, "IBM", 10, 10),
+            OutputMessage::trade("IBM", 2, 1, 1, 2, 11, 100, Side::Buy, T0),
+            OutputMessage::triggered(3, 1, "IBM", 11, 11),
+            OutputMessage::trade("IBM", 3, 1, 1, 3, 12, 50, Side::Buy, T0),
+            OutputMessage::top_of_book("IBM", Side::Sell, 12, 50),
+        ]
+    );
+    assert_eq!(book.stop_order_count(), 0);
+    assert_eq!(book.last_trade_price(), 12);
+
+    // A sell stop below the market waits and can be cancelled.
+    let sell_stop = with_stop(limit(5, 1, Side::Sell, 0, 10), 11);
+    assert_eq!(book.add_order(&sell_stop), vec![OutputMessage::ack(5, 1, "IBM")]);
+    assert_eq!(book.cancel_order(5, 1), vec![OutputMessage::cancel_ack(5, 1, "IBM")]);
+    assert!(!book.contains_order(5, 1));
+}
+
+#[test]
+fn iceberg_shows_one_slice_and_refills_to_the_back_of_the_level() {
+    let mut book = ibm_book();
+    let outputs = book.add_order(&with_display(limit(1, 1, Side::Sell, 10, 100), 30));
+    assert_eq!(outputs[1], OutputMessage::top_of_book("IBM", Side::Sell, 10, 30));
+    let outputs = book.add_order(&limit(2, 1, Side::Sell, 10, 50));
+    assert_eq!(outputs[1], OutputMessage::top_of_book("IBM", Side::Sell, 10, 80));
+
+    // The first slice fills, the refill goes behind 2/1.
+    let outputs = book.add_order(&limit(9, 1, Side::Buy, 10, 40));
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(9, 1, "IBM"),
+            OutputMessage::trade("IBM", 9, 1, 1, 1, 10, 30, Side::Buy, T0),
+            OutputMessage::trade("IBM", 9, 1, 2, 1, 10, 10, Side::Buy, T0),
+            OutputMessage::top_of_book("IBM", Side::Sell, 10, 70),
+        ]
+    );
+
+    // Trades against the reserve are ordinary trades.
+    let outputs = book.add_order(&limit(9, 2, Side::Buy, 10, 100));
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(9, 2, "IBM"),
+            OutputMessage::trade("IBM", 9, 2, 2, 1, 10, 40, Side::Buy, T0),
+            OutputMessage::trade("IBM", 9, 2, 1, 1, 10, 30, Side::Buy, T0),
+            OutputMessage::trade("IBM", 9, 2, 1, 1, 10, 30, Side::Buy, T0),
+            OutputMessage::top_of_book("IBM", Side::Sell, 10, 10),
+        ]
+    );
+    assert_eq!(book.get_order(1, 1).map(|o| o.remaining_qty), Some(10));
+}
+
+#[test]
+fn self_trade_prevention_cancels_instead_of_trading() {
+    // User 1 has the oldest ask; user 2's ask is behind it.
+    let run = |mode| {
+        let mut book = ibm_book();
+        book.add_order(&limit(1, 1, Side::Sell, 10, 30));
+        book.add_order(&limit(2, 1, Side::Sell, 10, 30));
+        let outputs = book.add_order(&with_stp(limit(1, 2, Side::Buy, 10, 50), mode));
+        (book, outputs)
+    };
+
+    let (book, outputs) = run(SelfTradePrevention::CancelNewest);
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(1, 2, "IBM"),
+            OutputMessage::self_trade_cancel(1, 2, "IBM", 50, 0),
+        ]
+    );
+    assert_eq!(book.order_count(), 2);
+
+    let (book, outputs) = run(SelfTradePrevention::CancelOldest);
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(1, 2, "IBM"),
+            OutputMessage::self_trade_cancel(1, 1, "IBM", 30, 0),
+            OutputMessage::trade("IBM", 1, 2, 2, 1, 10, 30, Side::Buy, T0),
+            OutputMessage::top_of_book("IBM", Side::Buy, 10, 20),
+            OutputMessage::top_of_book_eliminated("IBM", Side::Sell),
+        ]
+    );
+    assert!(!book.contains_order(1, 1));
+
+    let (book, outputs) = run(SelfTradePrevention::CancelBoth);
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(1, 2, "IBM"),
+            OutputMessage::self_trade_cancel(1, 1, "IBM", 30, 0),
+            OutputMessage::self_trade_cancel(1, 2, "IBM", 50, 0),
+            OutputMessage::top_of_book("IBM", Side::Sell, 10, 30),
+        ]
+    );
+    assert_eq!(book.order_count(), 1);
+
+    // 30 comes off both; the last 20 trade with user 2.
+    let (book, outputs) = run(SelfTradePrevention::DecrementAndCancel);
+    assert_eq!(
+        outputs,
+        vec![
+            OutputMessage::ack(1, 2, "IBM"),
+            OutputMessage::self_trade_cancel(1, 1, "IBM", 30, 0),
+            OutputMessage::self_trade_cancel(1, 2, "IBM", 30, 20),
+            OutputMessage::trade("IBM", 1, 2, 2, 1, 10, 20, Side::Buy, T0),
+            OutputMessage::top_of_book("IBM", Side::Sell, 10, 10),
+        ]
+    );
+    assert_eq!(book.get_order(2, 1).map(|o| o.remaining_qty), Some(10));
+}
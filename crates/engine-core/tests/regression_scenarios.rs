@@ -1,9 +1,13 @@
 // crates/engine-core/tests/regression_scenarios.rs
-use engine_core::MatchingEngine;
+use engine_core::{
+    Instrument, InstrumentRegistry, ManualClock, MatchingEngine, OutputMessage, Side, VolatilityAction,
+    VolatilityBands,
+};
 use engine_protocol::csv_codec::{format_output_legacy, parse_input_line};
 use std::fs;
 use std::env;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[test] 
 fn full_input_matches_reference_output() {
@@ -105,3 +109,445 @@ fn test_individual_scenarios() {
         println!("  Produced {} outputs", outputs.len());
     }
 }
+
+#[test]
+fn trades_carry_the_clock_time_of_the_input_that_caused_them() {
+    const INPUT: &str = include_str!("data/inputFile.csv");
+
+    // Input n (1-based, comments skipped) is processed at n microseconds.
+    let clock = ManualClock::new(0);
+    let mut engine = MatchingEngine::with_clock(Arc::new(clock.clone()));
+    let mut trades = Vec::new();
+
+    let inputs = INPUT.lines().filter_map(|line| parse_input_line(line.trim()));
+    for (n, input_msg) in (1u64..).zip(inputs) {
+        clock.set(n * 1_000);
+        for out in engine.process_message(input_msg) {
+            if let OutputMessage::Trade(t) = out {
+                assert_eq!(t.timestamp_ns, n * 1_000, "trade {:?} from input {}", t, n);
+                trades.push(t);
+            }
+        }
+    }
+
+    // Scenario 1: inputs 5 and 6 each hit one resting order.
+    assert_eq!(
+        OutputMessage::Trade(trades[0].clone()),
+        OutputMessage::trade("IBM", 1, 3, 2, 102, 11, 100, Side::Buy, 5_000)
+    );
+    assert_eq!(
+        OutputMessage::Trade(trades[1].clone()),
+        OutputMessage::trade("IBM", 1, 1, 2, 103, 10, 100, Side::Sell, 6_000)
+    );
+}
+
+/// Run CSV input lines through a fresh engine and return the legacy CSV
+/// output lines.
+fn run_scenario(lines: &[&str]) -> Vec<String> {
+    run_scenario_on(&mut MatchingEngine::new(), lines)
+}
+
+/// Like `run_scenario`, on a configured engine.
+fn run_scenario_on(engine: &mut MatchingEngine, lines: &[&str]) -> Vec<String> {
+    let mut outputs = Vec::new();
+    for line in lines {
+        let msg = parse_input_line(line).unwrap_or_else(|| panic!("bad input line: {}", line));
+        outputs.extend(engine.process_message(msg).iter().map(format_output_legacy));
+    }
+    outputs
+}
+
+#[test]
+fn post_only_orders_reject_or_reprice_instead_of_taking() {
+    let outputs = run_scenario(&[
+        "N, 1, IBM, 10, 100, B, 1",
+        "N, 2, IBM, 12, 100, S, 1",
+        // Would trade at 10: rejected.
+        "N, 3, IBM, 10, 50, S, 1, DAY, 0, 0, POST_ONLY",
+        // Doesn't cross: rests like any limit order.
+        "N, 3, IBM, 11, 50, S, 2, DAY, 0, 0, POST_ONLY",
+        // Would trade at 11: repriced one tick behind it, to 10, joining 1/1.
+        "N, 4, IBM, 12, 30, B, 1, DAY, 0, 0, POST_ONLY_REPRICE",
+        // Post-only on a market order makes no sense.
+        "N, 4, IBM, 0, 30, B, 2, DAY, 0, 0, POST_ONLY",
+    ]);
+    assert_eq!(
+        outputs,
+        vec![
+            "A, 1, 1",
+            "B, B, 10, 100",
+            "A, 2, 1",
+            "B, S, 12, 100",
+            "X, 3, 1, POST_ONLY_WOULD_TRADE",
+            "A, 3, 2",
+            "B, S, 11, 50",
+            "A, 4, 1",
+            "B, B, 10, 130",
+            "X, 4, 2, INVALID_FLAGS",
+        ]
+    );
+}
+
+#[test]
+fn hidden_orders_trade_but_are_never_shown() {
+    let outputs = run_scenario(&[
+        "N, 1, IBM, 10, 100, B, 1",
+        "N, 2, IBM, 11, 100, B, 1, DAY, 0, 0, HIDDEN",
+        "N, 3, IBM, 10, 50, B, 1",
+        // Hits the hidden bid at 11 first, then 1/1 at 10.
+        "N, 4, IBM, 10, 150, S, 1",
+        "N, 2, IBM, 12, 70, S, 2, DAY, 0, 0, HIDDEN",
+        "N, 5, IBM, 0, 20, B, 1",
+        "C, 2, 2",
+    ]);
+    assert_eq!(
+        outputs,
+        vec![
+            "A, 1, 1",
+            "B, B, 10, 100",
+            "A, 2, 1",
+            "A, 3, 1",
+            "B, B, 10, 150",
+            "A, 4, 1",
+            "T, 2, 1, 4, 1, 11, 100",
+            "T, 1, 1, 4, 1, 10, 50",
+            "B, B, 10, 100",
+            "A, 2, 2",
+            "A, 5, 1",
+            "T, 5, 1, 2, 2, 12, 20",
+            "C, 2, 2",
+        ]
+    );
+}
+
+#[test]
+fn call_auction_collects_orders_and_uncrosses_at_one_price() {
+    let outputs = run_scenario(&[
+        "U, IBM, START",
+        "N, 1, IBM, 10, 100, B, 1",
+        // Crossed, but nothing trades; 9 and 10 both clear 60, the lower wins.
+        "N, 2, IBM, 9, 60, S, 1",
+        // 10 now clears all 100 with no imbalance.
+        "N, 3, IBM, 10, 40, S, 1",
+        "N, 4, IBM, 0, 10, B, 1",
+        "U, IBM, UNCROSS",
+        // Second auction: 8 and 11 both clear 50; 11 is closer to the
+        // last trade price (10).
+        "U, IBM, START",
+        "N, 5, IBM, 11, 50, B, 1",
+        "N, 6, IBM, 8, 50, S, 1",
+        "U, IBM, UNCROSS",
+        "U, IBM, UNCROSS",
+    ]);
+    assert_eq!(
+        outputs,
+        vec![
+            "H, IBM, AUCTION",
+            "I, IBM, 0, 0, 0, B",
+            "A, 1, 1",
+            "B, B, 10, 100",
+            "A, 2, 1",
+            "B, S, 9, 60",
+            "I, IBM, 9, 60, 40, B",
+            "A, 3, 1",
+            "I, IBM, 10, 100, 0, B",
+            "X, 4, 1, INVALID_IN_AUCTION",
+            "H, IBM, CONTINUOUS",
+            "T, 1, 1, 2, 1, 10, 60",
+            "T, 1, 1, 3, 1, 10, 40",
+            "B, B, -, -",
+            "B, S, -, -",
+            "H, IBM, AUCTION",
+            "I, IBM, 0, 0, 0, B",
+            "A, 5, 1",
+            "B, B, 11, 50",
+            "A, 6, 1",
+            "B, S, 8, 50",
+            "I, IBM, 11, 50, 0, B",
+            "H, IBM, CONTINUOUS",
+            "T, 5, 1, 6, 1, 11, 50",
+            "B, B, -, -",
+            "B, S, -, -",
+        ]
+    );
+}
+
+#[test]
+fn halted_and_closed_symbols_reject_or_queue_new_orders() {
+    let outputs = run_scenario(&[
+        "N, 1, IBM, 10, 100, B, 1",
+        "S, IBM, HALTED",
+        "N, 2, IBM, 10, 50, S, 1",
+        "R, 1, 1, 11, -",
+        // Cancels always go through.
+        "C, 1, 1",
+        "S, IBM, CLOSED",
+        "N, 2, IBM, 10, 50, S, 2",
+        "S, IBM, CLOSED",
+    ]);
+    assert_eq!(
+        outputs,
+        vec![
+            "A, 1, 1",
+            "B, B, 10, 100",
+            "H, IBM, HALTED",
+            "X, 2, 1, TRADING_HALTED",
+            "X, 1, 1, TRADING_HALTED",
+            "C, 1, 1",
+            "B, B, -, -",
+            "H, IBM, CLOSED",
+            "X, 2, 2, MARKET_CLOSED",
+        ]
+    );
+
+    let mut engine = MatchingEngine::new();
+    engine.set_queue_when_halted(true);
+    let outputs = run_scenario_on(
+        &mut engine,
+        &[
+            "N, 1, IBM, 10, 100, B, 1",
+            "S, IBM, HALTED",
+            "N, 2, IBM, 10, 50, S, 1",
+            "N, 2, IBM, 9, 20, S, 2",
+            "C, 2, 2",
+            // The queued order is entered (not acked again) on reopening.
+            "S, IBM, CONTINUOUS",
+        ],
+    );
+    assert_eq!(
+        outputs,
+        vec![
+            "A, 1, 1",
+            "B, B, 10, 100",
+            "H, IBM, HALTED",
+            "A, 2, 1",
+            "A, 2, 2",
+            "C, 2, 2",
+            "H, IBM, CONTINUOUS",
+            "T, 1, 1, 2, 1, 10, 50",
+            "B, B, 10, 50",
+        ]
+    );
+}
+
+#[test]
+fn orders_must_follow_their_instrument_definition() {
+    let mut instruments = InstrumentRegistry::new();
+    instruments.insert(Instrument {
+        tick_size: 5,
+        lot_size: 10,
+        min_qty: 10,
+        max_qty: 1000,
+        min_price: 50,
+        max_price: 200,
+        ..Instrument::new("IBM")
+    });
+    let mut engine = MatchingEngine::new();
+    engine.set_instruments(instruments);
+
+    let outputs = run_scenario_on(
+        &mut engine,
+        &[
+            "N, 1, IBM, 100, 100, B, 1",
+            "N, 1, IBM, 101, 100, B, 2",
+            "N, 1, IBM, 100, 105, B, 3",
+            "N, 1, IBM, 100, 2000, B, 4",
+            "N, 1, IBM, 250, 100, B, 5",
+            "N, 1, IBM, 100, 100, B, 6, DAY, 0, 15",
+            "N, 1, MSFT, 100, 100, B, 7",
+            "R, 1, 1, 102, -",
+            "R, 1, 1, -, 5",
+            // Repriced a whole tick behind the bid.
+            "N, 2, IBM, 100, 50, S, 1, DAY, 0, 0, POST_ONLY_REPRICE",
+            "I, IBM",
+            "I, MSFT",
+        ],
+    );
+    assert_eq!(
+        outputs,
+        vec![
+            "A, 1, 1",
+            "B, B, 100, 100",
+            "X, 1, 2, INVALID_TICK_SIZE",
+            "X, 1, 3, INVALID_LOT_SIZE",
+            "X, 1, 4, QUANTITY_OUT_OF_RANGE",
+            "X, 1, 5, PRICE_OUT_OF_RANGE",
+            "X, 1, 6, INVALID_LOT_SIZE",
+            "X, 1, 7, UNKNOWN_INSTRUMENT",
+            "X, 1, 1, INVALID_TICK_SIZE",
+            "X, 1, 1, INVALID_LOT_SIZE",
+            "A, 2, 1",
+            "B, S, 105, 50",
+            "L, 5, 10, 10, 1000, 50, 200, 0",
+            "X, 0, 0, UNKNOWN_INSTRUMENT",
+        ]
+    );
+}
+
+#[test]
+fn market_orders_sweep_up_to_their_protection_and_expire_the_rest() {
+    let mut instruments = InstrumentRegistry::new();
+    instruments.insert(Instrument {
+        tick_size: 5,
+        market_protection_ticks: 2,
+        ..Instrument::new("IBM")
+    });
+    instruments.insert(Instrument::new("MSFT"));
+    let mut engine = MatchingEngine::new();
+    engine.set_instruments(instruments);
+
+    let outputs = run_scenario_on(
+        &mut engine,
+        &[
+            "N, 1, IBM, 100, 10, S, 1",
+            "N, 1, IBM, 110, 10, S, 2",
+            "N, 1, IBM, 115, 10, S, 3",
+            // Two ticks through the 100 touch: 110 trades, 115 doesn't.
+            "N, 2, IBM, 0, 30, B, 1",
+            // Without protection the book runs out instead.
+            "N, 1, MSFT, 50, 10, S, 1",
+            "N, 2, MSFT, 0, 15, B, 2",
+        ],
+    );
+    assert_eq!(
+        outputs,
+        vec![
+            "A, 1, 1",
+            "B, S, 100, 10",
+            "A, 1, 2",
+            "A, 1, 3",
+            "A, 2, 1",
+            "T, 2, 1, 1, 1, 100, 10",
+            "T, 2, 1, 1, 2, 110, 10",
+            "E, 2, 1, 10",
+            "B, S, 115, 10",
+            "A, 1, 1",
+            "B, S, 50, 10",
+            "A, 2, 2",
+            "T, 2, 2, 1, 1, 50, 10",
+            "E, 2, 2, 5",
+            "B, S, -, -",
+        ]
+    );
+}
+
+#[test]
+fn volatility_band_breach_interrupts_the_symbol_until_its_resume_time() {
+    // ±10% around the last trade; 30s volatility auction.
+    let clock = ManualClock::new(1_000_000_000);
+    let mut engine = MatchingEngine::with_clock(Arc::new(clock.clone()));
+    engine.set_volatility_bands(Some(VolatilityBands {
+        band_bps: 1_000,
+        duration_ns: 30_000_000_000,
+        action: VolatilityAction::Auction,
+    }));
+
+    let mut outputs = run_scenario_on(
+        &mut engine,
+        &[
+            "N, 1, IBM, 100, 10, B, 1",
+            "N, 2, IBM, 100, 10, S, 1",
+            "N, 2, IBM, 105, 10, S, 2",
+            "N, 2, IBM, 120, 10, S, 3",
+            // Trades at 105, stopped at 120: the rest of the market order
+            // expires and IBM goes into its volatility auction.
+            "N, 3, IBM, 0, 30, B, 1",
+            "N, 1, IBM, 120, 5, B, 2",
+        ],
+    );
+    // Not over yet: no resume.
+    clock.advance(29_000_000_000);
+    outputs.extend(run_scenario_on(&mut engine, &["N, 1, IBM, 90, 5, B, 3"]));
+    // Over: the next request uncrosses the auction first.
+    clock.advance(1_000_000_000);
+    outputs.extend(run_scenario_on(&mut engine, &["C, 1, 3"]));
+    assert_eq!(
+        outputs,
+        vec![
+            "A, 1, 1",
+            "B, B, 100, 10",
+            "A, 2, 1",
+            "T, 1, 1, 2, 1, 100, 10",
+            "B, B, -, -",
+            "A, 2, 2",
+            "B, S, 105, 10",
+            "A, 2, 3",
+            "A, 3, 1",
+            "T, 3, 1, 2, 2, 105, 10",
+            "E, 3, 1, 20",
+            "B, S, 120, 10",
+            "V, IBM, 100, 120, 31000000000",
+            "H, IBM, AUCTION",
+            "I, IBM, 0, 0, 0, B",
+            "A, 1, 2",
+            "B, B, 120, 5",
+            "I, IBM, 120, 5, 5, S",
+            "A, 1, 3",
+            "H, IBM, CONTINUOUS",
+            "T, 1, 2, 2, 3, 120, 5",
+            "B, B, 90, 5",
+            "B, S, 120, 5",
+            "C, 1, 3",
+            "B, B, -, -",
+        ]
+    );
+}
+
+#[test]
+fn volatility_halt_publishes_nothing_until_it_resumes() {
+    // ±10% around the last trade; 30s halt.
+    let clock = ManualClock::new(1_000_000_000);
+    let mut engine = MatchingEngine::with_clock(Arc::new(clock.clone()));
+    engine.set_volatility_bands(Some(VolatilityBands {
+        band_bps: 1_000,
+        duration_ns: 30_000_000_000,
+        action: VolatilityAction::Halt,
+    }));
+
+    let mut outputs = run_scenario_on(
+        &mut engine,
+        &[
+            "N, 1, IBM, 100, 10, B, 1",
+            "N, 2, IBM, 100, 10, S, 1",
+            "N, 2, IBM, 105, 10, S, 2",
+            "N, 2, IBM, 120, 10, S, 3",
+            "N, 2, IBM, 121, 5, S, 4",
+            // Trades at 105, stopped at 120: the rest rests crossing it
+            // and IBM is halted.
+            "N, 3, IBM, 125, 30, B, 1",
+            // No indicative while halted.
+            "C, 2, 4",
+        ],
+    );
+    assert_eq!(engine.next_volatility_resume(), Some((31_000_000_000, "IBM")));
+
+    // Due: the server resumes it without waiting for other traffic.
+    clock.advance(30_000_000_000);
+    outputs.extend(run_scenario_on(&mut engine, &["S, IBM, CONTINUOUS"]));
+    assert_eq!(engine.next_volatility_resume(), None);
+    assert_eq!(
+        outputs,
+        vec![
+            "A, 1, 1",
+            "B, B, 100, 10",
+            "A, 2, 1",
+            "T, 1, 1, 2, 1, 100, 10",
+            "B, B, -, -",
+            "A, 2, 2",
+            "B, S, 105, 10",
+            "A, 2, 3",
+            "A, 2, 4",
+            "A, 3, 1",
+            "T, 3, 1, 2, 2, 105, 10",
+            "B, B, 125, 20",
+            "B, S, 120, 10",
+            "V, IBM, 100, 120, 31000000000",
+            "H, IBM, HALTED",
+            "C, 2, 4",
+            "H, IBM, CONTINUOUS",
+            "T, 3, 1, 2, 3, 120, 10",
+            "B, B, 125, 10",
+            "B, S, -, -",
+        ]
+    );
+}
@@ -0,0 +1,109 @@
+// crates/engine-core/tests/snapshot.rs
+use std::sync::Arc;
+
+use engine_core::{
+    Cancel, FlushCommand, InputMessage, ManualClock, MatchingEngine, NewOrder, OrderFlags, Replace,
+    SelfTradePrevention, Side, TimeInForce,
+};
+use engine_protocol::encode_output;
+
+fn new_order(user_id: u32, user_order_id: u32, symbol: &str, price: u32, quantity: u32, side: Side) -> InputMessage {
+    InputMessage::NewOrder(NewOrder {
+        user_id,
+        symbol: symbol.to_string(),
+        price,
+        quantity,
+        side,
+        user_order_id,
+        time_in_force: TimeInForce::Day,
+        stop_price: 0,
+        display_qty: 0,
+        flags: OrderFlags::NONE,
+        self_trade_prevention: SelfTradePrevention::None,
+    })
+}
+
+fn stop(user_id: u32, user_order_id: u32, symbol: &str, price: u32, quantity: u32, side: Side, stop_price: u32) -> InputMessage {
+    match new_order(user_id, user_order_id, symbol, price, quantity, side) {
+        InputMessage::NewOrder(order) => InputMessage::NewOrder(NewOrder { stop_price, ..order }),
+        _ => unreachable!(),
+    }
+}
+
+fn iceberg(user_id: u32, user_order_id: u32, symbol: &str, price: u32, quantity: u32, side: Side, display_qty: u32) -> InputMessage {
+    match new_order(user_id, user_order_id, symbol, price, quantity, side) {
+        InputMessage::NewOrder(order) => InputMessage::NewOrder(NewOrder { display_qty, ..order }),
+        _ => unreachable!(),
+    }
+}
+
+fn run(engine: &mut MatchingEngine, inputs: &[InputMessage]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for msg in inputs {
+        for out in engine.process_message(msg.clone()) {
+            encode_output(&out, &mut bytes).unwrap();
+        }
+    }
+    bytes
+}
+
+#[test]
+fn restored_engine_produces_identical_outputs() {
+    let clock = ManualClock::new(1_000);
+    let mut original = MatchingEngine::with_clock(Arc::new(clock.clone()));
+    original.set_execution_reports(true);
+    run(
+        &mut original,
+        &[
+            new_order(1, 1, "IBM", 10, 100, Side::Buy),
+            new_order(2, 1, "IBM", 10, 50, Side::Buy),
+            new_order(3, 1, "IBM", 12, 70, Side::Sell),
+            new_order(3, 2, "IBM", 9, 30, Side::Sell), // partially fills 1/1
+            new_order(4, 1, "MSFT", 20, 10, Side::Sell),
+            iceberg(4, 2, "MSFT", 20, 30, Side::Sell, 10),
+            stop(6, 1, "IBM", 0, 20, Side::Sell, 9), // held: last trade is 10
+            InputMessage::Replace(Replace {
+                user_id: 2,
+                user_order_id: 1,
+                new_price: None,
+                new_quantity: Some(40),
+            }),
+        ],
+    );
+
+    let mut file = Vec::new();
+    original.snapshot(7, &mut file).unwrap();
+    let (mut restored, journal_seq) =
+        MatchingEngine::restore_with_clock(file.as_slice(), Arc::new(clock.clone())).unwrap();
+    assert_eq!(journal_seq, 7);
+    restored.set_execution_reports(true);
+
+    let mut again = Vec::new();
+    restored.snapshot(7, &mut again).unwrap();
+    assert_eq!(again, file);
+
+    let tail = [
+        new_order(5, 1, "IBM", 10, 100, Side::Sell), // queue order: 1/1 before 2/1
+        new_order(7, 1, "IBM", 9, 30, Side::Buy),
+        new_order(8, 1, "IBM", 0, 20, Side::Sell), // trades at 9: triggers 6/1
+        InputMessage::Cancel(Cancel {
+            user_id: 3,
+            user_order_id: 1,
+        }),
+        new_order(5, 2, "MSFT", 0, 25, Side::Buy), // 4/1, then 4/2 refills once
+        new_order(1, 1, "IBM", 11, 10, Side::Buy), // 1/1 is filled, id reusable
+        InputMessage::Flush(FlushCommand::default()),
+    ];
+    clock.advance(1_000);
+    assert_eq!(run(&mut restored, &tail), run(&mut original, &tail));
+}
+
+#[test]
+fn restore_rejects_garbage() {
+    assert!(MatchingEngine::restore(&b"NOTSNAP"[..]).is_err());
+
+    let mut file = Vec::new();
+    MatchingEngine::new().snapshot(0, &mut file).unwrap();
+    file.truncate(file.len() - 1);
+    assert!(MatchingEngine::restore(file.as_slice()).is_err());
+}
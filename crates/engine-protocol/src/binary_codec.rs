@@ -20,20 +20,96 @@
 //!   [12..16] price (u32 BE)
 //!   [16..20] quantity (u32 BE)
 //!   [20]     side (0=Buy, 1=Sell)
-//!   [21]     symbol_len (u8, 1..=MAX_SYMBOL_LEN)
-//!   [22..]   symbol bytes (UTF-8)
+//!   [21]     time_in_force (0=Day, 1=GTC, 2=IOC, 3=FOK)
+//!   [22..26] stop_price (u32 BE, 0 = not a stop order)
+//!   [26..30] display_qty (u32 BE, 0 = fully displayed)
+//!   [30]     flags (u8: 0x01 post-only, 0x02 post-only reprice, 0x04 hidden)
+//!   [31]     self_trade_prevention (u8: 0=none, 1=cancel newest,
+//!            2=cancel oldest, 3=cancel both, 4=decrement and cancel)
+//!   [32]     symbol_len (u8, 1..=MAX_SYMBOL_LEN)
+//!   [33..]   symbol bytes (UTF-8)
 //!
 //! Cancel (type=1):
 //!   [4..8]   user_id (u32 BE)
 //!   [8..12]  user_order_id (u32 BE)
 //!
 //! Flush (type=2):
-//!   [no body]
+//!   [4]      token_len (u8)
+//!   [5..]    admin_token (UTF-8)
 //!
 //! QueryTopOfBook (type=3):
 //!   [4]      symbol_len (u8, 1..=MAX_SYMBOL_LEN)
 //!   [5..]    symbol bytes
 //!
+//! Replace (type=4):
+//!   [4..8]   user_id (u32 BE)
+//!   [8..12]  user_order_id (u32 BE)
+//!   [12..16] new_price (u32 BE, 0 = unchanged)
+//!   [16..20] new_quantity (u32 BE, 0 = unchanged)
+//!
+//! QueryDepth (type=5):
+//!   [4..8]   levels per side (u32 BE, 0 = all)
+//!   [8]      symbol_len (u8, 1..=MAX_SYMBOL_LEN)
+//!   [9..]    symbol bytes
+//!
+//! Auction (type=6):
+//!   [4]      action (0=Start, 1=Uncross)
+//!   [5]      symbol_len (u8, 1..=MAX_SYMBOL_LEN)
+//!   [6..]    symbol bytes
+//!   [..]     token_len (u8), then admin_token (UTF-8)
+//!
+//! SetTradingState (type=7):
+//!   [4]      state (0=PreOpen, 1=Auction, 2=Continuous, 3=Halted, 4=Closed)
+//!   [5]      symbol_len (u8, 1..=MAX_SYMBOL_LEN)
+//!   [6..]    symbol bytes
+//!   [..]     token_len (u8), then admin_token (UTF-8)
+//!
+//! QueryInstrument (type=8):
+//!   [4]      symbol_len (u8, 1..=MAX_SYMBOL_LEN)
+//!   [5..]    symbol bytes
+//!
+//! SetRiskLimits (type=9; limits 0 = none):
+//!   [4..8]   user_id (u32 BE, 0 = default for every user)
+//!   [8..12]  max_order_qty (u32 BE)
+//!   [12..20] max_notional (u64 BE)
+//!   [20..24] max_open_orders (u32 BE)
+//!   [24..32] max_position (u64 BE)
+//!   [32..40] max_gross_exposure (u64 BE)
+//!   [40]     token_len (u8)
+//!   [41..]   admin_token (UTF-8)
+//!
+//! Logon (type=10):
+//!   [4]      cancel_on_disconnect (0 or 1)
+//!   [5..9]   heartbeat_secs (u32 BE, 0 = no heartbeat timeout)
+//!   [9]      username_len (u8, 0 = no credentials)
+//!   [10..]   username (UTF-8)
+//!   [..]     secret_len (u8), then secret (UTF-8)
+//!
+//! Heartbeat (type=11):
+//!   [no body]
+//!
+//! MassCancel (type=12):
+//!   [4..8]   user_id (u32 BE)
+//!   [8]      side (0=Buy, 1=Sell, 2=both)
+//!   [9..13]  min_price (u32 BE, 0 = no lower bound)
+//!   [13..17] max_price (u32 BE, 0 = no upper bound)
+//!   [17]     symbol_len (u8, 0..=MAX_SYMBOL_LEN; 0 = every symbol)
+//!   [18..]   symbol bytes
+//!
+//! KillSwitch (type=13):
+//!   [4..8]   user_id (u32 BE, 0 = every user)
+//!   [8]      cancel_orders (0 or 1)
+//!   [9]      token_len (u8)
+//!   [10..]   admin_token (UTF-8)
+//!
+//! EnableTrading (type=14):
+//!   [4..8]   user_id (u32 BE, 0 = every user)
+//!   [8]      token_len (u8)
+//!   [9..]    admin_token (UTF-8)
+//!
+//! Logout (type=15):
+//!   [no body]
+//!
 //! Output (server → client)
 //! ------------------------
 //! [0]   : msg_type (WireOutputType as u8)
@@ -62,6 +138,8 @@
 //!   [...+4]  user_order_id_sell (u32 BE)
 //!   [...+4]  price (u32 BE)
 //!   [...+4]  quantity (u32 BE)
+//!   [...+1]  aggressor_side (0=Buy, 1=Sell)
+//!   [...+8]  timestamp_ns (u64 BE)
 //!
 //! TopOfBook (type=13):
 //!   [4]      symbol_len (u8)
@@ -70,6 +148,135 @@
 //!   [...+1]  eliminated (0/1)
 //!   [...+4]  price (u32 BE, ignored if eliminated)
 //!   [...+4]  total_quantity (u32 BE, ignored if eliminated)
+//!
+//! Expired (type=14):
+//!   [4..8]   user_id (u32 BE)
+//!   [8..12]  user_order_id (u32 BE)
+//!   [12..16] remaining_qty (u32 BE)
+//!   [16]     symbol_len (u8)
+//!   [17..]   symbol
+//!
+//! ReplaceAck (type=15):
+//!   [4..8]   user_id (u32 BE)
+//!   [8..12]  user_order_id (u32 BE)
+//!   [12..16] price (u32 BE)
+//!   [16..20] remaining_qty (u32 BE)
+//!   [20]     symbol_len (u8)
+//!   [21..]   symbol
+//!
+//! Reject (type=16):
+//!   [4..8]   user_id (u32 BE)
+//!   [8..12]  user_order_id (u32 BE)
+//!   [12]     reason (RejectReason as u8)
+//!   [13]     symbol_len (u8, 0..=MAX_SYMBOL_LEN; 0 = unknown)
+//!   [14..]   symbol
+//!
+//! PublicTrade (type=17):
+//!   [4]      symbol_len (u8)
+//!   [5..]    symbol
+//!   [...+4]  price (u32 BE)
+//!   [...+4]  quantity (u32 BE)
+//!   [...+1]  aggressor_side (0=Buy, 1=Sell)
+//!   [...+8]  timestamp_ns (u64 BE)
+//!
+//! DepthUpdate (type=18):
+//!   [4]      symbol_len (u8)
+//!   [5..]    symbol
+//!   [...+1]  snapshot (0/1)
+//!   [...+2]  event_count (u16 BE)
+//!   then event_count times:
+//!   [+0]     action (0=Add, 1=Change, 2=Delete)
+//!   [+1]     side (0=Bid, 1=Ask)
+//!   [+2..6]  price (u32 BE)
+//!   [+6..10] quantity (u32 BE)
+//!
+//! Triggered (type=19):
+//!   [4..8]   user_id (u32 BE)
+//!   [8..12]  user_order_id (u32 BE)
+//!   [12..16] stop_price (u32 BE)
+//!   [16..20] last_trade_price (u32 BE)
+//!   [20]     symbol_len (u8)
+//!   [21..]   symbol
+//!
+//! SelfTradeCancel (type=20):
+//!   [4..8]   user_id (u32 BE)
+//!   [8..12]  user_order_id (u32 BE)
+//!   [12..16] cancelled_qty (u32 BE)
+//!   [16..20] remaining_qty (u32 BE)
+//!   [20]     symbol_len (u8)
+//!   [21..]   symbol
+//!
+//! IndicativeUncross (type=21):
+//!   [4..8]   price (u32 BE, 0 = no cross)
+//!   [8..12]  volume (u32 BE)
+//!   [12..16] imbalance_qty (u32 BE)
+//!   [16]     imbalance_side (0=Buy, 1=Sell)
+//!   [17]     symbol_len (u8)
+//!   [18..]   symbol
+//!
+//! TradingStatus (type=22):
+//!   [4]      state (as in SetTradingState)
+//!   [5]      symbol_len (u8)
+//!   [6..]    symbol
+//!
+//! InstrumentDefinition (type=23; limits 0 = none):
+//!   [4..8]   tick_size (u32 BE)
+//!   [8..12]  lot_size (u32 BE)
+//!   [12..16] min_qty (u32 BE)
+//!   [16..20] max_qty (u32 BE)
+//!   [20..24] min_price (u32 BE)
+//!   [24..28] max_price (u32 BE)
+//!   [28..32] market_protection_ticks (u32 BE)
+//!   [32]     symbol_len (u8)
+//!   [33..]   symbol
+//!
+//! VolatilityInterruption (type=24):
+//!   [4..8]   reference_price (u32 BE)
+//!   [8..12]  trigger_price (u32 BE)
+//!   [12..20] resume_ns (u64 BE)
+//!   [20]     symbol_len (u8)
+//!   [21..]   symbol
+//!
+//! ExecutionReport (type=25):
+//!   [4..8]   user_id (u32 BE)
+//!   [8..12]  user_order_id (u32 BE)
+//!   [12..20] exec_id (u64 BE)
+//!   [20]     exec_type (ExecType as u8)
+//!   [21]     order_status (OrdStatus as u8)
+//!   [22..26] last_qty (u32 BE)
+//!   [26..30] last_price (u32 BE)
+//!   [30..34] cum_qty (u32 BE)
+//!   [34..38] leaves_qty (u32 BE)
+//!   [38..46] timestamp_ns (u64 BE)
+//!   [46]     symbol_len (u8, 0..=MAX_SYMBOL_LEN; 0 = unknown)
+//!   [47..]   symbol
+//!
+//! MassCancelAck (type=26):
+//!   [4..8]   user_id (u32 BE)
+//!   [8..12]  cancelled_count (u32 BE)
+//!   [12]     symbol_len (u8, 0..=MAX_SYMBOL_LEN; 0 = every symbol)
+//!   [13..]   symbol
+//!
+//! UserTradingStatus (type=27):
+//!   [4..8]   user_id (u32 BE, 0 = every user)
+//!   [8]      enabled (0 or 1)
+//!
+//! SessionStatus (type=28):
+//!   [4]      logged_on (0 or 1)
+//!   [5]      username_len (u8)
+//!   [6..]    username (UTF-8)
+//!
+//! Market-by-order feed (`OrderBookEvent`)
+//! ---------------------------------------
+//! Same header; every body starts with the symbol and exchange order id:
+//!   [4]      symbol_len (u8)
+//!   [5..]    symbol
+//!   [...+8]  order_id (u64 BE; old_order_id for Replace)
+//!
+//! Add (type=30):      side (u8), price (u32 BE), quantity (u32 BE)
+//! Execute (type=31):  price (u32 BE), executed_qty (u32 BE), remaining_qty (u32 BE)
+//! Cancel (type=32):   cancelled_qty (u32 BE), remaining_qty (u32 BE)
+//! Replace (type=33):  new_order_id (u64 BE), price (u32 BE), quantity (u32 BE)
 //! ```
 //!
 //! NOTE: This module encodes/decodes **one message per buffer**. A TCP
@@ -80,12 +287,16 @@ use std::convert::TryFrom;
 use std::fmt;
 
 use engine_core::{
-    Ack, Cancel, CancelAck, InputMessage, NewOrder, OutputMessage, Side, TopOfBook, TopOfBookQuery,
-    Trade,
+    Ack, AuctionAction, AuctionCommand, Cancel, CancelAck, DepthAction, DepthEvent, DepthQuery, DepthUpdate, ExecType,
+    EnableTrading, ExecutionReport, Expired, OrdStatus,
+    IndicativeUncross, InputMessage, Instrument, InstrumentQuery, KillSwitch, Logon, MassCancel, MassCancelAck, NewOrder, OrderBookEvent, OrderFlags, OutputMessage, PublicTrade, Reject, RejectReason, Replace,
+    FlushCommand, ReplaceAck, RiskLimits, SelfTradeCancel, SelfTradePrevention, SessionStatus, SetRiskLimits, SetTradingState, Side, TimeInForce, TopOfBook, TopOfBookQuery,
+    Trade, TradingState, TradingStatus, Triggered, UserTradingStatus, VolatilityInterruption,
 };
 
 use crate::wire_types::{
-    validate_symbol_len, MAX_SYMBOL_LEN, PROTOCOL_VERSION, WireInputType, WireOutputType,
+    validate_symbol_len, MAX_SYMBOL_LEN, PROTOCOL_VERSION, WireBookEventType, WireInputType,
+    WireOutputType,
 };
 
 /// Errors that can arise when encoding/decoding a binary frame.
@@ -123,29 +334,92 @@ impl std::error::Error for ProtocolError {}
 // INPUT: client → server
 // ============================================================================
 
+/// Oldest protocol version whose input frames [`decode_input`] still
+/// reads.
+pub const OLDEST_VERSION: u8 = 1;
+
 /// Decode a single input message from a binary buffer.
 ///
-/// The buffer must contain exactly one full message as described above.
+/// The buffer must contain exactly one full message as described above,
+/// written by this or an earlier protocol version (back to
+/// [`OLDEST_VERSION`]): clients built against an older version keep
+/// working, and journals survive upgrades. Older layouts are upgraded to
+/// the current one first, with the fields added since at their defaults.
 pub fn decode_input(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
     if buf.len() < 4 {
         return Err(ProtocolError::Truncated);
     }
 
-    let msg_type = buf[0];
     let version = buf[1];
-
-    if version != PROTOCOL_VERSION {
+    if !(OLDEST_VERSION..=PROTOCOL_VERSION).contains(&version) {
         return Err(ProtocolError::VersionMismatch(version));
     }
 
+    if version == PROTOCOL_VERSION {
+        decode_input_body(buf)
+    } else {
+        decode_input_body(&upgrade_input(buf, version))
+    }
+}
+
+/// Rewrite an input frame of an older `version` in the current layout.
+///
+/// Input layouts changed since: `NewOrder` gained its time in force (2),
+/// stop price (6), display quantity (7), flags (8) and self-trade
+/// prevention (9) before the symbol, `Logon` its credentials (20), `SetRiskLimits`
+/// its admin token (21) and `Flush`, `Auction` and `SetTradingState`
+/// theirs (22) at the end.
+fn upgrade_input(buf: &[u8], version: u8) -> Vec<u8> {
+    let mut frame = buf.to_vec();
+    frame[1] = PROTOCOL_VERSION;
+
+    match WireInputType::from_u8(frame[0]) {
+        Some(WireInputType::NewOrder) => {
+            for (since, at, len) in [(2, 21, 1), (6, 22, 4), (7, 26, 4), (8, 30, 1), (9, 31, 1)] {
+                if version < since && at <= frame.len() {
+                    frame.splice(at..at, std::iter::repeat_n(0, len));
+                }
+            }
+        }
+        Some(WireInputType::Logon) if version < 20 => {
+            // No username, no secret.
+            frame.extend_from_slice(&[0, 0]);
+        }
+        Some(WireInputType::SetRiskLimits) if version < 21 => {
+            // No admin token.
+            frame.push(0);
+        }
+        Some(WireInputType::Flush | WireInputType::Auction | WireInputType::SetTradingState) if version < 22 => {
+            // No admin token.
+            frame.push(0);
+        }
+        _ => {}
+    }
+    frame
+}
+
+fn decode_input_body(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    let msg_type = buf[0];
     let wire_type =
         WireInputType::from_u8(msg_type).ok_or(ProtocolError::UnknownMessageType(msg_type))?;
 
     match wire_type {
         WireInputType::NewOrder => decode_new_order(buf),
         WireInputType::Cancel => decode_cancel(buf),
-        WireInputType::Flush => Ok(InputMessage::Flush),
+        WireInputType::Flush => decode_flush(buf),
         WireInputType::QueryTopOfBook => decode_query_tob(buf),
+        WireInputType::Replace => decode_replace(buf),
+        WireInputType::QueryDepth => decode_query_depth(buf),
+        WireInputType::Auction => decode_auction(buf),
+        WireInputType::SetTradingState => decode_set_trading_state(buf),
+        WireInputType::QueryInstrument => decode_query_instrument(buf),
+        WireInputType::SetRiskLimits => decode_set_risk_limits(buf),
+        WireInputType::Logon => decode_logon(buf),
+        WireInputType::Heartbeat => Ok(InputMessage::Heartbeat),
+        WireInputType::MassCancel => decode_mass_cancel(buf),
+        WireInputType::KillSwitch => decode_kill_switch(buf),
+        WireInputType::EnableTrading => decode_enable_trading(buf),
+        WireInputType::Logout => Ok(InputMessage::Logout),
     }
 }
 
@@ -156,13 +430,25 @@ pub fn encode_input(msg: &InputMessage, out: &mut Vec<u8>) -> Result<(), Protoco
     match msg {
         InputMessage::NewOrder(n) => encode_input_new_order(n, out),
         InputMessage::Cancel(c) => encode_input_cancel(c, out),
-        InputMessage::Flush => encode_input_flush(out),
+        InputMessage::Flush(f) => encode_input_flush(f, out),
         InputMessage::QueryTopOfBook(q) => encode_input_query_tob(q, out),
+        InputMessage::Replace(r) => encode_input_replace(r, out),
+        InputMessage::QueryDepth(q) => encode_input_query_depth(q, out),
+        InputMessage::Auction(a) => encode_input_auction(a, out),
+        InputMessage::SetTradingState(s) => encode_input_set_trading_state(s, out),
+        InputMessage::QueryInstrument(q) => encode_input_query_instrument(q, out),
+        InputMessage::SetRiskLimits(l) => encode_input_set_risk_limits(l, out),
+        InputMessage::Logon(l) => encode_input_logon(l, out),
+        InputMessage::Heartbeat => encode_input_heartbeat(out),
+        InputMessage::MassCancel(m) => encode_input_mass_cancel(m, out),
+        InputMessage::KillSwitch(k) => encode_input_kill_switch(k, out),
+        InputMessage::EnableTrading(e) => encode_input_enable_trading(e, out),
+        InputMessage::Logout => encode_input_logout(out),
     }
 }
 
 fn decode_new_order(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
-    if buf.len() < 22 {
+    if buf.len() < 33 {
         return Err(ProtocolError::Truncated);
     }
 
@@ -178,24 +464,34 @@ fn decode_new_order(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
         _ => return Err(ProtocolError::InvalidField("side")),
     };
 
-    let symbol_len = buf[21] as usize;
+    let time_in_force = match buf[21] {
+        0 => TimeInForce::Day,
+        1 => TimeInForce::Gtc,
+        2 => TimeInForce::Ioc,
+        3 => TimeInForce::Fok,
+        _ => return Err(ProtocolError::InvalidField("time_in_force")),
+    };
+
+    let stop_price = read_u32_be(&buf[22..26]);
+    let display_qty = read_u32_be(&buf[26..30]);
+    let flags = OrderFlags::from_bits(buf[30]).ok_or(ProtocolError::InvalidField("flags"))?;
+    let self_trade_prevention = SelfTradePrevention::from_u8(buf[31])
+        .ok_or(ProtocolError::InvalidField("self_trade_prevention"))?;
+
+    let symbol_len = buf[32] as usize;
     if !validate_symbol_len(symbol_len) {
         return Err(ProtocolError::InvalidSymbol);
     }
 
-    if buf.len() < 22 + symbol_len {
+    if buf.len() < 33 + symbol_len {
         return Err(ProtocolError::Truncated);
     }
 
-    let symbol_bytes = &buf[22..22 + symbol_len];
+    let symbol_bytes = &buf[33..33 + symbol_len];
     let symbol = std::str::from_utf8(symbol_bytes)
         .map_err(|_| ProtocolError::InvalidSymbol)?
         .to_string();
 
-    if quantity == 0 {
-        return Err(ProtocolError::InvalidField("quantity"));
-    }
-
     Ok(InputMessage::NewOrder(NewOrder {
         user_id,
         symbol,
@@ -203,6 +499,11 @@ fn decode_new_order(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
         quantity,
         side,
         user_order_id,
+        time_in_force,
+        stop_price,
+        display_qty,
+        flags,
+        self_trade_prevention,
     }))
 }
 
@@ -220,6 +521,24 @@ fn decode_cancel(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
     }))
 }
 
+fn decode_replace(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 20 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let new_price = read_u32_be(&buf[12..16]);
+    let new_quantity = read_u32_be(&buf[16..20]);
+
+    Ok(InputMessage::Replace(Replace {
+        user_id,
+        user_order_id,
+        new_price: (new_price != 0).then_some(new_price),
+        new_quantity: (new_quantity != 0).then_some(new_quantity),
+    }))
+}
+
 fn decode_query_tob(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
     if buf.len() < 5 {
         return Err(ProtocolError::Truncated);
@@ -242,6 +561,234 @@ fn decode_query_tob(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
     Ok(InputMessage::QueryTopOfBook(TopOfBookQuery { symbol }))
 }
 
+fn decode_query_depth(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 9 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let levels = read_u32_be(&buf[4..8]);
+
+    let symbol_len = buf[8] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 9 + symbol_len {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[9..9 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(InputMessage::QueryDepth(DepthQuery { symbol, levels }))
+}
+
+fn decode_flush(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 5 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let admin_token = read_short_str(&buf[4..], "admin_token")?;
+
+    Ok(InputMessage::Flush(FlushCommand { admin_token }))
+}
+
+fn decode_auction(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 6 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let action = AuctionAction::from_u8(buf[4]).ok_or(ProtocolError::InvalidField("action"))?;
+
+    let symbol_len = buf[5] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 6 + symbol_len {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[6..6 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    let admin_token = read_short_str(&buf[6 + symbol_len..], "admin_token")?;
+
+    Ok(InputMessage::Auction(AuctionCommand {
+        symbol,
+        action,
+        admin_token,
+    }))
+}
+
+fn decode_set_trading_state(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 6 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let state = TradingState::from_u8(buf[4]).ok_or(ProtocolError::InvalidField("state"))?;
+
+    let symbol_len = buf[5] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 6 + symbol_len {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[6..6 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    let admin_token = read_short_str(&buf[6 + symbol_len..], "admin_token")?;
+
+    Ok(InputMessage::SetTradingState(SetTradingState {
+        symbol,
+        state,
+        admin_token,
+    }))
+}
+
+fn decode_query_instrument(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 5 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_len = buf[4] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 5 + symbol_len {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[5..5 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(InputMessage::QueryInstrument(InstrumentQuery { symbol }))
+}
+
+fn decode_set_risk_limits(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 41 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let limits = RiskLimits {
+        max_order_qty: read_u32_be(&buf[8..12]),
+        max_notional: read_u64_be(&buf[12..20]),
+        max_open_orders: read_u32_be(&buf[20..24]),
+        max_position: read_u64_be(&buf[24..32]),
+        max_gross_exposure: read_u64_be(&buf[32..40]),
+    };
+
+    let admin_token = read_short_str(&buf[40..], "admin_token")?;
+
+    Ok(InputMessage::SetRiskLimits(SetRiskLimits {
+        user_id,
+        limits,
+        admin_token,
+    }))
+}
+
+fn decode_logon(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 9 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let cancel_on_disconnect = match buf[4] {
+        0 => false,
+        1 => true,
+        _ => return Err(ProtocolError::InvalidField("cancel_on_disconnect")),
+    };
+    let heartbeat_secs = read_u32_be(&buf[5..9]);
+    let username = read_short_str(&buf[9..], "username")?;
+    let secret = read_short_str(&buf[10 + username.len()..], "secret")?;
+
+    Ok(InputMessage::Logon(Logon {
+        cancel_on_disconnect,
+        heartbeat_secs,
+        username,
+        secret,
+    }))
+}
+
+fn decode_kill_switch(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 10 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let cancel_orders = match buf[8] {
+        0 => false,
+        1 => true,
+        _ => return Err(ProtocolError::InvalidField("cancel_orders")),
+    };
+    let admin_token = read_short_str(&buf[9..], "admin_token")?;
+
+    Ok(InputMessage::KillSwitch(KillSwitch {
+        user_id,
+        cancel_orders,
+        admin_token,
+    }))
+}
+
+fn decode_enable_trading(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 9 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let admin_token = read_short_str(&buf[8..], "admin_token")?;
+
+    Ok(InputMessage::EnableTrading(EnableTrading { user_id, admin_token }))
+}
+
+fn decode_mass_cancel(buf: &[u8]) -> Result<InputMessage, ProtocolError> {
+    if buf.len() < 18 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let side = match buf[8] {
+        2 => None,
+        byte => Some(decode_side(byte)?),
+    };
+    let min_price = Some(read_u32_be(&buf[9..13])).filter(|&p| p > 0);
+    let max_price = Some(read_u32_be(&buf[13..17])).filter(|&p| p > 0);
+
+    let symbol_len = buf[17] as usize;
+    if symbol_len > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 18 + symbol_len {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[18..18 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(InputMessage::MassCancel(MassCancel {
+        user_id,
+        symbol: Some(symbol).filter(|s| !s.is_empty()),
+        side,
+        min_price,
+        max_price,
+    }))
+}
+
 fn encode_input_new_order(n: &NewOrder, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
     let symbol_bytes = n.symbol.as_bytes();
     if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
@@ -263,6 +810,18 @@ fn encode_input_new_order(n: &NewOrder, out: &mut Vec<u8>) -> Result<(), Protoco
     };
     out.push(side_byte);
 
+    let tif_byte = match n.time_in_force {
+        TimeInForce::Day => 0,
+        TimeInForce::Gtc => 1,
+        TimeInForce::Ioc => 2,
+        TimeInForce::Fok => 3,
+    };
+    out.push(tif_byte);
+    out.extend_from_slice(&n.stop_price.to_be_bytes());
+    out.extend_from_slice(&n.display_qty.to_be_bytes());
+    out.push(n.flags.bits());
+    out.push(n.self_trade_prevention as u8);
+
     out.push(u8::try_from(symbol_bytes.len()).unwrap());
     out.extend_from_slice(symbol_bytes);
 
@@ -280,10 +839,24 @@ fn encode_input_cancel(c: &Cancel, out: &mut Vec<u8>) -> Result<(), ProtocolErro
     Ok(())
 }
 
-fn encode_input_flush(out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+fn encode_input_replace(r: &Replace, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireInputType::Replace as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&r.user_id.to_be_bytes());
+    out.extend_from_slice(&r.user_order_id.to_be_bytes());
+    out.extend_from_slice(&r.new_price.unwrap_or(0).to_be_bytes());
+    out.extend_from_slice(&r.new_quantity.unwrap_or(0).to_be_bytes());
+
+    Ok(())
+}
+
+fn encode_input_flush(f: &FlushCommand, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
     out.push(WireInputType::Flush as u8);
     out.push(PROTOCOL_VERSION);
     out.extend_from_slice(&[0, 0]);
+    write_short_str(&f.admin_token, "admin_token", out)?;
     Ok(())
 }
 
@@ -303,19 +876,183 @@ fn encode_input_query_tob(q: &TopOfBookQuery, out: &mut Vec<u8>) -> Result<(), P
     Ok(())
 }
 
-// ============================================================================
-// OUTPUT: server → client
-// ============================================================================
+fn encode_input_query_depth(q: &DepthQuery, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = q.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
 
-/// Encode a single output message into a binary frame.
-///
-/// The encoded bytes are appended to `out`.
-pub fn encode_output(msg: &OutputMessage, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
-    match msg {
-        OutputMessage::Ack(a) => encode_ack(a, out),
-        OutputMessage::CancelAck(c) => encode_cancel_ack(c, out),
+    out.push(WireInputType::QueryDepth as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&q.levels.to_be_bytes());
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn encode_input_auction(a: &AuctionCommand, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = a.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireInputType::Auction as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(a.action as u8);
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+    write_short_str(&a.admin_token, "admin_token", out)?;
+
+    Ok(())
+}
+
+fn encode_input_set_trading_state(s: &SetTradingState, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = s.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireInputType::SetTradingState as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(s.state as u8);
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+    write_short_str(&s.admin_token, "admin_token", out)?;
+
+    Ok(())
+}
+
+fn encode_input_query_instrument(q: &InstrumentQuery, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = q.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireInputType::QueryInstrument as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn encode_input_set_risk_limits(l: &SetRiskLimits, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireInputType::SetRiskLimits as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&l.user_id.to_be_bytes());
+    out.extend_from_slice(&l.limits.max_order_qty.to_be_bytes());
+    out.extend_from_slice(&l.limits.max_notional.to_be_bytes());
+    out.extend_from_slice(&l.limits.max_open_orders.to_be_bytes());
+    out.extend_from_slice(&l.limits.max_position.to_be_bytes());
+    out.extend_from_slice(&l.limits.max_gross_exposure.to_be_bytes());
+    write_short_str(&l.admin_token, "admin_token", out)?;
+
+    Ok(())
+}
+
+fn encode_input_logon(l: &Logon, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireInputType::Logon as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(u8::from(l.cancel_on_disconnect));
+    out.extend_from_slice(&l.heartbeat_secs.to_be_bytes());
+    write_short_str(&l.username, "username", out)?;
+    write_short_str(&l.secret, "secret", out)
+}
+
+fn encode_input_logout(out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireInputType::Logout as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+    Ok(())
+}
+
+fn encode_input_heartbeat(out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireInputType::Heartbeat as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+    Ok(())
+}
+
+fn encode_input_kill_switch(k: &KillSwitch, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireInputType::KillSwitch as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&k.user_id.to_be_bytes());
+    out.push(u8::from(k.cancel_orders));
+    write_short_str(&k.admin_token, "admin_token", out)
+}
+
+fn encode_input_enable_trading(e: &EnableTrading, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireInputType::EnableTrading as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&e.user_id.to_be_bytes());
+    write_short_str(&e.admin_token, "admin_token", out)
+}
+
+fn encode_input_mass_cancel(m: &MassCancel, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = m.symbol.as_deref().unwrap_or("").as_bytes();
+    if symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireInputType::MassCancel as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&m.user_id.to_be_bytes());
+    out.push(m.side.map_or(2, encode_side));
+    out.extend_from_slice(&m.min_price.unwrap_or(0).to_be_bytes());
+    out.extend_from_slice(&m.max_price.unwrap_or(0).to_be_bytes());
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+// ============================================================================
+// OUTPUT: server → client
+// ============================================================================
+
+/// Encode a single output message into a binary frame.
+///
+/// The encoded bytes are appended to `out`.
+pub fn encode_output(msg: &OutputMessage, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    match msg {
+        OutputMessage::Ack(a) => encode_ack(a, out),
+        OutputMessage::CancelAck(c) => encode_cancel_ack(c, out),
         OutputMessage::Trade(t) => encode_trade(t, out),
+        OutputMessage::PublicTrade(t) => encode_public_trade(t, out),
+        OutputMessage::DepthUpdate(d) => encode_depth_update(d, out),
         OutputMessage::TopOfBook(tob) => encode_top_of_book(tob, out),
+        OutputMessage::Expired(e) => encode_expired(e, out),
+        OutputMessage::Triggered(t) => encode_triggered(t, out),
+        OutputMessage::SelfTradeCancel(c) => encode_self_trade_cancel(c, out),
+        OutputMessage::IndicativeUncross(i) => encode_indicative_uncross(i, out),
+        OutputMessage::TradingStatus(s) => encode_trading_status(s, out),
+        OutputMessage::InstrumentDefinition(i) => encode_instrument_definition(i, out),
+        OutputMessage::VolatilityInterruption(v) => encode_volatility_interruption(v, out),
+        OutputMessage::ExecutionReport(e) => encode_execution_report(e, out),
+        OutputMessage::MassCancelAck(m) => encode_mass_cancel_ack(m, out),
+        OutputMessage::UserTradingStatus(s) => encode_user_trading_status(s, out),
+        OutputMessage::SessionStatus(s) => encode_session_status(s, out),
+        OutputMessage::ReplaceAck(r) => encode_replace_ack(r, out),
+        OutputMessage::Reject(r) => encode_reject(r, out),
     }
 }
 
@@ -341,7 +1078,22 @@ pub fn decode_output(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
         WireOutputType::Ack => decode_ack(buf),
         WireOutputType::CancelAck => decode_cancel_ack(buf),
         WireOutputType::Trade => decode_trade(buf),
+        WireOutputType::PublicTrade => decode_public_trade(buf),
+        WireOutputType::DepthUpdate => decode_depth_update(buf),
         WireOutputType::TopOfBook => decode_top_of_book(buf),
+        WireOutputType::Expired => decode_expired(buf),
+        WireOutputType::Triggered => decode_triggered(buf),
+        WireOutputType::SelfTradeCancel => decode_self_trade_cancel(buf),
+        WireOutputType::IndicativeUncross => decode_indicative_uncross(buf),
+        WireOutputType::TradingStatus => decode_trading_status(buf),
+        WireOutputType::InstrumentDefinition => decode_instrument_definition(buf),
+        WireOutputType::VolatilityInterruption => decode_volatility_interruption(buf),
+        WireOutputType::ExecutionReport => decode_execution_report(buf),
+        WireOutputType::MassCancelAck => decode_mass_cancel_ack(buf),
+        WireOutputType::UserTradingStatus => decode_user_trading_status(buf),
+        WireOutputType::SessionStatus => decode_session_status(buf),
+        WireOutputType::ReplaceAck => decode_replace_ack(buf),
+        WireOutputType::Reject => decode_reject(buf),
     }
 }
 
@@ -404,6 +1156,56 @@ fn encode_trade(t: &Trade, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
     out.extend_from_slice(&t.user_order_id_sell.to_be_bytes());
     out.extend_from_slice(&t.price.to_be_bytes());
     out.extend_from_slice(&t.quantity.to_be_bytes());
+    out.push(encode_side(t.aggressor_side));
+    out.extend_from_slice(&t.timestamp_ns.to_be_bytes());
+
+    Ok(())
+}
+
+fn encode_public_trade(t: &PublicTrade, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = t.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireOutputType::PublicTrade as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    out.extend_from_slice(&t.price.to_be_bytes());
+    out.extend_from_slice(&t.quantity.to_be_bytes());
+    out.push(encode_side(t.aggressor_side));
+    out.extend_from_slice(&t.timestamp_ns.to_be_bytes());
+
+    Ok(())
+}
+
+fn encode_depth_update(d: &DepthUpdate, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = d.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+    let event_count =
+        u16::try_from(d.events.len()).map_err(|_| ProtocolError::InvalidField("event_count"))?;
+
+    out.push(WireOutputType::DepthUpdate as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    out.push(u8::from(d.snapshot));
+    out.extend_from_slice(&event_count.to_be_bytes());
+    for e in &d.events {
+        out.push(e.action as u8);
+        out.push(encode_side(e.side));
+        out.extend_from_slice(&e.price.to_be_bytes());
+        out.extend_from_slice(&e.quantity.to_be_bytes());
+    }
 
     Ok(())
 }
@@ -439,142 +1241,789 @@ fn encode_top_of_book(t: &TopOfBook, out: &mut Vec<u8>) -> Result<(), ProtocolEr
     Ok(())
 }
 
-fn decode_ack(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
-    if buf.len() < 13 {
-        return Err(ProtocolError::Truncated);
+fn encode_expired(e: &Expired, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = e.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
     }
 
-    let user_id = read_u32_be(&buf[4..8]);
-    let user_order_id = read_u32_be(&buf[8..12]);
-    let symbol_len = buf[12] as usize;
+    out.push(WireOutputType::Expired as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
 
-    if !validate_symbol_len(symbol_len) || buf.len() < 13 + symbol_len {
-        return Err(ProtocolError::InvalidSymbol);
-    }
+    out.extend_from_slice(&e.user_id.to_be_bytes());
+    out.extend_from_slice(&e.user_order_id.to_be_bytes());
+    out.extend_from_slice(&e.remaining_qty.to_be_bytes());
 
-    let symbol_bytes = &buf[13..13 + symbol_len];
-    let symbol = std::str::from_utf8(symbol_bytes)
-        .map_err(|_| ProtocolError::InvalidSymbol)?
-        .to_string();
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
 
-    Ok(OutputMessage::Ack(Ack {
-        user_id,
-        user_order_id,
-        symbol,
-    }))
+    Ok(())
 }
 
-fn decode_cancel_ack(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
-    if buf.len() < 13 {
-        return Err(ProtocolError::Truncated);
+fn encode_triggered(t: &Triggered, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = t.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
     }
 
-    let user_id = read_u32_be(&buf[4..8]);
-    let user_order_id = read_u32_be(&buf[8..12]);
-    let symbol_len = buf[12] as usize;
+    out.push(WireOutputType::Triggered as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
 
-    if !validate_symbol_len(symbol_len) || buf.len() < 13 + symbol_len {
-        return Err(ProtocolError::InvalidSymbol);
-    }
+    out.extend_from_slice(&t.user_id.to_be_bytes());
+    out.extend_from_slice(&t.user_order_id.to_be_bytes());
+    out.extend_from_slice(&t.stop_price.to_be_bytes());
+    out.extend_from_slice(&t.last_trade_price.to_be_bytes());
 
-    let symbol_bytes = &buf[13..13 + symbol_len];
-    let symbol = std::str::from_utf8(symbol_bytes)
-        .map_err(|_| ProtocolError::InvalidSymbol)?
-        .to_string();
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
 
-    Ok(OutputMessage::CancelAck(CancelAck {
-        user_id,
-        user_order_id,
-        symbol,
-    }))
+    Ok(())
 }
 
-fn decode_trade(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
-    if buf.len() < 5 {
-        return Err(ProtocolError::Truncated);
-    }
-
-    let symbol_len = buf[4] as usize;
-    if !validate_symbol_len(symbol_len) {
+fn encode_self_trade_cancel(c: &SelfTradeCancel, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = c.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
         return Err(ProtocolError::InvalidSymbol);
     }
 
-    if buf.len() < 5 + symbol_len + 4 * 6 {
-        return Err(ProtocolError::Truncated);
-    }
-
-    let symbol_bytes = &buf[5..5 + symbol_len];
-    let symbol = std::str::from_utf8(symbol_bytes)
-        .map_err(|_| ProtocolError::InvalidSymbol)?
-        .to_string();
+    out.push(WireOutputType::SelfTradeCancel as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
 
-    let mut offset = 5 + symbol_len;
+    out.extend_from_slice(&c.user_id.to_be_bytes());
+    out.extend_from_slice(&c.user_order_id.to_be_bytes());
+    out.extend_from_slice(&c.cancelled_qty.to_be_bytes());
+    out.extend_from_slice(&c.remaining_qty.to_be_bytes());
 
-    let user_id_buy = read_u32_be(&buf[offset..offset + 4]);
-    offset += 4;
-    let user_order_id_buy = read_u32_be(&buf[offset..offset + 4]);
-    offset += 4;
-    let user_id_sell = read_u32_be(&buf[offset..offset + 4]);
-    offset += 4;
-    let user_order_id_sell = read_u32_be(&buf[offset..offset + 4]);
-    offset += 4;
-    let price = read_u32_be(&buf[offset..offset + 4]);
-    offset += 4;
-    let quantity = read_u32_be(&buf[offset..offset + 4]);
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
 
-    Ok(OutputMessage::Trade(Trade {
-        symbol,
-        user_id_buy,
-        user_order_id_buy,
-        user_id_sell,
-        user_order_id_sell,
-        price,
-        quantity,
-    }))
+    Ok(())
 }
 
-fn decode_top_of_book(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
-    if buf.len() < 5 {
-        return Err(ProtocolError::Truncated);
-    }
-
-    let symbol_len = buf[4] as usize;
-    if !validate_symbol_len(symbol_len) {
+fn encode_indicative_uncross(i: &IndicativeUncross, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = i.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
         return Err(ProtocolError::InvalidSymbol);
     }
 
-    if buf.len() < 5 + symbol_len + 1 + 1 + 4 + 4 {
-        return Err(ProtocolError::Truncated);
-    }
+    out.push(WireOutputType::IndicativeUncross as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
 
-    let symbol_bytes = &buf[5..5 + symbol_len];
-    let symbol = std::str::from_utf8(symbol_bytes)
-        .map_err(|_| ProtocolError::InvalidSymbol)?
-        .to_string();
+    out.extend_from_slice(&i.price.to_be_bytes());
+    out.extend_from_slice(&i.volume.to_be_bytes());
+    out.extend_from_slice(&i.imbalance_qty.to_be_bytes());
+    out.push(encode_side(i.imbalance_side));
 
-    let mut offset = 5 + symbol_len;
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
 
-    let side_byte = buf[offset];
-    offset += 1;
-    let side = match side_byte {
-        0 => Side::Buy,
+    Ok(())
+}
+
+fn encode_trading_status(s: &TradingStatus, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = s.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireOutputType::TradingStatus as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(s.state as u8);
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn encode_instrument_definition(i: &Instrument, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = i.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireOutputType::InstrumentDefinition as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&i.tick_size.to_be_bytes());
+    out.extend_from_slice(&i.lot_size.to_be_bytes());
+    out.extend_from_slice(&i.min_qty.to_be_bytes());
+    out.extend_from_slice(&i.max_qty.to_be_bytes());
+    out.extend_from_slice(&i.min_price.to_be_bytes());
+    out.extend_from_slice(&i.max_price.to_be_bytes());
+    out.extend_from_slice(&i.market_protection_ticks.to_be_bytes());
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn encode_volatility_interruption(v: &VolatilityInterruption, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = v.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireOutputType::VolatilityInterruption as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&v.reference_price.to_be_bytes());
+    out.extend_from_slice(&v.trigger_price.to_be_bytes());
+    out.extend_from_slice(&v.resume_ns.to_be_bytes());
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn encode_execution_report(e: &ExecutionReport, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    // Like a Reject, the symbol may be empty (not known).
+    let symbol_bytes = e.symbol.as_bytes();
+    if symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireOutputType::ExecutionReport as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&e.user_id.to_be_bytes());
+    out.extend_from_slice(&e.user_order_id.to_be_bytes());
+    out.extend_from_slice(&e.exec_id.to_be_bytes());
+    out.push(e.exec_type as u8);
+    out.push(e.order_status as u8);
+    out.extend_from_slice(&e.last_qty.to_be_bytes());
+    out.extend_from_slice(&e.last_price.to_be_bytes());
+    out.extend_from_slice(&e.cum_qty.to_be_bytes());
+    out.extend_from_slice(&e.leaves_qty.to_be_bytes());
+    out.extend_from_slice(&e.timestamp_ns.to_be_bytes());
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn encode_mass_cancel_ack(m: &MassCancelAck, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    // The symbol is empty when every symbol was covered.
+    let symbol_bytes = m.symbol.as_bytes();
+    if symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireOutputType::MassCancelAck as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&m.user_id.to_be_bytes());
+    out.extend_from_slice(&m.cancelled_count.to_be_bytes());
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn encode_user_trading_status(s: &UserTradingStatus, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireOutputType::UserTradingStatus as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&s.user_id.to_be_bytes());
+    out.push(u8::from(s.enabled));
+
+    Ok(())
+}
+
+fn encode_session_status(s: &SessionStatus, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    out.push(WireOutputType::SessionStatus as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(u8::from(s.logged_on));
+    write_short_str(&s.username, "username", out)
+}
+
+fn encode_replace_ack(r: &ReplaceAck, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = r.symbol.as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireOutputType::ReplaceAck as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&r.user_id.to_be_bytes());
+    out.extend_from_slice(&r.user_order_id.to_be_bytes());
+    out.extend_from_slice(&r.price.to_be_bytes());
+    out.extend_from_slice(&r.remaining_qty.to_be_bytes());
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn encode_reject(r: &Reject, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    // Unlike other outputs the symbol may be empty (not known).
+    let symbol_bytes = r.symbol.as_bytes();
+    if symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    out.push(WireOutputType::Reject as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.extend_from_slice(&r.user_id.to_be_bytes());
+    out.extend_from_slice(&r.user_order_id.to_be_bytes());
+    out.push(r.reason as u8);
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+
+    Ok(())
+}
+
+fn decode_ack(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 13 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let symbol_len = buf[12] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 13 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[13..13 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::Ack(Ack {
+        user_id,
+        user_order_id,
+        symbol,
+    }))
+}
+
+fn decode_cancel_ack(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 13 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let symbol_len = buf[12] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 13 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[13..13 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::CancelAck(CancelAck {
+        user_id,
+        user_order_id,
+        symbol,
+    }))
+}
+
+fn decode_trade(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 5 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_len = buf[4] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 5 + symbol_len + 4 * 6 + 1 + 8 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[5..5 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    let mut offset = 5 + symbol_len;
+
+    let user_id_buy = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let user_order_id_buy = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let user_id_sell = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let user_order_id_sell = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let price = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let quantity = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let aggressor_side = decode_side(buf[offset])?;
+    offset += 1;
+    let timestamp_ns = read_u64_be(&buf[offset..offset + 8]);
+
+    Ok(OutputMessage::Trade(Trade {
+        symbol,
+        user_id_buy,
+        user_order_id_buy,
+        user_id_sell,
+        user_order_id_sell,
+        price,
+        quantity,
+        aggressor_side,
+        timestamp_ns,
+    }))
+}
+
+fn decode_public_trade(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 5 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_len = buf[4] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 5 + symbol_len + 4 + 4 + 1 + 8 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[5..5 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    let mut offset = 5 + symbol_len;
+
+    let price = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let quantity = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let aggressor_side = decode_side(buf[offset])?;
+    offset += 1;
+    let timestamp_ns = read_u64_be(&buf[offset..offset + 8]);
+
+    Ok(OutputMessage::PublicTrade(PublicTrade {
+        symbol,
+        price,
+        quantity,
+        aggressor_side,
+        timestamp_ns,
+    }))
+}
+
+fn decode_top_of_book(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 5 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_len = buf[4] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 5 + symbol_len + 1 + 1 + 4 + 4 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[5..5 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    let mut offset = 5 + symbol_len;
+
+    let side_byte = buf[offset];
+    offset += 1;
+    let side = match side_byte {
+        0 => Side::Buy,
         1 => Side::Sell,
         _ => return Err(ProtocolError::InvalidField("side")),
     };
 
-    let eliminated = buf[offset] != 0;
-    offset += 1;
+    let eliminated = buf[offset] != 0;
+    offset += 1;
+
+    let price = read_u32_be(&buf[offset..offset + 4]);
+    offset += 4;
+    let total_quantity = read_u32_be(&buf[offset..offset + 4]);
+
+    Ok(OutputMessage::TopOfBook(TopOfBook {
+        symbol,
+        side,
+        price,
+        total_quantity,
+        eliminated,
+    }))
+}
+
+fn decode_expired(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 17 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let remaining_qty = read_u32_be(&buf[12..16]);
+    let symbol_len = buf[16] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 17 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[17..17 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::Expired(Expired {
+        user_id,
+        user_order_id,
+        symbol,
+        remaining_qty,
+    }))
+}
+
+fn decode_triggered(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 21 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let stop_price = read_u32_be(&buf[12..16]);
+    let last_trade_price = read_u32_be(&buf[16..20]);
+    let symbol_len = buf[20] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 21 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[21..21 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::Triggered(Triggered {
+        user_id,
+        user_order_id,
+        symbol,
+        stop_price,
+        last_trade_price,
+    }))
+}
+
+fn decode_self_trade_cancel(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 21 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let cancelled_qty = read_u32_be(&buf[12..16]);
+    let remaining_qty = read_u32_be(&buf[16..20]);
+    let symbol_len = buf[20] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 21 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[21..21 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::SelfTradeCancel(SelfTradeCancel {
+        user_id,
+        user_order_id,
+        symbol,
+        cancelled_qty,
+        remaining_qty,
+    }))
+}
+
+fn decode_indicative_uncross(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 18 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let price = read_u32_be(&buf[4..8]);
+    let volume = read_u32_be(&buf[8..12]);
+    let imbalance_qty = read_u32_be(&buf[12..16]);
+    let imbalance_side = decode_side(buf[16])?;
+    let symbol_len = buf[17] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 18 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[18..18 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::IndicativeUncross(IndicativeUncross {
+        symbol,
+        price,
+        volume,
+        imbalance_qty,
+        imbalance_side,
+    }))
+}
+
+fn decode_trading_status(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 6 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let state = TradingState::from_u8(buf[4]).ok_or(ProtocolError::InvalidField("state"))?;
+    let symbol_len = buf[5] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 6 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[6..6 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::TradingStatus(TradingStatus { symbol, state }))
+}
+
+fn decode_instrument_definition(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 33 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let tick_size = read_u32_be(&buf[4..8]);
+    let lot_size = read_u32_be(&buf[8..12]);
+    let min_qty = read_u32_be(&buf[12..16]);
+    let max_qty = read_u32_be(&buf[16..20]);
+    let min_price = read_u32_be(&buf[20..24]);
+    let max_price = read_u32_be(&buf[24..28]);
+    let market_protection_ticks = read_u32_be(&buf[28..32]);
+    let symbol_len = buf[32] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 33 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[33..33 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::InstrumentDefinition(Instrument {
+        symbol,
+        tick_size,
+        lot_size,
+        min_qty,
+        max_qty,
+        min_price,
+        max_price,
+        market_protection_ticks,
+    }))
+}
+
+fn decode_volatility_interruption(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 21 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let reference_price = read_u32_be(&buf[4..8]);
+    let trigger_price = read_u32_be(&buf[8..12]);
+    let resume_ns = read_u64_be(&buf[12..20]);
+    let symbol_len = buf[20] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 21 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[21..21 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::VolatilityInterruption(VolatilityInterruption {
+        symbol,
+        reference_price,
+        trigger_price,
+        resume_ns,
+    }))
+}
+
+fn decode_execution_report(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 47 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let exec_id = read_u64_be(&buf[12..20]);
+    let exec_type = ExecType::from_u8(buf[20]).ok_or(ProtocolError::InvalidField("exec_type"))?;
+    let order_status =
+        OrdStatus::from_u8(buf[21]).ok_or(ProtocolError::InvalidField("order_status"))?;
+    let last_qty = read_u32_be(&buf[22..26]);
+    let last_price = read_u32_be(&buf[26..30]);
+    let cum_qty = read_u32_be(&buf[30..34]);
+    let leaves_qty = read_u32_be(&buf[34..38]);
+    let timestamp_ns = read_u64_be(&buf[38..46]);
+    let symbol_len = buf[46] as usize;
+
+    if symbol_len > MAX_SYMBOL_LEN || buf.len() < 47 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[47..47 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::ExecutionReport(ExecutionReport {
+        user_id,
+        user_order_id,
+        symbol,
+        exec_id,
+        exec_type,
+        order_status,
+        last_qty,
+        last_price,
+        cum_qty,
+        leaves_qty,
+        timestamp_ns,
+    }))
+}
+
+fn decode_mass_cancel_ack(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 13 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let cancelled_count = read_u32_be(&buf[8..12]);
+    let symbol_len = buf[12] as usize;
+
+    if symbol_len > MAX_SYMBOL_LEN || buf.len() < 13 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[13..13 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::MassCancelAck(MassCancelAck {
+        user_id,
+        symbol,
+        cancelled_count,
+    }))
+}
+
+fn decode_user_trading_status(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 9 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let enabled = match buf[8] {
+        0 => false,
+        1 => true,
+        _ => return Err(ProtocolError::InvalidField("enabled")),
+    };
+
+    Ok(OutputMessage::UserTradingStatus(UserTradingStatus { user_id, enabled }))
+}
 
-    let price = read_u32_be(&buf[offset..offset + 4]);
-    offset += 4;
-    let total_quantity = read_u32_be(&buf[offset..offset + 4]);
+fn decode_session_status(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 6 {
+        return Err(ProtocolError::Truncated);
+    }
 
-    Ok(OutputMessage::TopOfBook(TopOfBook {
+    let logged_on = match buf[4] {
+        0 => false,
+        1 => true,
+        _ => return Err(ProtocolError::InvalidField("logged_on")),
+    };
+    let username = read_short_str(&buf[5..], "username")?;
+
+    Ok(OutputMessage::SessionStatus(SessionStatus { username, logged_on }))
+}
+
+fn decode_replace_ack(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 21 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let price = read_u32_be(&buf[12..16]);
+    let remaining_qty = read_u32_be(&buf[16..20]);
+    let symbol_len = buf[20] as usize;
+
+    if !validate_symbol_len(symbol_len) || buf.len() < 21 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[21..21 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::ReplaceAck(ReplaceAck {
+        user_id,
+        user_order_id,
         symbol,
-        side,
         price,
-        total_quantity,
-        eliminated,
+        remaining_qty,
+    }))
+}
+
+fn decode_reject(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 14 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let user_id = read_u32_be(&buf[4..8]);
+    let user_order_id = read_u32_be(&buf[8..12]);
+    let reason =
+        RejectReason::from_u8(buf[12]).ok_or(ProtocolError::InvalidField("reason"))?;
+    let symbol_len = buf[13] as usize;
+
+    if symbol_len > MAX_SYMBOL_LEN || buf.len() < 14 + symbol_len {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let symbol_bytes = &buf[14..14 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    Ok(OutputMessage::Reject(Reject {
+        user_id,
+        user_order_id,
+        symbol,
+        reason,
     }))
 }
 
@@ -582,6 +2031,241 @@ fn decode_top_of_book(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
 // Helpers
 // -----------------------------------------------------------------------------
 
+fn decode_depth_update(buf: &[u8]) -> Result<OutputMessage, ProtocolError> {
+    if buf.len() < 5 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_len = buf[4] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    if buf.len() < 5 + symbol_len + 3 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol_bytes = &buf[5..5 + symbol_len];
+    let symbol = std::str::from_utf8(symbol_bytes)
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+
+    let mut offset = 5 + symbol_len;
+    let snapshot = match buf[offset] {
+        0 => false,
+        1 => true,
+        _ => return Err(ProtocolError::InvalidField("snapshot")),
+    };
+    offset += 1;
+    let event_count = u16::from_be_bytes([buf[offset], buf[offset + 1]]) as usize;
+    offset += 2;
+
+    if buf.len() < offset + event_count * 10 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let mut events = Vec::with_capacity(event_count);
+    for _ in 0..event_count {
+        let action =
+            DepthAction::from_u8(buf[offset]).ok_or(ProtocolError::InvalidField("action"))?;
+        let side = decode_side(buf[offset + 1])?;
+        let price = read_u32_be(&buf[offset + 2..offset + 6]);
+        let quantity = read_u32_be(&buf[offset + 6..offset + 10]);
+        events.push(DepthEvent {
+            action,
+            side,
+            price,
+            quantity,
+        });
+        offset += 10;
+    }
+
+    Ok(OutputMessage::DepthUpdate(DepthUpdate {
+        symbol,
+        snapshot,
+        events,
+    }))
+}
+
+// ============================================================================
+// MARKET-BY-ORDER FEED
+// ============================================================================
+
+/// Encode a single L3 book event into a binary frame.
+///
+/// The encoded bytes are appended to `out`.
+pub fn encode_book_event(event: &OrderBookEvent, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let symbol_bytes = event.symbol().as_bytes();
+    if symbol_bytes.is_empty() || symbol_bytes.len() > MAX_SYMBOL_LEN {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let (wire_type, order_id) = match event {
+        OrderBookEvent::Add { order_id, .. } => (WireBookEventType::Add, *order_id),
+        OrderBookEvent::Execute { order_id, .. } => (WireBookEventType::Execute, *order_id),
+        OrderBookEvent::Cancel { order_id, .. } => (WireBookEventType::Cancel, *order_id),
+        OrderBookEvent::Replace { old_order_id, .. } => (WireBookEventType::Replace, *old_order_id),
+    };
+
+    out.push(wire_type as u8);
+    out.push(PROTOCOL_VERSION);
+    out.extend_from_slice(&[0, 0]);
+
+    out.push(u8::try_from(symbol_bytes.len()).unwrap());
+    out.extend_from_slice(symbol_bytes);
+    out.extend_from_slice(&order_id.to_be_bytes());
+
+    match event {
+        OrderBookEvent::Add {
+            side,
+            price,
+            quantity,
+            ..
+        } => {
+            out.push(encode_side(*side));
+            out.extend_from_slice(&price.to_be_bytes());
+            out.extend_from_slice(&quantity.to_be_bytes());
+        }
+        OrderBookEvent::Execute {
+            price,
+            executed_qty,
+            remaining_qty,
+            ..
+        } => {
+            out.extend_from_slice(&price.to_be_bytes());
+            out.extend_from_slice(&executed_qty.to_be_bytes());
+            out.extend_from_slice(&remaining_qty.to_be_bytes());
+        }
+        OrderBookEvent::Cancel {
+            cancelled_qty,
+            remaining_qty,
+            ..
+        } => {
+            out.extend_from_slice(&cancelled_qty.to_be_bytes());
+            out.extend_from_slice(&remaining_qty.to_be_bytes());
+        }
+        OrderBookEvent::Replace {
+            new_order_id,
+            price,
+            quantity,
+            ..
+        } => {
+            out.extend_from_slice(&new_order_id.to_be_bytes());
+            out.extend_from_slice(&price.to_be_bytes());
+            out.extend_from_slice(&quantity.to_be_bytes());
+        }
+    }
+
+    Ok(())
+}
+
+/// Decode a single L3 book event from a binary frame.
+pub fn decode_book_event(buf: &[u8]) -> Result<OrderBookEvent, ProtocolError> {
+    if buf.len() < 5 {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let msg_type = buf[0];
+    let version = buf[1];
+    if version != PROTOCOL_VERSION {
+        return Err(ProtocolError::VersionMismatch(version));
+    }
+    let wire_type =
+        WireBookEventType::from_u8(msg_type).ok_or(ProtocolError::UnknownMessageType(msg_type))?;
+
+    let symbol_len = buf[4] as usize;
+    if !validate_symbol_len(symbol_len) {
+        return Err(ProtocolError::InvalidSymbol);
+    }
+
+    let body_len = match wire_type {
+        WireBookEventType::Add => 9,
+        WireBookEventType::Execute => 12,
+        WireBookEventType::Cancel => 8,
+        WireBookEventType::Replace => 16,
+    };
+    let mut offset = 5 + symbol_len;
+    if buf.len() < offset + 8 + body_len {
+        return Err(ProtocolError::Truncated);
+    }
+
+    let symbol = std::str::from_utf8(&buf[5..offset])
+        .map_err(|_| ProtocolError::InvalidSymbol)?
+        .to_string();
+    let order_id = read_u64_be(&buf[offset..offset + 8]);
+    offset += 8;
+
+    let event = match wire_type {
+        WireBookEventType::Add => OrderBookEvent::Add {
+            symbol,
+            order_id,
+            side: decode_side(buf[offset])?,
+            price: read_u32_be(&buf[offset + 1..offset + 5]),
+            quantity: read_u32_be(&buf[offset + 5..offset + 9]),
+        },
+        WireBookEventType::Execute => OrderBookEvent::Execute {
+            symbol,
+            order_id,
+            price: read_u32_be(&buf[offset..offset + 4]),
+            executed_qty: read_u32_be(&buf[offset + 4..offset + 8]),
+            remaining_qty: read_u32_be(&buf[offset + 8..offset + 12]),
+        },
+        WireBookEventType::Cancel => OrderBookEvent::Cancel {
+            symbol,
+            order_id,
+            cancelled_qty: read_u32_be(&buf[offset..offset + 4]),
+            remaining_qty: read_u32_be(&buf[offset + 4..offset + 8]),
+        },
+        WireBookEventType::Replace => OrderBookEvent::Replace {
+            symbol,
+            old_order_id: order_id,
+            new_order_id: read_u64_be(&buf[offset..offset + 8]),
+            price: read_u32_be(&buf[offset + 8..offset + 12]),
+            quantity: read_u32_be(&buf[offset + 12..offset + 16]),
+        },
+    };
+
+    Ok(event)
+}
+
+fn encode_side(side: Side) -> u8 {
+    match side {
+        Side::Buy => 0,
+        Side::Sell => 1,
+    }
+}
+
+fn decode_side(byte: u8) -> Result<Side, ProtocolError> {
+    match byte {
+        0 => Ok(Side::Buy),
+        1 => Ok(Side::Sell),
+        _ => Err(ProtocolError::InvalidField("side")),
+    }
+}
+
+fn read_u64_be(bytes: &[u8]) -> u64 {
+    let mut be = [0u8; 8];
+    be.copy_from_slice(&bytes[..8]);
+    u64::from_be_bytes(be)
+}
+
+/// Write a token or credential as `len (u8)` + UTF-8 bytes.
+fn write_short_str(value: &str, field: &'static str, out: &mut Vec<u8>) -> Result<(), ProtocolError> {
+    let len = u8::try_from(value.len()).map_err(|_| ProtocolError::InvalidField(field))?;
+    out.push(len);
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+/// Read a string written by [`write_short_str`] from the start of `bytes`.
+fn read_short_str(bytes: &[u8], field: &'static str) -> Result<String, ProtocolError> {
+    let len = *bytes.first().ok_or(ProtocolError::Truncated)? as usize;
+    let value = bytes.get(1..1 + len).ok_or(ProtocolError::Truncated)?;
+    std::str::from_utf8(value)
+        .map(str::to_string)
+        .map_err(|_| ProtocolError::InvalidField(field))
+}
+
 fn read_u32_be(bytes: &[u8]) -> u32 {
     let arr: [u8; 4] = bytes[0..4].try_into().expect("slice with incorrect length");
     u32::from_be_bytes(arr)
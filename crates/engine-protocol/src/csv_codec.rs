@@ -8,17 +8,72 @@
 //! Input format (lines → `InputMessage`):
 //!
 //! - New order:
-//!   `N, user(int), symbol(string), price(int), qty(int), side(char B or S), userOrderId(int)`
+//!   `N, user(int), symbol(string), price(int), qty(int), side(char B or S), userOrderId(int)[, tif[, stopPrice[, displayQty[, flags[, stp]]]]]`
+//!
+//!   `tif` is optional: `DAY` (default), `GTC`, `IOC` or `FOK`.
+//!   `stopPrice` (default `0` = none) makes it a stop order: stop-market
+//!   with price `0`, stop-limit otherwise.
+//!   `displayQty` (default `0` = fully displayed) makes it an iceberg that
+//!   shows at most that much at a time.
+//!   `flags` is `-` (default) or any of `POST_ONLY`, `POST_ONLY_REPRICE`,
+//!   `HIDDEN` joined by `|`, e.g. `POST_ONLY|HIDDEN`.
+//!   `stp` is the self-trade prevention mode: `NONE` (default),
+//!   `CANCEL_NEWEST`, `CANCEL_OLDEST`, `CANCEL_BOTH` or `DECREMENT`.
 //!
 //! - Cancel:
 //!   `C, user(int), userOrderId(int)`
 //!
-//! - Flush:
-//!   `F`
+//! - Replace (cancel-replace; `-` leaves a field unchanged):
+//!   `R, user(int), userOrderId(int), newPrice(int or -), newQty(int or -)`
+//!
+//! - Flush (admin):
+//!   `F[, adminToken]`
 //!
 //! - Query top-of-book (NEW):
 //!   `Q, symbol(string)`
 //!
+//! - Query depth (`levels` per side, optional, default / `0` = all):
+//!   `D, symbol(string)[, levels(int)]`
+//!
+//! - Call auction (admin; `START` or `UNCROSS`):
+//!   `U, symbol(string), action[, adminToken]`
+//!
+//! - Trading state (admin; `PRE_OPEN`, `AUCTION`, `CONTINUOUS`, `HALTED`
+//!   or `CLOSED`):
+//!   `S, symbol(string), state[, adminToken]`
+//!
+//! The server refuses admin messages without its admin token; it is
+//! optional in the syntax for the flush, auction and trading state lines
+//! of scenario files, which go straight to the engine.
+//!
+//! - Query instrument definition:
+//!   `I, symbol(string)`
+//!
+//! - Risk limits (admin; user `0` = default for every user without
+//!   limits of their own; `0` = no limit):
+//!   `L, user(int), maxOrderQty(int), maxNotional(int), maxOpenOrders(int), maxPosition(int), maxGrossExposure(int), adminToken`
+//!
+//! - Logon (session options; `cancelOnDisconnect` `Y` or `N`,
+//!   `heartbeatSecs` optional, default / `0` = no heartbeat timeout;
+//!   credentials after it when the server checks them):
+//!   `A, cancelOnDisconnect[, heartbeatSecs(int)[, username, secret]]`
+//!
+//! - Logout (ends the session):
+//!   `O`
+//!
+//! - Heartbeat:
+//!   `H`
+//!
+//! - Mass cancel (every filter optional, `-` = any; prices inclusive):
+//!   `M, user(int)[, symbol|-[, side(B/S)|-[, minPrice(int)|-[, maxPrice(int)|-]]]]`
+//!
+//! - Kill switch (admin; user 0 = every user; `Y` also cancels their
+//!   live orders):
+//!   `K, user(int), Y|N, adminToken`
+//!
+//! - Enable trading (admin; lifts a kill switch):
+//!   `E, user(int), adminToken`
+//!
 //! Output format (`OutputMessage` → line) - NEW FORMAT:
 //!
 //! - Ack:
@@ -28,17 +83,79 @@
 //!   `C, userId, userOrderId, symbol`
 //!
 //! - Trade:
-//!   `T, symbol, userIdBuy, userOrderIdBuy, userIdSell, userOrderIdSell, price, quantity`
+//!   `T, symbol, userIdBuy, userOrderIdBuy, userIdSell, userOrderIdSell, price, quantity, aggressorSide(B/S), timestampNs`
+//!
+//! - PublicTrade (anonymized print):
+//!   `P, symbol, price, quantity, aggressorSide(B/S), timestampNs`
 //!
 //! - TopOfBook (non-eliminated):
 //!   `B, symbol, side(B/S), price, totalQuantity`
 //!
 //! - TopOfBook (eliminated):
 //!   `B, symbol, side(B/S), -, -`
+//!
+//! - ReplaceAck:
+//!   `R, userId, userOrderId, symbol, price, remainingQty`
+//!
+//! - Reject (symbol may be empty; reason e.g. `UNKNOWN_ORDER`):
+//!   `X, userId, userOrderId, symbol, reason`
+//!
+//! - Expired (unfilled IOC or market remainder / killed FOK):
+//!   `E, userId, userOrderId, symbol, remainingQty`
+//!
+//! - Triggered (stop order reached its stop price):
+//!   `S, userId, userOrderId, symbol, stopPrice, lastTradePrice`
+//!
+//! - SelfTradeCancel (quantity cancelled instead of a self-trade):
+//!   `K, userId, userOrderId, symbol, cancelledQty, remainingQty`
+//!
+//! - IndicativeUncross (price `0` = the auction doesn't cross):
+//!   `I, symbol, price, volume, imbalanceQty, imbalanceSide(B/S)`
+//!
+//! - TradingStatus (a symbol's trading state changed):
+//!   `H, symbol, state`
+//!
+//! - InstrumentDefinition (limits `0` = none):
+//!   `L, symbol, tickSize, lotSize, minQty, maxQty, minPrice, maxPrice, marketProtectionTicks`
+//!
+//! - VolatilityInterruption (band breached, trading interrupted until
+//!   `resumeNs`):
+//!   `V, symbol, referencePrice, triggerPrice, resumeNs`
+//!
+//! - ExecutionReport (FIX-style; execType `NEW`, `TRADE`, `CANCELED`,
+//!   `REPLACED`, `REJECTED`, `EXPIRED`, `TRIGGERED` or `RESTATED`;
+//!   ordStatus `NEW`, `PARTIALLY_FILLED`, `FILLED`, `CANCELED`,
+//!   `REJECTED` or `EXPIRED`):
+//!   `O, userId, userOrderId, symbol, execId, execType, ordStatus, lastQty, lastPrice, cumQty, leavesQty, timestampNs`
+//!
+//! - MassCancelAck (after one `C` line per cancelled order; symbol empty
+//!   = every symbol):
+//!   `M, userId, symbol, cancelledCount`
+//!
+//! - UserTradingStatus (userId 0 = every user):
+//!   `U, userId, ENABLED|DISABLED`
+//!
+//! - SessionStatus (answer to a logon / logout):
+//!   `G, username, LOGGED_ON|LOGGED_OFF`
+//!
+//! - DepthUpdate (`S` = snapshot, `U` = incremental), followed by one
+//!   `action(A/C/D), side(B/S), price, quantity` group per level:
+//!   `D, symbol, S|U[, action, side, price, qty]...`
+//!
+//! Market-by-order (L3) feed (`OrderBookEvent` → line, ITCH-style letters):
+//!
+//! - Add:      `A, symbol, orderId, side(B/S), price, quantity`
+//! - Execute:  `E, symbol, orderId, price, executedQty, remainingQty`
+//! - Cancel:   `X, symbol, orderId, cancelledQty, remainingQty`
+//! - Replace:  `U, symbol, oldOrderId, newOrderId, price, quantity`
 
 use std::num::ParseIntError;
 
-use engine_core::{Cancel, InputMessage, NewOrder, OutputMessage, Side, TopOfBookQuery};
+use engine_core::{
+    AuctionAction, AuctionCommand, Cancel, DepthQuery, DepthUpdate, EnableTrading, FlushCommand, InputMessage, InstrumentQuery, KillSwitch, Logon, MassCancel, NewOrder, OrderBookEvent, OrderFlags, OutputMessage,
+    Replace, RiskLimits, SelfTradePrevention, SessionStatus, SetRiskLimits, SetTradingState, Side,
+    TimeInForce, TopOfBookQuery, TradingState, UserTradingStatus,
+};
 
 /// Parse a single CSV line into an `InputMessage`.
 ///
@@ -59,21 +176,36 @@ pub fn parse_input_line(line: &str) -> Option<InputMessage> {
     match msg_type {
         'N' => parse_new_order(&tokens),
         'C' => parse_cancel(&tokens),
+        'R' => parse_replace(&tokens),
         'F' => {
-            if tokens.len() == 1 {
-                Some(InputMessage::Flush)
+            // F[, adminToken]
+            if tokens.len() <= 2 {
+                Some(InputMessage::Flush(FlushCommand {
+                    admin_token: admin_token(&tokens, 1),
+                }))
             } else {
                 None
             }
         }
         'Q' => parse_query_tob(&tokens),
+        'D' => parse_query_depth(&tokens),
+        'U' => parse_auction(&tokens),
+        'S' => parse_trading_state(&tokens),
+        'I' => parse_query_instrument(&tokens),
+        'L' => parse_risk_limits(&tokens),
+        'A' => parse_logon(&tokens),
+        'H' if tokens.len() == 1 => Some(InputMessage::Heartbeat),
+        'O' if tokens.len() == 1 => Some(InputMessage::Logout),
+        'M' => parse_mass_cancel(&tokens),
+        'K' => parse_kill_switch(&tokens),
+        'E' => parse_enable_trading(&tokens),
         _ => None,
     }
 }
 
 fn parse_new_order(tokens: &[String]) -> Option<InputMessage> {
-    // N, user, symbol, price, qty, side, userOrderId
-    if tokens.len() != 7 {
+    // N, user, symbol, price, qty, side, userOrderId[, tif[, stopPrice[, displayQty[, flags[, stp]]]]]
+    if !(7..=12).contains(&tokens.len()) {
         return None;
     }
 
@@ -82,10 +214,6 @@ fn parse_new_order(tokens: &[String]) -> Option<InputMessage> {
     let price = parse_u32(&tokens[3]).ok()?;
     let quantity = parse_u32(&tokens[4]).ok()?;
 
-    if quantity == 0 {
-        return None;
-    }
-
     let side_char = tokens[5].chars().next()?;
     let side = match side_char {
         'B' => Side::Buy,
@@ -95,6 +223,31 @@ fn parse_new_order(tokens: &[String]) -> Option<InputMessage> {
 
     let user_order_id = parse_u32(&tokens[6]).ok()?;
 
+    let time_in_force = match tokens.get(7) {
+        Some(tif) => TimeInForce::from_str_code(tif)?,
+        None => TimeInForce::Day,
+    };
+
+    let stop_price = match tokens.get(8) {
+        Some(stop) => parse_u32(stop).ok()?,
+        None => 0,
+    };
+
+    let display_qty = match tokens.get(9) {
+        Some(display) => parse_u32(display).ok()?,
+        None => 0,
+    };
+
+    let flags = match tokens.get(10) {
+        Some(flags) => OrderFlags::from_str_code(flags)?,
+        None => OrderFlags::NONE,
+    };
+
+    let self_trade_prevention = match tokens.get(11) {
+        Some(stp) => SelfTradePrevention::from_str_code(stp)?,
+        None => SelfTradePrevention::None,
+    };
+
     Some(InputMessage::NewOrder(NewOrder {
         user_id,
         symbol,
@@ -102,6 +255,11 @@ fn parse_new_order(tokens: &[String]) -> Option<InputMessage> {
         quantity,
         side,
         user_order_id,
+        time_in_force,
+        stop_price,
+        display_qty,
+        flags,
+        self_trade_prevention,
     }))
 }
 
@@ -120,6 +278,25 @@ fn parse_cancel(tokens: &[String]) -> Option<InputMessage> {
     }))
 }
 
+fn parse_replace(tokens: &[String]) -> Option<InputMessage> {
+    // R, user, userOrderId, newPrice|-, newQty|-
+    if tokens.len() != 5 {
+        return None;
+    }
+
+    let user_id = parse_u32(&tokens[1]).ok()?;
+    let user_order_id = parse_u32(&tokens[2]).ok()?;
+    let new_price = parse_optional_u32(&tokens[3])?;
+    let new_quantity = parse_optional_u32(&tokens[4])?;
+
+    Some(InputMessage::Replace(Replace {
+        user_id,
+        user_order_id,
+        new_price,
+        new_quantity,
+    }))
+}
+
 fn parse_query_tob(tokens: &[String]) -> Option<InputMessage> {
     // Q, symbol
     if tokens.len() != 2 {
@@ -130,6 +307,181 @@ fn parse_query_tob(tokens: &[String]) -> Option<InputMessage> {
     Some(InputMessage::QueryTopOfBook(TopOfBookQuery { symbol }))
 }
 
+fn parse_query_depth(tokens: &[String]) -> Option<InputMessage> {
+    // D, symbol[, levels]
+    if tokens.len() != 2 && tokens.len() != 3 {
+        return None;
+    }
+
+    let symbol = tokens[1].clone();
+    let levels = match tokens.get(2) {
+        Some(levels) => parse_u32(levels).ok()?,
+        None => 0,
+    };
+    Some(InputMessage::QueryDepth(DepthQuery { symbol, levels }))
+}
+
+fn parse_auction(tokens: &[String]) -> Option<InputMessage> {
+    // U, symbol, START|UNCROSS[, adminToken]
+    if tokens.len() != 3 && tokens.len() != 4 {
+        return None;
+    }
+
+    let symbol = tokens[1].clone();
+    let action = AuctionAction::from_str_code(&tokens[2])?;
+    Some(InputMessage::Auction(AuctionCommand {
+        symbol,
+        action,
+        admin_token: admin_token(tokens, 3),
+    }))
+}
+
+fn parse_trading_state(tokens: &[String]) -> Option<InputMessage> {
+    // S, symbol, state[, adminToken]
+    if tokens.len() != 3 && tokens.len() != 4 {
+        return None;
+    }
+
+    let symbol = tokens[1].clone();
+    let state = TradingState::from_str_code(&tokens[2])?;
+    Some(InputMessage::SetTradingState(SetTradingState {
+        symbol,
+        state,
+        admin_token: admin_token(tokens, 3),
+    }))
+}
+
+fn parse_query_instrument(tokens: &[String]) -> Option<InputMessage> {
+    // I, symbol
+    if tokens.len() != 2 {
+        return None;
+    }
+
+    let symbol = tokens[1].clone();
+    Some(InputMessage::QueryInstrument(InstrumentQuery { symbol }))
+}
+
+fn parse_risk_limits(tokens: &[String]) -> Option<InputMessage> {
+    // L, user, maxOrderQty, maxNotional, maxOpenOrders, maxPosition, maxGrossExposure, adminToken
+    if tokens.len() != 8 {
+        return None;
+    }
+
+    let user_id = parse_u32(&tokens[1]).ok()?;
+    let limits = RiskLimits {
+        max_order_qty: parse_u32(&tokens[2]).ok()?,
+        max_notional: parse_u64(&tokens[3]).ok()?,
+        max_open_orders: parse_u32(&tokens[4]).ok()?,
+        max_position: parse_u64(&tokens[5]).ok()?,
+        max_gross_exposure: parse_u64(&tokens[6]).ok()?,
+    };
+    let admin_token = tokens[7].clone();
+    Some(InputMessage::SetRiskLimits(SetRiskLimits {
+        user_id,
+        limits,
+        admin_token,
+    }))
+}
+
+fn parse_mass_cancel(tokens: &[String]) -> Option<InputMessage> {
+    // M, user[, symbol|-[, side|-[, minPrice|-[, maxPrice|-]]]]
+    if !(2..=6).contains(&tokens.len()) {
+        return None;
+    }
+
+    let user_id = parse_u32(&tokens[1]).ok()?;
+    let symbol = tokens
+        .get(2)
+        .filter(|symbol| symbol.as_str() != "-")
+        .cloned();
+    let side = match tokens.get(3).map(String::as_str) {
+        None | Some("-") => None,
+        Some("B") => Some(Side::Buy),
+        Some("S") => Some(Side::Sell),
+        Some(_) => return None,
+    };
+    let min_price = match tokens.get(4) {
+        Some(price) => parse_optional_u32(price)?,
+        None => None,
+    };
+    let max_price = match tokens.get(5) {
+        Some(price) => parse_optional_u32(price)?,
+        None => None,
+    };
+
+    Some(InputMessage::MassCancel(MassCancel {
+        user_id,
+        symbol,
+        side,
+        min_price,
+        max_price,
+    }))
+}
+
+fn parse_logon(tokens: &[String]) -> Option<InputMessage> {
+    // A, Y|N[, heartbeatSecs[, username, secret]]
+    if !matches!(tokens.len(), 2 | 3 | 5) {
+        return None;
+    }
+
+    let cancel_on_disconnect = match tokens[1].as_str() {
+        "Y" => true,
+        "N" => false,
+        _ => return None,
+    };
+    let heartbeat_secs = match tokens.get(2) {
+        Some(secs) => parse_u32(secs).ok()?,
+        None => 0,
+    };
+    let (username, secret) = match tokens.get(3..5) {
+        Some([username, secret]) => (username.clone(), secret.clone()),
+        _ => (String::new(), String::new()),
+    };
+    Some(InputMessage::Logon(Logon {
+        cancel_on_disconnect,
+        heartbeat_secs,
+        username,
+        secret,
+    }))
+}
+
+fn parse_kill_switch(tokens: &[String]) -> Option<InputMessage> {
+    // K, user, Y|N, adminToken
+    if tokens.len() != 4 {
+        return None;
+    }
+
+    let user_id = parse_u32(&tokens[1]).ok()?;
+    let cancel_orders = match tokens[2].as_str() {
+        "Y" => true,
+        "N" => false,
+        _ => return None,
+    };
+    Some(InputMessage::KillSwitch(KillSwitch {
+        user_id,
+        cancel_orders,
+        admin_token: tokens[3].clone(),
+    }))
+}
+
+fn parse_enable_trading(tokens: &[String]) -> Option<InputMessage> {
+    // E, user, adminToken
+    if tokens.len() != 3 {
+        return None;
+    }
+
+    let user_id = parse_u32(&tokens[1]).ok()?;
+    Some(InputMessage::EnableTrading(EnableTrading {
+        user_id,
+        admin_token: tokens[2].clone(),
+    }))
+}
+
+/// The optional admin token at `tokens[index]` (empty when absent).
+fn admin_token(tokens: &[String], index: usize) -> String {
+    tokens.get(index).cloned().unwrap_or_default()
+}
+
 /// Format an `OutputMessage` as a CSV line (NEW, symbol-aware format).
 pub fn format_output_csv(msg: &OutputMessage) -> String {
     match msg {
@@ -138,14 +490,24 @@ pub fn format_output_csv(msg: &OutputMessage) -> String {
             format!("C, {}, {}, {}", c.user_id, c.user_order_id, c.symbol)
         }
         OutputMessage::Trade(t) => format!(
-            "T, {}, {}, {}, {}, {}, {}, {}",
+            "T, {}, {}, {}, {}, {}, {}, {}, {}, {}",
             t.symbol,
             t.user_id_buy,
             t.user_order_id_buy,
             t.user_id_sell,
             t.user_order_id_sell,
             t.price,
-            t.quantity
+            t.quantity,
+            t.aggressor_side.as_char(),
+            t.timestamp_ns
+        ),
+        OutputMessage::PublicTrade(t) => format!(
+            "P, {}, {}, {}, {}, {}",
+            t.symbol,
+            t.price,
+            t.quantity,
+            t.aggressor_side.as_char(),
+            t.timestamp_ns
         ),
         OutputMessage::TopOfBook(t) => {
             let side_char = match t.side {
@@ -161,6 +523,70 @@ pub fn format_output_csv(msg: &OutputMessage) -> String {
                 )
             }
         }
+        OutputMessage::ReplaceAck(r) => format!(
+            "R, {}, {}, {}, {}, {}",
+            r.user_id, r.user_order_id, r.symbol, r.price, r.remaining_qty
+        ),
+        OutputMessage::Reject(r) => format!(
+            "X, {}, {}, {}, {}",
+            r.user_id, r.user_order_id, r.symbol, r.reason
+        ),
+        OutputMessage::Expired(e) => format!(
+            "E, {}, {}, {}, {}",
+            e.user_id, e.user_order_id, e.symbol, e.remaining_qty
+        ),
+        OutputMessage::Triggered(t) => format!(
+            "S, {}, {}, {}, {}, {}",
+            t.user_id, t.user_order_id, t.symbol, t.stop_price, t.last_trade_price
+        ),
+        OutputMessage::SelfTradeCancel(c) => format!(
+            "K, {}, {}, {}, {}, {}",
+            c.user_id, c.user_order_id, c.symbol, c.cancelled_qty, c.remaining_qty
+        ),
+        OutputMessage::IndicativeUncross(i) => format!(
+            "I, {}, {}, {}, {}, {}",
+            i.symbol,
+            i.price,
+            i.volume,
+            i.imbalance_qty,
+            i.imbalance_side.as_char()
+        ),
+        OutputMessage::TradingStatus(s) => format!("H, {}, {}", s.symbol, s.state.as_str()),
+        OutputMessage::InstrumentDefinition(i) => format!(
+            "L, {}, {}, {}, {}, {}, {}, {}, {}",
+            i.symbol,
+            i.tick_size,
+            i.lot_size,
+            i.min_qty,
+            i.max_qty,
+            i.min_price,
+            i.max_price,
+            i.market_protection_ticks
+        ),
+        OutputMessage::VolatilityInterruption(v) => format!(
+            "V, {}, {}, {}, {}",
+            v.symbol, v.reference_price, v.trigger_price, v.resume_ns
+        ),
+        OutputMessage::ExecutionReport(e) => format!(
+            "O, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}",
+            e.user_id,
+            e.user_order_id,
+            e.symbol,
+            e.exec_id,
+            e.exec_type.as_str(),
+            e.order_status.as_str(),
+            e.last_qty,
+            e.last_price,
+            e.cum_qty,
+            e.leaves_qty,
+            e.timestamp_ns
+        ),
+        OutputMessage::MassCancelAck(m) => {
+            format!("M, {}, {}, {}", m.user_id, m.symbol, m.cancelled_count)
+        }
+        OutputMessage::UserTradingStatus(s) => format_user_trading_status(s),
+        OutputMessage::SessionStatus(s) => format_session_status(s),
+        OutputMessage::DepthUpdate(d) => format!("D, {}, {}", d.symbol, format_depth_body(d)),
     }
 }
 
@@ -172,6 +598,19 @@ pub fn format_output_csv(msg: &OutputMessage) -> String {
 /// - Trade:      `T, userIdBuy, userOrderIdBuy, userIdSell, userOrderIdSell, price, quantity`
 /// - TopOfBook:  `B, side, price, totalQuantity`
 /// - TOB elim:   `B, side, -, -`
+/// - Expired:    `E, userId, userOrderId, remainingQty` (no C++ equivalent)
+/// - Triggered:  `S, userId, userOrderId, stopPrice, lastTradePrice` (no C++ equivalent)
+/// - SelfTrdCxl: `K, userId, userOrderId, cancelledQty, remainingQty` (no C++ equivalent)
+/// - Indicative: `I, symbol, price, volume, imbalanceQty, imbalanceSide` (no C++ equivalent)
+/// - Status:     `H, symbol, state` (no C++ equivalent)
+/// - Instrument: `L, tickSize, lotSize, minQty, maxQty, minPrice, maxPrice` (no C++ equivalent)
+/// - ReplaceAck: `R, userId, userOrderId, price, remainingQty` (no C++ equivalent)
+/// - Reject:     `X, userId, userOrderId, reason` (no C++ equivalent)
+/// - PublicTrd:  `P, price, quantity, aggressorSide` (no C++ equivalent)
+/// - Depth:      `D, S|U[, action, side, price, qty]...` (no C++ equivalent)
+/// - Volatility: `V, symbol, referencePrice, triggerPrice, resumeNs` (no C++ equivalent)
+/// - ExecReport: `O, userId, userOrderId, symbol, execId, execType, ordStatus, lastQty,
+///   lastPrice, cumQty, leavesQty, timestampNs` (no C++ equivalent)
 pub fn format_output_legacy(msg: &OutputMessage) -> String {
     match msg {
         OutputMessage::Ack(a) => format!("A, {}, {}", a.user_id, a.user_order_id),
@@ -185,6 +624,12 @@ pub fn format_output_legacy(msg: &OutputMessage) -> String {
             t.price,
             t.quantity
         ),
+        OutputMessage::PublicTrade(t) => format!(
+            "P, {}, {}, {}",
+            t.price,
+            t.quantity,
+            t.aggressor_side.as_char()
+        ),
         OutputMessage::TopOfBook(t) => {
             let side_char = match t.side {
                 Side::Buy => 'B',
@@ -196,6 +641,99 @@ pub fn format_output_legacy(msg: &OutputMessage) -> String {
                 format!("B, {}, {}, {}", side_char, t.price, t.total_quantity)
             }
         }
+        OutputMessage::Expired(e) => {
+            format!("E, {}, {}, {}", e.user_id, e.user_order_id, e.remaining_qty)
+        }
+        OutputMessage::Triggered(t) => format!(
+            "S, {}, {}, {}, {}",
+            t.user_id, t.user_order_id, t.stop_price, t.last_trade_price
+        ),
+        OutputMessage::SelfTradeCancel(c) => format!(
+            "K, {}, {}, {}, {}",
+            c.user_id, c.user_order_id, c.cancelled_qty, c.remaining_qty
+        ),
+        OutputMessage::InstrumentDefinition(i) => format!(
+            "L, {}, {}, {}, {}, {}, {}, {}",
+            i.tick_size, i.lot_size, i.min_qty, i.max_qty, i.min_price, i.max_price, i.market_protection_ticks
+        ),
+        // Broadcast or multi-symbol records with no C++ equivalent carry
+        // their symbol, as in the full format.
+        OutputMessage::IndicativeUncross(_)
+        | OutputMessage::TradingStatus(_)
+        | OutputMessage::VolatilityInterruption(_)
+        | OutputMessage::ExecutionReport(_) => format_output_csv(msg),
+        OutputMessage::ReplaceAck(r) => format!(
+            "R, {}, {}, {}, {}",
+            r.user_id, r.user_order_id, r.price, r.remaining_qty
+        ),
+        OutputMessage::Reject(r) => {
+            format!("X, {}, {}, {}", r.user_id, r.user_order_id, r.reason)
+        }
+        OutputMessage::MassCancelAck(m) => format!("M, {}, {}", m.user_id, m.cancelled_count),
+        OutputMessage::UserTradingStatus(s) => format_user_trading_status(s),
+        OutputMessage::SessionStatus(s) => format_session_status(s),
+        OutputMessage::DepthUpdate(d) => format!("D, {}", format_depth_body(d)),
+    }
+}
+
+/// `U, userId, ENABLED|DISABLED` (the same in both formats).
+fn format_user_trading_status(s: &UserTradingStatus) -> String {
+    let status = if s.enabled { "ENABLED" } else { "DISABLED" };
+    format!("U, {}, {}", s.user_id, status)
+}
+
+/// `G, username, LOGGED_ON|LOGGED_OFF` (the same in both formats).
+fn format_session_status(s: &SessionStatus) -> String {
+    let status = if s.logged_on { "LOGGED_ON" } else { "LOGGED_OFF" };
+    format!("G, {}, {}", s.username, status)
+}
+
+/// Format an L3 `OrderBookEvent` as a CSV line.
+pub fn format_book_event_csv(event: &OrderBookEvent) -> String {
+    match event {
+        OrderBookEvent::Add {
+            symbol,
+            order_id,
+            side,
+            price,
+            quantity,
+        } => format!(
+            "A, {}, {}, {}, {}, {}",
+            symbol,
+            order_id,
+            side.as_char(),
+            price,
+            quantity
+        ),
+        OrderBookEvent::Execute {
+            symbol,
+            order_id,
+            price,
+            executed_qty,
+            remaining_qty,
+        } => format!(
+            "E, {}, {}, {}, {}, {}",
+            symbol, order_id, price, executed_qty, remaining_qty
+        ),
+        OrderBookEvent::Cancel {
+            symbol,
+            order_id,
+            cancelled_qty,
+            remaining_qty,
+        } => format!(
+            "X, {}, {}, {}, {}",
+            symbol, order_id, cancelled_qty, remaining_qty
+        ),
+        OrderBookEvent::Replace {
+            symbol,
+            old_order_id,
+            new_order_id,
+            price,
+            quantity,
+        } => format!(
+            "U, {}, {}, {}, {}, {}",
+            symbol, old_order_id, new_order_id, price, quantity
+        ),
     }
 }
 
@@ -203,6 +741,21 @@ pub fn format_output_legacy(msg: &OutputMessage) -> String {
 // Helpers
 // -----------------------------------------------------------------------------
 
+/// `S|U` followed by the level events, shared by both formatters.
+fn format_depth_body(d: &DepthUpdate) -> String {
+    let mut body = String::from(if d.snapshot { "S" } else { "U" });
+    for e in &d.events {
+        body.push_str(&format!(
+            ", {}, {}, {}, {}",
+            e.action.as_char(),
+            e.side.as_char(),
+            e.price,
+            e.quantity
+        ));
+    }
+    body
+}
+
 fn split_and_trim(s: &str, delimiter: char) -> Vec<String> {
     s.split(delimiter)
         .map(|tok| tok.trim().to_string())
@@ -213,3 +766,16 @@ fn parse_u32(s: &str) -> Result<u32, ParseIntError> {
     s.parse::<u32>()
 }
 
+fn parse_u64(s: &str) -> Result<u64, ParseIntError> {
+    s.parse::<u64>()
+}
+
+/// `-` means "not given"; anything else must be a `u32`.
+fn parse_optional_u32(s: &str) -> Option<Option<u32>> {
+    if s == "-" {
+        Some(None)
+    } else {
+        parse_u32(s).ok().map(Some)
+    }
+}
+
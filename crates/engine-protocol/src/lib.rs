@@ -8,6 +8,9 @@
 //!
 //! - [`binary_codec`] : binary wire protocol (for multi-client TCP)
 //! - [`csv_codec`]    : CSV compatibility (for tools / replay)
+//!
+//! Both codecs also encode the market-by-order (L3) feed of
+//! `engine_core::OrderBookEvent`s.
 
 pub mod wire_types;
 pub mod binary_codec;
@@ -19,5 +22,7 @@ pub use binary_codec::{
     encode_input,
     decode_output,
     encode_output,
+    decode_book_event,
+    encode_book_event,
 };
 
@@ -10,8 +10,39 @@
 /// Current protocol version.
 ///
 /// This can be bumped in the future if we change the framing or add
-/// incompatible message variants.
-pub const PROTOCOL_VERSION: u8 = 1;
+/// incompatible message variants. Older clients and journals still send
+/// frames of older versions, so a changed input layout also needs its old
+/// form upgraded in `binary_codec::decode_input`.
+///
+/// History:
+/// - 1: initial layout.
+/// - 2: `NewOrder` carries a time-in-force byte; `Expired` output.
+/// - 3: type 16 is the general `Reject` (with reason code).
+/// - 4: `Trade` carries the aggressor side; `PublicTrade` output.
+/// - 5: `Trade` and `PublicTrade` carry the execution timestamp.
+/// - 6: `NewOrder` carries a stop price; `Triggered` output.
+/// - 7: `NewOrder` carries a display (iceberg) quantity.
+/// - 8: `NewOrder` carries a flags byte (post-only, hidden).
+/// - 9: `NewOrder` carries a self-trade prevention mode; `SelfTradeCancel`
+///   output.
+/// - 10: `Auction` input; `IndicativeUncross` output; reject reason 10.
+/// - 11: `SetTradingState` input; `TradingStatus` output; reject reasons
+///   11 and 12.
+/// - 12: `QueryInstrument` input; `InstrumentDefinition` output; reject
+///   reasons 13 to 17.
+/// - 13: `VolatilityInterruption` output.
+/// - 14: `InstrumentDefinition` carries the market order protection.
+/// - 15: `ExecutionReport` output.
+/// - 16: `SetRiskLimits` input; reject reasons 18 to 22.
+/// - 17: `Logon` and `Heartbeat` inputs.
+/// - 18: `MassCancel` input; `MassCancelAck` output.
+/// - 19: `KillSwitch` and `EnableTrading` inputs; `UserTradingStatus`
+///   output; reject reasons 23 and 24.
+/// - 20: `Logon` carries credentials; `Logout` input; `SessionStatus`
+///   output.
+/// - 21: `SetRiskLimits` carries an admin token.
+/// - 22: `Flush`, `Auction` and `SetTradingState` carry an admin token.
+pub const PROTOCOL_VERSION: u8 = 22;
 
 /// Input message types (client → server).
 ///
@@ -25,11 +56,47 @@ pub enum WireInputType {
     /// Cancel `(user_id, user_order_id)`.
     Cancel = 1,
 
-    /// Flush all books.
+    /// Flush all books (admin).
     Flush = 2,
 
     /// Query current top-of-book for a symbol.
     QueryTopOfBook = 3,
+
+    /// Cancel-replace `(user_id, user_order_id)`.
+    Replace = 4,
+
+    /// Query price-level depth for a symbol.
+    QueryDepth = 5,
+
+    /// Start or uncross a symbol's call auction (admin).
+    Auction = 6,
+
+    /// Move a symbol to another trading state (admin).
+    SetTradingState = 7,
+
+    /// Query a symbol's instrument definition.
+    QueryInstrument = 8,
+
+    /// Replace a user's pre-trade risk limits (admin).
+    SetRiskLimits = 9,
+
+    /// Set the session's options (session).
+    Logon = 10,
+
+    /// Keep an idle session alive (session).
+    Heartbeat = 11,
+
+    /// Cancel a user's orders matching filters.
+    MassCancel = 12,
+
+    /// Block a user's trading (admin).
+    KillSwitch = 13,
+
+    /// Lift a kill switch (admin).
+    EnableTrading = 14,
+
+    /// End the session (session).
+    Logout = 15,
 }
 
 impl WireInputType {
@@ -39,6 +106,18 @@ impl WireInputType {
             1 => Some(WireInputType::Cancel),
             2 => Some(WireInputType::Flush),
             3 => Some(WireInputType::QueryTopOfBook),
+            4 => Some(WireInputType::Replace),
+            5 => Some(WireInputType::QueryDepth),
+            6 => Some(WireInputType::Auction),
+            7 => Some(WireInputType::SetTradingState),
+            8 => Some(WireInputType::QueryInstrument),
+            9 => Some(WireInputType::SetRiskLimits),
+            10 => Some(WireInputType::Logon),
+            11 => Some(WireInputType::Heartbeat),
+            12 => Some(WireInputType::MassCancel),
+            13 => Some(WireInputType::KillSwitch),
+            14 => Some(WireInputType::EnableTrading),
+            15 => Some(WireInputType::Logout),
             _ => None,
         }
     }
@@ -61,6 +140,51 @@ pub enum WireOutputType {
 
     /// Top-of-book event (snapshot or change).
     TopOfBook = 13,
+
+    /// Unfilled IOC/FOK quantity expired.
+    Expired = 14,
+
+    /// Ack a replace request.
+    ReplaceAck = 15,
+
+    /// Reject a request (new order, cancel, replace, or undecodable frame).
+    Reject = 16,
+
+    /// Anonymized public trade print.
+    PublicTrade = 17,
+
+    /// Price-level depth snapshot or incremental update.
+    DepthUpdate = 18,
+
+    /// A stop order was triggered.
+    Triggered = 19,
+
+    /// Quantity cancelled by self-trade prevention.
+    SelfTradeCancel = 20,
+
+    /// Indicative uncross of a call auction.
+    IndicativeUncross = 21,
+
+    /// A symbol's trading state changed.
+    TradingStatus = 22,
+
+    /// Instrument reference data.
+    InstrumentDefinition = 23,
+
+    /// A symbol's volatility band was breached.
+    VolatilityInterruption = 24,
+
+    /// FIX-style execution report of an order.
+    ExecutionReport = 25,
+
+    /// Summary of a mass cancel.
+    MassCancelAck = 26,
+
+    /// A user's trading was disabled or enabled.
+    UserTradingStatus = 27,
+
+    /// A session logged on or off.
+    SessionStatus = 28,
 }
 
 impl WireOutputType {
@@ -70,6 +194,52 @@ impl WireOutputType {
             11 => Some(WireOutputType::CancelAck),
             12 => Some(WireOutputType::Trade),
             13 => Some(WireOutputType::TopOfBook),
+            14 => Some(WireOutputType::Expired),
+            15 => Some(WireOutputType::ReplaceAck),
+            16 => Some(WireOutputType::Reject),
+            17 => Some(WireOutputType::PublicTrade),
+            18 => Some(WireOutputType::DepthUpdate),
+            19 => Some(WireOutputType::Triggered),
+            20 => Some(WireOutputType::SelfTradeCancel),
+            21 => Some(WireOutputType::IndicativeUncross),
+            22 => Some(WireOutputType::TradingStatus),
+            23 => Some(WireOutputType::InstrumentDefinition),
+            24 => Some(WireOutputType::VolatilityInterruption),
+            25 => Some(WireOutputType::ExecutionReport),
+            26 => Some(WireOutputType::MassCancelAck),
+            27 => Some(WireOutputType::UserTradingStatus),
+            28 => Some(WireOutputType::SessionStatus),
+            _ => None,
+        }
+    }
+}
+
+/// Market-by-order (L3) feed event types.
+///
+/// These frames form a separate feed from the `WireOutputType` messages.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WireBookEventType {
+    /// Order added to the book.
+    Add = 30,
+
+    /// Resting order (partially) executed.
+    Execute = 31,
+
+    /// Resting order reduced or deleted.
+    Cancel = 32,
+
+    /// Resting order replaced by a new id.
+    Replace = 33,
+}
+
+impl WireBookEventType {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            30 => Some(WireBookEventType::Add),
+            31 => Some(WireBookEventType::Execute),
+            32 => Some(WireBookEventType::Cancel),
+            33 => Some(WireBookEventType::Replace),
             _ => None,
         }
     }
@@ -0,0 +1,130 @@
+//! Session authentication.
+//!
+//! The [`Authenticator`] holds what client sessions check before a
+//! message reaches the engine:
+//! - the admin token that every admin message must carry: `Flush`,
+//!   `Auction`, `SetTradingState`, `SetRiskLimits`, `KillSwitch` and
+//!   `EnableTrading`;
+//! - the [`Credentials`] a `Logon` is checked against, when the server
+//!   has a credentials file (see [`crate::config::parse_credentials`]).
+//!   A successful logon binds the session to the account's user ids.
+//!
+//! Without a credentials file every session may act for any user id, as
+//! before logons existed.
+//!
+//! Secrets are never stored: an account keeps a salt and the SHA-256 of
+//! the salt followed by the secret (see [`hash_secret`]).
+
+use std::collections::{BTreeSet, HashMap};
+
+use engine_core::Logon;
+use sha2::{Digest, Sha256};
+
+/// The user ids a session may act for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserIds {
+    Any,
+    Only(BTreeSet<u32>),
+}
+
+impl UserIds {
+    pub fn contains(&self, user_id: u32) -> bool {
+        match self {
+            UserIds::Any => true,
+            UserIds::Only(user_ids) => user_ids.contains(&user_id),
+        }
+    }
+}
+
+/// One credentials file entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub salt: String,
+    /// SHA-256 of `salt` followed by the secret.
+    pub secret_hash: [u8; 32],
+    pub user_ids: UserIds,
+}
+
+/// Accounts by username.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    accounts: HashMap<String, Account>,
+}
+
+impl Credentials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or replace) the account of `username`.
+    pub fn insert(&mut self, username: String, account: Account) {
+        self.accounts.insert(username, account);
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// The user ids of `username`, if `secret` is its secret.
+    pub fn verify(&self, username: &str, secret: &str) -> Option<&UserIds> {
+        let account = self.accounts.get(username)?;
+        constant_time_eq(&hash_secret(&account.salt, secret), &account.secret_hash).then_some(&account.user_ids)
+    }
+}
+
+/// Checks admin tokens and logons for every session.
+#[derive(Debug, Default)]
+pub struct Authenticator {
+    admin_token: Option<String>,
+    credentials: Option<Credentials>,
+}
+
+impl Authenticator {
+    pub fn new(admin_token: Option<String>, credentials: Option<Credentials>) -> Self {
+        Self {
+            admin_token,
+            credentials,
+        }
+    }
+
+    /// Whether sessions must log on before sending anything else.
+    pub fn requires_logon(&self) -> bool {
+        self.credentials.is_some()
+    }
+
+    /// Whether `token` is the admin token (never true without one).
+    pub fn is_admin(&self, token: &str) -> bool {
+        self.admin_token
+            .as_deref()
+            .is_some_and(|admin| constant_time_eq(admin.as_bytes(), token.as_bytes()))
+    }
+
+    /// The user ids `logon` binds its session to, or `None` if its
+    /// credentials are wrong. Without a credentials file any logon
+    /// passes, for any user id.
+    pub fn logon(&self, logon: &Logon) -> Option<UserIds> {
+        match &self.credentials {
+            Some(credentials) => credentials.verify(&logon.username, &logon.secret).cloned(),
+            None => Some(UserIds::Any),
+        }
+    }
+}
+
+/// SHA-256 of `salt` followed by `secret`, as kept in the credentials
+/// file (in hex).
+pub fn hash_secret(salt: &str, secret: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(secret.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Compare without returning early, so the time taken says nothing about
+/// where a guess goes wrong.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -2,22 +2,29 @@
 // Update to handle BOTH CSV and binary protocols
 
 use std::error::Error;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
-use engine_core::OutputMessage;
+use engine_core::{InputMessage, Logon, OutputMessage, RejectReason, SessionStatus};
 use engine_protocol::binary_codec;  // Import the module
 use engine_protocol::csv_codec;     // Also import CSV codec
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::net::{tcp::OwnedWriteHalf, TcpStream};
+use tokio::time;
 
-use crate::types::{ClientId, ClientRegistry, EngineRequest, EngineTx, OutboundRx};
+use crate::auth::{Authenticator, UserIds};
+use crate::types::{ClientId, ClientRegistry, EngineRequest, EngineTx, OutboundRx, OutboundTx};
 
 /// Run the client I/O loop for a single connection.
 pub async fn run_client(
     client_id: ClientId,
     stream: TcpStream,
     engine_tx: EngineTx,
+    out_tx: OutboundTx,
     mut out_rx: OutboundRx,
     clients: ClientRegistry,
+    auth: Arc<Authenticator>,
 ) -> Result<(), Box<dyn Error>> {
     let _peer_addr = stream.peer_addr().ok();
 
@@ -40,9 +47,14 @@ pub async fn run_client(
 
     // Try to detect protocol by peeking at first byte
     let mut first_byte = [0u8; 1];
-    let protocol = if let Ok(_) = read_stream.peek(&mut first_byte).await {
-        if first_byte[0] == b'N' || first_byte[0] == b'C' || first_byte[0] == b'F' || first_byte[0] == b'Q' {
-            // Looks like CSV (N=NewOrder, C=Cancel, F=Flush, Q=Query)
+    let protocol = if read_stream.peek(&mut first_byte).await.is_ok() {
+        if matches!(
+            first_byte[0],
+            b'N' | b'C' | b'R' | b'F' | b'Q' | b'D' | b'U' | b'S' | b'I' | b'L' | b'A' | b'H' | b'M'
+                | b'K' | b'E' | b'O'
+        ) {
+            // Looks like CSV (N=NewOrder, C=Cancel, R=Replace, F=Flush, Q=Query,
+            // A=Logon, ...); binary frames start with a length prefix
             Protocol::Csv
         } else {
             // Assume binary
@@ -53,36 +65,202 @@ pub async fn run_client(
     };
 
     eprintln!("Client {} using {:?} protocol", client_id.0, protocol);
+    run_session(client_id, protocol, read_stream, engine_tx, out_tx, clients, auth).await
+}
+
+/// Read the client's inputs in `protocol` until it disconnects, logs out
+/// or misses its heartbeat, then close its session.
+///
+/// Replies go to `out_tx`, whoever writes them; the client must already
+/// be registered in `clients`.
+pub async fn run_session<R: AsyncRead + Unpin>(
+    client_id: ClientId,
+    protocol: Protocol,
+    read_stream: R,
+    engine_tx: EngineTx,
+    out_tx: OutboundTx,
+    clients: ClientRegistry,
+    auth: Arc<Authenticator>,
+) -> Result<(), Box<dyn Error>> {
+    let session = Session::new(auth);
 
     // Reader loop based on protocol
     match protocol {
         Protocol::Csv => {
-            run_csv_reader(client_id, read_stream, engine_tx, clients).await
+            run_csv_reader(client_id, read_stream, engine_tx, out_tx, clients, session).await
         }
         Protocol::Binary => {
-            run_binary_reader(client_id, read_stream, engine_tx, clients).await
+            run_binary_reader(client_id, read_stream, engine_tx, out_tx, clients, session).await
         }
     }
 }
 
-#[derive(Debug)]
-enum Protocol {
+/// Wire format of a client's inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// CSV lines.
     Csv,
+    /// Length-prefixed binary frames.
     Binary,
 }
 
-async fn run_csv_reader(
+/// Who a session is logged on as.
+///
+/// The place for per-user permissions: [`Session::permits`] asks it
+/// about every message.
+#[derive(Debug)]
+struct SessionUser {
+    username: String,
+    user_ids: UserIds,
+}
+
+/// Options the connection set with its last `Logon`, and who it is
+/// logged on as.
+#[derive(Debug)]
+struct Session {
+    cancel_on_disconnect: bool,
+    heartbeat_timeout: Option<Duration>,
+    auth: Arc<Authenticator>,
+    user: Option<SessionUser>,
+}
+
+impl Session {
+    fn new(auth: Arc<Authenticator>) -> Self {
+        Session {
+            cancel_on_disconnect: false,
+            heartbeat_timeout: None,
+            auth,
+            user: None,
+        }
+    }
+
+    /// Apply a session message; anything else the session may send is
+    /// handed back for the engine, and the rest is rejected here.
+    ///
+    /// A `Logout` is handed back too, for the reader to end the session
+    /// (it never reaches the engine). Admin messages (see [`access`]) are
+    /// only handed back if they carry the server's admin token, which is
+    /// cleared so it never reaches the journal.
+    fn handle(&mut self, client_id: ClientId, msg: InputMessage, out_tx: &OutboundTx) -> Option<InputMessage> {
+        match msg {
+            InputMessage::Logon(logon) => {
+                self.logon(client_id, logon, out_tx);
+                None
+            }
+            InputMessage::Heartbeat => None,
+            InputMessage::Logout => {
+                eprintln!("Client {} logout", client_id.0);
+                let username = self.user.take().map(|user| user.username).unwrap_or_default();
+                let _ = out_tx.send(OutputMessage::SessionStatus(SessionStatus {
+                    username,
+                    logged_on: false,
+                }));
+                Some(InputMessage::Logout)
+            }
+            msg if !self.permits(&msg) => {
+                eprintln!("Client {} not permitted: {:?}", client_id.0, msg);
+                let _ = out_tx.send(unauthorized(&msg));
+                None
+            }
+            mut msg if access(&msg) == Access::Admin => match admin_token_mut(&mut msg) {
+                Some(token) if self.auth.is_admin(token) => {
+                    token.clear();
+                    Some(msg)
+                }
+                _ => {
+                    eprintln!("Client {} admin command refused: bad admin token", client_id.0);
+                    let _ = out_tx.send(unauthorized(&msg));
+                    None
+                }
+            },
+            msg => Some(msg),
+        }
+    }
+
+    /// Check the logon's credentials and, if they pass, bind the session
+    /// to its user ids and take its options. A failed logon also drops
+    /// any earlier binding.
+    fn logon(&mut self, client_id: ClientId, logon: Logon, out_tx: &OutboundTx) {
+        let Some(user_ids) = self.auth.logon(&logon) else {
+            eprintln!("Client {} logon refused for '{}'", client_id.0, logon.username);
+            self.user = None;
+            let _ = out_tx.send(OutputMessage::reject(0, 0, String::new(), RejectReason::Unauthorized));
+            return;
+        };
+        eprintln!("Client {} logon: {:?} for {:?}", client_id.0, logon, user_ids);
+        self.cancel_on_disconnect = logon.cancel_on_disconnect;
+        self.heartbeat_timeout = (logon.heartbeat_secs > 0)
+            .then(|| Duration::from_secs(u64::from(logon.heartbeat_secs)));
+        self.user = Some(SessionUser {
+            username: logon.username.clone(),
+            user_ids,
+        });
+        let _ = out_tx.send(OutputMessage::SessionStatus(SessionStatus {
+            username: logon.username,
+            logged_on: true,
+        }));
+    }
+
+    /// Whether the session may send `msg`, admin token aside: with
+    /// credentials configured it must be logged on, and trade only for
+    /// the user ids it is bound to.
+    fn permits(&self, msg: &InputMessage) -> bool {
+        if !self.auth.requires_logon() {
+            return true;
+        }
+        let Some(user) = &self.user else {
+            return false;
+        };
+        match access(msg) {
+            Access::User(user_id) => user.user_ids.contains(user_id),
+            Access::Public | Access::Admin => true,
+        }
+    }
+
+    /// Wait for `read`, or give up (`None`) when the heartbeat timeout
+    /// passes first.
+    async fn within_heartbeat<F: Future>(&self, read: F) -> Option<F::Output> {
+        match self.heartbeat_timeout {
+            Some(limit) => time::timeout(limit, read).await.ok(),
+            None => Some(read.await),
+        }
+    }
+
+    /// Unregister the client and tell the engine, which (if the session
+    /// asked for it) cancels the orders it entered.
+    async fn close(self, client_id: ClientId, clients: &ClientRegistry, engine_tx: &EngineTx) {
+        clients.write().await.remove(&client_id);
+        let request = EngineRequest::Disconnected {
+            client_id,
+            cancel_orders: self.cancel_on_disconnect,
+        };
+        if engine_tx.send(request).is_err() {
+            eprintln!("Engine channel closed");
+        }
+    }
+}
+
+async fn run_csv_reader<R: AsyncRead + Unpin>(
     client_id: ClientId,
-    mut read_stream: tokio::net::tcp::OwnedReadHalf,
+    mut read_stream: R,
     engine_tx: EngineTx,
+    out_tx: OutboundTx,
     clients: ClientRegistry,
+    mut session: Session,
 ) -> Result<(), Box<dyn Error>> {
     let mut buffer = Vec::new();
     let mut temp_buf = [0u8; 1024];
 
-    loop {
+    'read: loop {
         // Read available data
-        match read_stream.read(&mut temp_buf).await {
+        let read = match session.within_heartbeat(read_stream.read(&mut temp_buf)).await {
+            Some(read) => read,
+            None => {
+                eprintln!("Client {} heartbeat timeout", client_id.0);
+                break;
+            }
+        };
+        match read {
             Ok(0) => {
                 // EOF - client disconnected
                 eprintln!("Client {} disconnected", client_id.0);
@@ -97,18 +275,23 @@ async fn run_csv_reader(
                     let line_str = String::from_utf8_lossy(&line);
                     let line_str = line_str.trim();
                     
-                    if line_str.is_empty() {
+                    if line_str.is_empty() || line_str.starts_with('#') {
                         continue;
                     }
                     
-                    eprintln!("Client {} CSV: {}", client_id.0, line_str);
-                    
                     // Parse CSV line
                     if let Some(input_msg) = csv_codec::parse_input_line(line_str) {
-                        let req = EngineRequest {
-                            client_id,
-                            msg: input_msg,
+                        // Log the parsed message rather than the line, whose
+                        // `Logon` secret `Debug` leaves out.
+                        eprintln!("Client {} CSV: {:?}", client_id.0, input_msg);
+
+                        let Some(msg) = session.handle(client_id, input_msg, &out_tx) else {
+                            continue;
                         };
+                        if msg == InputMessage::Logout {
+                            break 'read;
+                        }
+                        let req = EngineRequest::Input { client_id, msg };
                         
                         if engine_tx.send(req).is_err() {
                             eprintln!("Engine channel closed");
@@ -116,6 +299,7 @@ async fn run_csv_reader(
                         }
                     } else {
                         eprintln!("Client {} invalid CSV: {}", client_id.0, line_str);
+                        send_parse_error(&out_tx);
                     }
                 }
             }
@@ -126,27 +310,33 @@ async fn run_csv_reader(
         }
     }
 
-    // Remove client from registry
-    {
-        let mut guard = clients.write().await;
-        guard.remove(&client_id);
-    }
+    session.close(client_id, &clients, &engine_tx).await;
 
     Ok(())
 }
 
-async fn run_binary_reader(
+async fn run_binary_reader<R: AsyncRead + Unpin>(
     client_id: ClientId,
-    mut read_stream: tokio::net::tcp::OwnedReadHalf,
+    mut read_stream: R,
     engine_tx: EngineTx,
+    out_tx: OutboundTx,
     clients: ClientRegistry,
+    mut session: Session,
 ) -> Result<(), Box<dyn Error>> {
+
     loop {
         // Read length prefix (u32 BE)
         let mut len_buf = [0u8; 4];
-        if let Err(e) = read_stream.read_exact(&mut len_buf).await {
-            eprintln!("Client {} disconnected: {:?}", client_id.0, e);
-            break;
+        match session.within_heartbeat(read_stream.read_exact(&mut len_buf)).await {
+            Some(Ok(_)) => {}
+            Some(Err(e)) => {
+                eprintln!("Client {} disconnected: {:?}", client_id.0, e);
+                break;
+            }
+            None => {
+                eprintln!("Client {} heartbeat timeout", client_id.0);
+                break;
+            }
         }
 
         let frame_len = u32::from_be_bytes(len_buf) as usize;
@@ -154,20 +344,32 @@ async fn run_binary_reader(
             continue;
         }
 
+        // A peer that stalls mid-frame is as silent as one that sends
+        // nothing.
         let mut frame = vec![0u8; frame_len];
-        if let Err(e) = read_stream.read_exact(&mut frame).await {
-            eprintln!("Client {} read error: {:?}", client_id.0, e);
-            break;
+        match session.within_heartbeat(read_stream.read_exact(&mut frame)).await {
+            Some(Ok(_)) => {}
+            Some(Err(e)) => {
+                eprintln!("Client {} read error: {:?}", client_id.0, e);
+                break;
+            }
+            None => {
+                eprintln!("Client {} heartbeat timeout", client_id.0);
+                break;
+            }
         }
 
         match binary_codec::decode_input(&frame) {
             Ok(input_msg) => {
                 eprintln!("Client {} binary msg: {:?}", client_id.0, input_msg);
                 
-                let req = EngineRequest {
-                    client_id,
-                    msg: input_msg,
+                let Some(msg) = session.handle(client_id, input_msg, &out_tx) else {
+                    continue;
                 };
+                if msg == InputMessage::Logout {
+                    break;
+                }
+                let req = EngineRequest::Input { client_id, msg };
                 
                 if engine_tx.send(req).is_err() {
                     eprintln!("Engine channel closed");
@@ -175,21 +377,87 @@ async fn run_binary_reader(
                 }
             }
             Err(err) => {
+                // The frame was length-delimited, so the stream is still in
+                // sync: reject this frame and keep reading.
                 eprintln!("Client {} decode error: {:?}", client_id.0, err);
-                break;
+                send_parse_error(&out_tx);
             }
         }
     }
 
-    // Remove client from registry
-    {
-        let mut guard = clients.write().await;
-        guard.remove(&client_id);
-    }
+    session.close(client_id, &clients, &engine_tx).await;
 
     Ok(())
 }
 
+/// Tell the client a request could not be decoded.
+///
+/// Parse errors never reach the engine, so we reply directly on the
+/// client's own outbound channel.
+fn send_parse_error(out_tx: &OutboundTx) {
+    let _ = out_tx.send(OutputMessage::reject(0, 0, String::new(), RejectReason::ParseError));
+}
+
+/// Who may send a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Trading for this user id.
+    User(u32),
+    /// Session messages and queries: any session.
+    Public,
+    /// Everything else: only with the admin token.
+    Admin,
+}
+
+/// Who may send `msg`. Messages not listed here are admin messages, so
+/// a new one is refused until it is classified.
+fn access(msg: &InputMessage) -> Access {
+    match msg {
+        InputMessage::NewOrder(o) => Access::User(o.user_id),
+        InputMessage::Cancel(c) => Access::User(c.user_id),
+        InputMessage::Replace(r) => Access::User(r.user_id),
+        InputMessage::MassCancel(m) => Access::User(m.user_id),
+        InputMessage::QueryTopOfBook(_)
+        | InputMessage::QueryDepth(_)
+        | InputMessage::QueryInstrument(_)
+        | InputMessage::Logon(_)
+        | InputMessage::Heartbeat
+        | InputMessage::Logout => Access::Public,
+        _ => Access::Admin,
+    }
+}
+
+/// The admin token an admin message carries (`None` if it has none, so
+/// it is always refused).
+fn admin_token_mut(msg: &mut InputMessage) -> Option<&mut String> {
+    match msg {
+        InputMessage::Flush(f) => Some(&mut f.admin_token),
+        InputMessage::Auction(a) => Some(&mut a.admin_token),
+        InputMessage::SetTradingState(s) => Some(&mut s.admin_token),
+        InputMessage::SetRiskLimits(l) => Some(&mut l.admin_token),
+        InputMessage::KillSwitch(k) => Some(&mut k.admin_token),
+        InputMessage::EnableTrading(e) => Some(&mut e.admin_token),
+        _ => None,
+    }
+}
+
+/// The reject for a message the session may not send.
+fn unauthorized(msg: &InputMessage) -> OutputMessage {
+    let (user_id, user_order_id, symbol) = match msg {
+        InputMessage::NewOrder(o) => (o.user_id, o.user_order_id, o.symbol.clone()),
+        InputMessage::Cancel(c) => (c.user_id, c.user_order_id, String::new()),
+        InputMessage::Replace(r) => (r.user_id, r.user_order_id, String::new()),
+        InputMessage::MassCancel(m) => (m.user_id, 0, m.symbol.clone().unwrap_or_default()),
+        InputMessage::KillSwitch(k) => (k.user_id, 0, String::new()),
+        InputMessage::EnableTrading(e) => (e.user_id, 0, String::new()),
+        InputMessage::SetRiskLimits(l) => (l.user_id, 0, String::new()),
+        InputMessage::Auction(a) => (0, 0, a.symbol.clone()),
+        InputMessage::SetTradingState(s) => (0, 0, s.symbol.clone()),
+        _ => (0, 0, String::new()),
+    };
+    OutputMessage::reject(user_id, user_order_id, symbol, RejectReason::Unauthorized)
+}
+
 async fn write_csv_message(
     stream: &mut OwnedWriteHalf,
     msg: &OutputMessage,
@@ -7,17 +7,49 @@
 //! - `ENGINE_BIND_ADDR`   (default: "0.0.0.0")
 //! - `ENGINE_PORT`        (default: "9000")
 //! - `ENGINE_MAX_CLIENTS` (default: "1024")
+//! - `ENGINE_JOURNAL`       (journal file; default: none, nothing persisted)
+//! - `ENGINE_JOURNAL_FSYNC` (`always` (default), `never` or `every:N`)
+//! - `ENGINE_SNAPSHOT`       (snapshot file restored on startup; needs a journal)
+//! - `ENGINE_SNAPSHOT_EVERY` (journal records between snapshots; default "10000", 0 = shutdown only)
+//! - `ENGINE_STP`            (per-user self-trade prevention, `USER:MODE[,USER:MODE...]`)
+//! - `ENGINE_QUEUE_WHEN_HALTED` (`true` queues new orders for halted / closed symbols; default "false")
+//! - `ENGINE_INSTRUMENTS`    (instrument file; default: none, every symbol allowed)
+//! - `ENGINE_VOLATILITY_BAND` (`BPS:SECONDS[:AUCTION|HALT]`; default: none, no bands)
+//! - `ENGINE_RISK_LIMITS`    (pre-trade risk limits file; default: none, no limits)
+//! - `ENGINE_ADMIN_TOKEN`    (token authenticating admin messages: flush, auction, trading state, risk limits, kill switch; default: none, refused)
+//! - `ENGINE_CREDENTIALS`    (credentials file sessions log on with; default: none, no logon needed)
 //!
 //! CLI override (takes precedence over env):
 //! - `--addr HOST:PORT`
+//! - `--journal PATH`
+//! - `--fsync POLICY`
+//! - `--snapshot PATH`
+//! - `--snapshot-every N`
+//! - `--stp USER:MODE[,USER:MODE...]` (e.g. `1:CANCEL_OLDEST,2:DECREMENT`)
+//! - `--queue-when-halted`
+//! - `--instruments PATH`
+//! - `--volatility-band BPS:SECONDS[:AUCTION|HALT]` (e.g. `500:30` = ±5%, 30s auction)
+//! - `--risk-limits PATH`
+//! - `--admin-token TOKEN`
+//! - `--credentials PATH`
 //!
 //! Examples:
 //!   cargo run -p engine-server
 //!   cargo run -p engine-server -- --addr 127.0.0.1:7001
 
 use std::env;
+use std::fs;
+use std::io;
 use std::str::FromStr;
 
+use engine_core::{
+    Instrument, InstrumentRegistry, RiskLimits, SelfTradePrevention, SetRiskLimits, VolatilityAction,
+    VolatilityBands,
+};
+
+use crate::auth::{Account, Credentials, UserIds};
+use crate::journal::FsyncPolicy;
+
 /// Server configuration.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -29,6 +61,51 @@ pub struct Config {
 
     /// Maximum number of simultaneously connected clients.
     pub max_clients: usize,
+
+    /// Write-ahead journal to replay on startup and append to, if any.
+    pub journal_path: Option<String>,
+
+    /// When journal appends are fsynced.
+    pub journal_fsync: FsyncPolicy,
+
+    /// Snapshot file: restored on startup, rewritten periodically and on
+    /// shutdown. Only used together with a journal.
+    pub snapshot_path: Option<String>,
+
+    /// Journal records between snapshots (`0` = only on shutdown).
+    pub snapshot_every: u64,
+
+    /// Default self-trade prevention mode per user id, for orders that
+    /// don't set their own. Replaying a journal needs the same settings.
+    pub self_trade_prevention: Vec<(u32, SelfTradePrevention)>,
+
+    /// Queue new orders for halted / closed symbols until they reopen,
+    /// instead of rejecting them. Replaying a journal needs the same
+    /// setting.
+    pub queue_when_halted: bool,
+
+    /// Instrument reference data file (see [`parse_instruments`]). Without
+    /// one every symbol trades without rules. Replaying a journal needs
+    /// the same file.
+    pub instruments_path: Option<String>,
+
+    /// Volatility bands applied to every symbol (see
+    /// [`parse_volatility_bands`]), if any. Replaying a journal needs the
+    /// same setting.
+    pub volatility_bands: Option<VolatilityBands>,
+
+    /// Pre-trade risk limits file (see [`parse_risk_limits`]). Without one
+    /// no user has limits until an admin sets them.
+    pub risk_limits_path: Option<String>,
+
+    /// Token that admin messages (flush, auction, trading state, risk
+    /// limits, kill switch / enable trading) must carry. Without one
+    /// those messages are refused.
+    pub admin_token: Option<String>,
+
+    /// Credentials file (see [`parse_credentials`]). With one, sessions
+    /// must log on and may only act for their account's user ids.
+    pub credentials_path: Option<String>,
 }
 
 impl Config {
@@ -38,11 +115,42 @@ impl Config {
         let bind_addr = env::var("ENGINE_BIND_ADDR").unwrap_or_else(|_| "0.0.0.0".to_string());
         let port = read_env_or_default("ENGINE_PORT", 9000u16)?;
         let max_clients = read_env_or_default("ENGINE_MAX_CLIENTS", 1024usize)?;
+        let journal_path = env::var("ENGINE_JOURNAL").ok();
+        let journal_fsync = match env::var("ENGINE_JOURNAL_FSYNC") {
+            Ok(val) => val.parse::<FsyncPolicy>()?,
+            Err(_) => FsyncPolicy::default(),
+        };
+        let snapshot_path = env::var("ENGINE_SNAPSHOT").ok();
+        let snapshot_every = read_env_or_default("ENGINE_SNAPSHOT_EVERY", 10_000u64)?;
+        let self_trade_prevention = match env::var("ENGINE_STP") {
+            Ok(val) => parse_stp_list(&val)?,
+            Err(_) => Vec::new(),
+        };
+        let queue_when_halted = read_env_or_default("ENGINE_QUEUE_WHEN_HALTED", false)?;
+        let instruments_path = env::var("ENGINE_INSTRUMENTS").ok();
+        let volatility_bands = match env::var("ENGINE_VOLATILITY_BAND") {
+            Ok(val) => Some(parse_volatility_bands(&val)?),
+            Err(_) => None,
+        };
+        let risk_limits_path = env::var("ENGINE_RISK_LIMITS").ok();
+        let admin_token = env::var("ENGINE_ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+        let credentials_path = env::var("ENGINE_CREDENTIALS").ok();
 
         Ok(Config {
             bind_addr,
             port,
             max_clients,
+            journal_path,
+            journal_fsync,
+            snapshot_path,
+            snapshot_every,
+            self_trade_prevention,
+            queue_when_halted,
+            instruments_path,
+            volatility_bands,
+            risk_limits_path,
+            admin_token,
+            credentials_path,
         })
     }
 
@@ -50,6 +158,17 @@ impl Config {
     ///
     /// CLI overrides env where provided. Currently supports:
     ///   --addr HOST:PORT
+    ///   --journal PATH
+    ///   --fsync always|never|every:N
+    ///   --snapshot PATH
+    ///   --snapshot-every N
+    ///   --stp USER:MODE[,USER:MODE...]
+    ///   --queue-when-halted
+    ///   --instruments PATH
+    ///   --volatility-band BPS:SECONDS[:AUCTION|HALT]
+    ///   --risk-limits PATH
+    ///   --admin-token TOKEN
+    ///   --credentials PATH
     pub fn from_env_and_args() -> Result<Self, Box<dyn std::error::Error>> {
         let mut cfg = Self::from_env()?;
 
@@ -71,6 +190,72 @@ impl Config {
                         .parse::<u16>()
                         .map_err(|e| format!("Invalid port in --addr '{}': {}", val, e))?;
                 }
+                "--journal" => {
+                    let val = args
+                        .next()
+                        .ok_or_else(|| "Missing value for --journal (expected PATH)".to_string())?;
+                    cfg.journal_path = Some(val);
+                }
+                "--fsync" => {
+                    let val = args.next().ok_or_else(|| {
+                        "Missing value for --fsync (expected always, never or every:N)".to_string()
+                    })?;
+                    cfg.journal_fsync = val.parse::<FsyncPolicy>()?;
+                }
+                "--snapshot" => {
+                    let val = args
+                        .next()
+                        .ok_or_else(|| "Missing value for --snapshot (expected PATH)".to_string())?;
+                    cfg.snapshot_path = Some(val);
+                }
+                "--snapshot-every" => {
+                    let val = args.next().ok_or_else(|| {
+                        "Missing value for --snapshot-every (expected N)".to_string()
+                    })?;
+                    cfg.snapshot_every = val
+                        .parse::<u64>()
+                        .map_err(|e| format!("Invalid --snapshot-every '{}': {}", val, e))?;
+                }
+                "--stp" => {
+                    let val = args.next().ok_or_else(|| {
+                        "Missing value for --stp (expected USER:MODE[,USER:MODE...])".to_string()
+                    })?;
+                    cfg.self_trade_prevention = parse_stp_list(&val)?;
+                }
+                "--queue-when-halted" => {
+                    cfg.queue_when_halted = true;
+                }
+                "--instruments" => {
+                    let val = args
+                        .next()
+                        .ok_or_else(|| "Missing value for --instruments (expected PATH)".to_string())?;
+                    cfg.instruments_path = Some(val);
+                }
+                "--volatility-band" => {
+                    let val = args.next().ok_or_else(|| {
+                        "Missing value for --volatility-band (expected BPS:SECONDS[:AUCTION|HALT])".to_string()
+                    })?;
+                    cfg.volatility_bands = Some(parse_volatility_bands(&val)?);
+                }
+                "--risk-limits" => {
+                    let val = args
+                        .next()
+                        .ok_or_else(|| "Missing value for --risk-limits (expected PATH)".to_string())?;
+                    cfg.risk_limits_path = Some(val);
+                }
+                "--admin-token" => {
+                    let val = args
+                        .next()
+                        .filter(|token| !token.is_empty())
+                        .ok_or_else(|| "Missing value for --admin-token (expected TOKEN)".to_string())?;
+                    cfg.admin_token = Some(val);
+                }
+                "--credentials" => {
+                    let val = args
+                        .next()
+                        .ok_or_else(|| "Missing value for --credentials (expected PATH)".to_string())?;
+                    cfg.credentials_path = Some(val);
+                }
                 // Ignore unknown args for now (lets you extend later).
                 _ => {}
             }
@@ -85,6 +270,236 @@ impl Config {
     }
 }
 
+/// Parse per-user self-trade prevention settings:
+/// `USER:MODE[,USER:MODE...]`, where `MODE` is `CANCEL_NEWEST`,
+/// `CANCEL_OLDEST`, `CANCEL_BOTH`, `DECREMENT` or `NONE`.
+pub fn parse_stp_list(s: &str) -> Result<Vec<(u32, SelfTradePrevention)>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (user, mode) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("Invalid STP entry '{}', expected USER:MODE", entry))?;
+            let user_id = user
+                .trim()
+                .parse::<u32>()
+                .map_err(|e| format!("Invalid user id in STP entry '{}': {}", entry, e))?;
+            let mode = SelfTradePrevention::from_str_code(mode.trim())
+                .ok_or_else(|| format!("Invalid STP mode in entry '{}'", entry))?;
+            Ok((user_id, mode))
+        })
+        .collect()
+}
+
+/// Parse volatility band settings: `BPS:SECONDS[:ACTION]`, the band
+/// half-width in basis points of the last trade price, how long an
+/// interruption lasts, and `AUCTION` (default) or `HALT`.
+pub fn parse_volatility_bands(s: &str) -> Result<VolatilityBands, String> {
+    let parts: Vec<_> = s.split(':').map(str::trim).collect();
+    if parts.len() != 2 && parts.len() != 3 {
+        return Err(format!(
+            "Invalid volatility band '{}', expected BPS:SECONDS[:AUCTION|HALT]",
+            s
+        ));
+    }
+    let band_bps = parts[0]
+        .parse::<u32>()
+        .map_err(|e| format!("Invalid band width in volatility band '{}': {}", s, e))?;
+    if band_bps == 0 {
+        return Err(format!("Invalid volatility band '{}': the width must be non-zero", s));
+    }
+    let seconds = parts[1]
+        .parse::<u64>()
+        .map_err(|e| format!("Invalid duration in volatility band '{}': {}", s, e))?;
+    let action = match parts.get(2) {
+        Some(code) => VolatilityAction::from_str_code(code)
+            .ok_or_else(|| format!("Invalid action in volatility band '{}'", s))?,
+        None => VolatilityAction::default(),
+    };
+    Ok(VolatilityBands {
+        band_bps,
+        duration_ns: seconds.saturating_mul(1_000_000_000),
+        action,
+    })
+}
+
+/// Parse instrument reference data, one instrument per line:
+/// `SYMBOL, TICK_SIZE, LOT_SIZE, MIN_QTY, MAX_QTY, MIN_PRICE, MAX_PRICE[,
+/// MARKET_PROTECTION_TICKS]` (limits `0` = none). Blank lines and `#`
+/// comments are skipped.
+pub fn parse_instruments(s: &str) -> Result<InstrumentRegistry, String> {
+    let mut registry = InstrumentRegistry::new();
+    for (index, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        if fields.len() != 7 && fields.len() != 8 {
+            return Err(format!(
+                "Invalid instrument on line {}: expected 7 or 8 fields, got {}",
+                index + 1,
+                fields.len()
+            ));
+        }
+        let mut values = [0u32; 7];
+        for (value, field) in values.iter_mut().zip(&fields[1..]) {
+            *value = field
+                .parse::<u32>()
+                .map_err(|e| format!("Invalid instrument on line {}: '{}': {}", index + 1, field, e))?;
+        }
+        let [tick_size, lot_size, min_qty, max_qty, min_price, max_price, market_protection_ticks] = values;
+        let symbol = fields[0];
+        if symbol.is_empty() || tick_size == 0 || lot_size == 0 {
+            return Err(format!(
+                "Invalid instrument on line {}: needs a symbol and non-zero tick and lot sizes",
+                index + 1
+            ));
+        }
+        registry.insert(Instrument {
+            symbol: symbol.to_string(),
+            tick_size,
+            lot_size,
+            min_qty,
+            max_qty,
+            min_price,
+            max_price,
+            market_protection_ticks,
+        });
+    }
+    Ok(registry)
+}
+
+/// Read and parse an instrument file (see [`parse_instruments`]).
+pub fn load_instruments(path: &str) -> io::Result<InstrumentRegistry> {
+    let text = fs::read_to_string(path)?;
+    parse_instruments(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))
+}
+
+/// Parse pre-trade risk limits, one user per line: `USER, MAX_ORDER_QTY,
+/// MAX_NOTIONAL, MAX_OPEN_ORDERS, MAX_POSITION, MAX_GROSS_EXPOSURE`
+/// (limits `0` = none). `USER` `*` sets the defaults for every user
+/// without a line of their own. Blank lines and `#` comments are skipped.
+pub fn parse_risk_limits(s: &str) -> Result<Vec<SetRiskLimits>, String> {
+    let mut updates = Vec::new();
+    for (index, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        if fields.len() != 6 {
+            return Err(format!(
+                "Invalid risk limits on line {}: expected 6 fields, got {}",
+                index + 1,
+                fields.len()
+            ));
+        }
+        let user_id = match fields[0] {
+            "*" => 0,
+            user => match user.parse::<u32>() {
+                Ok(user_id) if user_id > 0 => user_id,
+                _ => return Err(format!("Invalid user on line {}: '{}'", index + 1, user)),
+            },
+        };
+        let mut values = [0u64; 5];
+        for (value, field) in values.iter_mut().zip(&fields[1..]) {
+            *value = field
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid risk limits on line {}: '{}': {}", index + 1, field, e))?;
+        }
+        let [max_order_qty, max_notional, max_open_orders, max_position, max_gross_exposure] = values;
+        let as_u32 = |value: u64| {
+            u32::try_from(value)
+                .map_err(|_| format!("Invalid risk limits on line {}: {} is too large", index + 1, value))
+        };
+        updates.push(SetRiskLimits {
+            user_id,
+            limits: RiskLimits {
+                max_order_qty: as_u32(max_order_qty)?,
+                max_notional,
+                max_open_orders: as_u32(max_open_orders)?,
+                max_position,
+                max_gross_exposure,
+            },
+            admin_token: String::new(),
+        });
+    }
+    Ok(updates)
+}
+
+/// Read and parse a risk limits file (see [`parse_risk_limits`]).
+pub fn load_risk_limits(path: &str) -> io::Result<Vec<SetRiskLimits>> {
+    let text = fs::read_to_string(path)?;
+    parse_risk_limits(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))
+}
+
+/// Parse a credentials file, one account per line: `USERNAME, SALT,
+/// SECRET_HASH, USER_IDS`, where `SECRET_HASH` is the hex SHA-256 of
+/// `SALT` followed by the secret (see [`crate::auth::hash_secret`]) and
+/// `USER_IDS` the user ids the account may act for, `|`-separated, or
+/// `*` for any. Blank lines and `#` comments are skipped.
+pub fn parse_credentials(s: &str) -> Result<Credentials, String> {
+    let mut credentials = Credentials::new();
+    for (index, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        let [username, salt, secret_hash, user_ids] = fields[..] else {
+            return Err(format!(
+                "Invalid credentials on line {}: expected 4 fields, got {}",
+                index + 1,
+                fields.len()
+            ));
+        };
+        if username.is_empty() {
+            return Err(format!("Missing username on line {}", index + 1));
+        }
+        let secret_hash = parse_sha256_hex(secret_hash)
+            .ok_or_else(|| format!("Invalid secret hash on line {}: expected 64 hex digits", index + 1))?;
+        let user_ids = match user_ids {
+            "*" => UserIds::Any,
+            list => UserIds::Only(
+                list.split('|')
+                    .map(|user| match user.trim().parse::<u32>() {
+                        Ok(user_id) if user_id > 0 => Ok(user_id),
+                        _ => Err(format!("Invalid user id on line {}: '{}'", index + 1, user.trim())),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        credentials.insert(
+            username.to_string(),
+            Account {
+                salt: salt.to_string(),
+                secret_hash,
+                user_ids,
+            },
+        );
+    }
+    Ok(credentials)
+}
+
+/// Read and parse a credentials file (see [`parse_credentials`]).
+pub fn load_credentials(path: &str) -> io::Result<Credentials> {
+    let text = fs::read_to_string(path)?;
+    parse_credentials(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))
+}
+
+fn parse_sha256_hex(s: &str) -> Option<[u8; 32]> {
+    if s.len() != 64 || !s.is_ascii() {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(s.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(digest)
+}
+
 fn read_env_or_default<T>(key: &str, default: T) -> Result<T, Box<dyn std::error::Error>>
 where
     T: FromStr,
@@ -1,44 +1,384 @@
 // crates/engine-server/src/engine_task.rs
 
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
 use tokio::sync::mpsc::UnboundedReceiver;
-use engine_core::{MatchingEngine, OutputMessage};
-use crate::types::{ClientRegistry, EngineRequest};
+use tokio::time;
+use engine_core::order::Order;
+use engine_core::{
+    Cancel, Clock, InputMessage, MatchingEngine, OutputMessage, ReplayClock, SetTradingState, SystemClock,
+    TradingState,
+};
+use crate::config::{self, Config};
+use crate::journal::{self, Journal};
+use crate::risk::RiskGateway;
+use crate::routing::{Destination, Router};
+use crate::types::{ClientId, ClientRegistry, EngineRequest};
+
+/// Requester of the inputs the engine task sends itself (client ids
+/// start at 1).
+const ENGINE: ClientId = ClientId(0);
+
+/// Journal plus optional periodic snapshots of the live engine.
+pub struct Persistence {
+    journal: Journal,
+    snapshot_path: Option<String>,
+    snapshot_every: u64,
+}
+
+impl Persistence {
+    /// Journal `msg` (if it can change state) with the engine time it is
+    /// processed at, and return its sequence number.
+    fn append(&mut self, msg: &InputMessage, timestamp_ns: u64) -> io::Result<Option<u64>> {
+        if !journal::is_journaled(msg) {
+            return Ok(None);
+        }
+        self.journal.append(msg, timestamp_ns).map(Some)
+    }
+
+    /// Write a snapshot covering everything journaled so far, plus the
+    /// risk gateway's state next to it.
+    ///
+    /// The journal is synced first so a snapshot never gets ahead of it.
+    fn snapshot(&mut self, engine: &MatchingEngine, risk: &RiskGateway) {
+        let path = match &self.snapshot_path {
+            Some(path) => path,
+            None => return,
+        };
+        let seq = self.journal.last_seq();
+        let result = self
+            .journal
+            .sync()
+            .and_then(|_| write_file_atomically(path, |w| engine.snapshot(seq, w)))
+            .and_then(|_| write_file_atomically(&risk_state_path(path), |w| risk.write_state(seq, w)));
+        match result {
+            Ok(()) => eprintln!("Snapshot: wrote {} at journal seq {}", path, seq),
+            Err(e) => eprintln!("Snapshot: failed to write {}: {}", path, e),
+        }
+    }
+}
+
+/// Where the risk gateway's state is kept next to the snapshot at `path`.
+fn risk_state_path(path: &str) -> String {
+    format!("{}.risk", path)
+}
+
+/// Write via a temporary file + rename so a crash never leaves a torn
+/// file in place.
+fn write_file_atomically(
+    path: &str,
+    write: impl FnOnce(&mut BufWriter<&File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let tmp = format!("{}.tmp", path);
+    {
+        let file = File::create(&tmp)?;
+        let mut writer = BufWriter::new(&file);
+        write(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)
+}
+
+/// Restore the risk gateway's state written with the snapshot at
+/// `snapshot_seq`. A missing or stale file (a crash between the two
+/// writes) leaves it empty: positions and open orders then only count
+/// from the journal records replayed after the snapshot.
+fn restore_risk_state(risk: &mut RiskGateway, snapshot_path: &str, snapshot_seq: u64) -> io::Result<()> {
+    let path = risk_state_path(snapshot_path);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("Risk: no state at {}, starting without positions", path);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let mut restored = RiskGateway::new();
+    let seq = restored.restore_state(BufReader::new(file))?;
+    if seq == snapshot_seq {
+        *risk = restored;
+    } else {
+        eprintln!(
+            "Risk: state at {} is at journal seq {}, not {}; starting without positions",
+            path, seq, snapshot_seq
+        );
+    }
+    Ok(())
+}
+
+/// Build the engine from the latest snapshot (if any) plus the journal
+/// records after it, so every book is back to where it was before the
+/// last shutdown.
+///
+/// The engine runs on the returned [`ReplayClock`]: during recovery it
+/// follows the journaled timestamps, and live it is advanced to the wall
+/// clock before each input (and that time is journaled), so replaying
+/// reproduces every order priority and trade timestamp.
+///
+/// The risk gateway is rebuilt alongside: limits from the configured
+/// file, then positions, open orders and admin limit changes from its
+/// state at the snapshot plus the replayed records.
+pub fn recover_engine(
+    config: &Config,
+) -> io::Result<(MatchingEngine, ReplayClock, RiskGateway, Option<Persistence>)> {
+    let clock = ReplayClock::new();
+    let mut risk = RiskGateway::new();
+    let mut engine = MatchingEngine::with_clock(Arc::new(clock.clone()));
+    let mut snapshot_seq = 0;
+
+    if let Some(path) = &config.snapshot_path {
+        match File::open(path) {
+            Ok(file) => {
+                let (restored, seq) = MatchingEngine::restore_with_clock(
+                    BufReader::new(file),
+                    Arc::new(clock.clone()),
+                )?;
+                engine = restored;
+                snapshot_seq = seq;
+                eprintln!("Snapshot: restored {} at journal seq {}", path, seq);
+                restore_risk_state(&mut risk, path, seq)?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    for &(user_id, mode) in &config.self_trade_prevention {
+        engine.set_self_trade_prevention(user_id, mode);
+    }
+    engine.set_queue_when_halted(config.queue_when_halted);
+    if let Some(path) = &config.instruments_path {
+        let instruments = config::load_instruments(path)?;
+        eprintln!("Instruments: loaded {} from {}", instruments.len(), path);
+        engine.set_instruments(instruments);
+    }
+    engine.set_volatility_bands(config.volatility_bands);
+    // On during recovery too, so exec ids and fill state carry on (and
+    // the risk gateway sees every order open and close).
+    engine.set_execution_reports(true);
+    if let Some(path) = &config.risk_limits_path {
+        let updates = config::load_risk_limits(path)?;
+        eprintln!("Risk: loaded limits for {} users from {}", updates.len(), path);
+        for update in &updates {
+            risk.set_limits(update);
+        }
+    }
+
+    let persistence = match &config.journal_path {
+        Some(path) => {
+            let (journal, records) = Journal::open(path, config.journal_fsync)?;
+            if journal.last_seq() < snapshot_seq {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "journal {} ends at seq {}, before snapshot seq {}",
+                        path,
+                        journal.last_seq(),
+                        snapshot_seq
+                    ),
+                ));
+            }
+            let mut replayed = 0;
+            for record in records.into_iter().filter(|r| r.seq > snapshot_seq) {
+                clock.advance_to(record.timestamp_ns);
+                let outputs = engine.process_message(record.msg.clone());
+                risk.on_outputs(&record.msg, &outputs);
+                replayed += 1;
+            }
+            eprintln!(
+                "Journal: replayed {} records from {} (fsync: {:?})",
+                replayed, path, config.journal_fsync
+            );
+            Some(Persistence {
+                journal,
+                snapshot_path: config.snapshot_path.clone(),
+                snapshot_every: config.snapshot_every,
+            })
+        }
+        None => None,
+    };
+
+    // Only live traffic needs incremental depth; replay outputs are dropped.
+    engine.set_depth_updates(true);
+    Ok((engine, clock, risk, persistence))
+}
 
 pub async fn run_engine_loop(
     mut engine_rx: UnboundedReceiver<EngineRequest>,
     clients: ClientRegistry,
+    mut engine: MatchingEngine,
+    clock: ReplayClock,
+    mut risk: RiskGateway,
+    mut persistence: Option<Persistence>,
 ) {
-    let mut engine = MatchingEngine::new();
+    let mut router = Router::new();
     let mut requests_received: u64 = 0;
     let mut outputs_generated: u64 = 0;
     
     eprintln!("Engine task: started");
     
-    while let Some(EngineRequest { client_id, msg }) = engine_rx.recv().await {
-        requests_received += 1;
-        
-        eprintln!("Engine: Processing {:?} from client {}", msg, client_id.0);
-        
-        // Process message in the matching engine
-        let outputs: Vec<OutputMessage> = engine.process_message(msg);
-        outputs_generated += outputs.len() as u64;
+    'requests: loop {
+        // A volatility interruption ends on time even if nothing else
+        // arrives: the engine task then sends itself a `SetTradingState`
+        // to `Continuous`, journaled so a replay ends it at the same time.
+        let request = match engine.next_volatility_resume() {
+            Some((resume_ns, symbol)) => {
+                let resume = InputMessage::SetTradingState(SetTradingState {
+                    symbol: symbol.to_string(),
+                    state: TradingState::Continuous,
+                    admin_token: String::new(),
+                });
+                let delay = Duration::from_nanos(resume_ns.saturating_sub(SystemClock.now_ns()));
+                tokio::select! {
+                    request = engine_rx.recv() => request,
+                    _ = time::sleep(delay) => Some(EngineRequest::Input { client_id: ENGINE, msg: resume }),
+                }
+            }
+            None => engine_rx.recv().await,
+        };
+        let Some(request) = request else {
+            break;
+        };
+
+        let inputs = match request {
+            EngineRequest::Input { client_id, msg } => vec![(client_id, msg)],
+            EngineRequest::Disconnected { client_id, cancel_orders: false } => {
+                router.disconnect(client_id);
+                continue;
+            }
+            // Cancel-on-disconnect: plain cancels on the session's behalf,
+            // journaled and published like any other.
+            EngineRequest::Disconnected { client_id, cancel_orders: true } => {
+                router.disconnect(client_id);
+                let orders: Vec<_> = router
+                    .orders_of(client_id)
+                    .into_iter()
+                    .filter(|&(user_id, user_order_id)| engine.has_order(user_id, user_order_id))
+                    .collect();
+                eprintln!(
+                    "Engine: client {} disconnected, cancelling its {} open orders",
+                    client_id.0,
+                    orders.len()
+                );
+                orders
+                    .into_iter()
+                    .map(|(user_id, user_order_id)| {
+                        (client_id, InputMessage::Cancel(Cancel { user_id, user_order_id }))
+                    })
+                    .collect()
+            }
+        };
+
+        for (client_id, msg) in inputs {
+            requests_received += 1;
         
-        eprintln!("Engine: Generated {} outputs", outputs.len());
-        for out in &outputs {
-            eprintln!("  -> {:?}", out);
-        }
+            eprintln!("Engine: Processing {:?} from client {}", msg, client_id.0);
+
+            // Pre-trade risk: breaches are rejected before they are
+            // journaled. Limit updates are journaled and applied with the
+            // outputs, like on replay.
+            if let InputMessage::SetRiskLimits(update) = &msg {
+                eprintln!("Risk: limits of user {} set to {:?}", update.user_id, update.limits);
+            }
+            let breach = match &msg {
+                InputMessage::NewOrder(order) => risk
+                    .check(order)
+                    .map(|reason| OutputMessage::reject(order.user_id, order.user_order_id, order.symbol.clone(), reason)),
+                InputMessage::Replace(replace) => {
+                    // A queued order is checked as the order it will enter as.
+                    let queued = engine
+                        .get_queued_order(replace.user_id, replace.user_order_id)
+                        .map(|o| Order::from_new_order(o, 0));
+                    engine
+                        .get_order(replace.user_id, replace.user_order_id)
+                        .or(queued.as_ref())
+                        .and_then(|order| {
+                            risk.check_replace(order, replace)
+                                .map(|reason| OutputMessage::reject(order.user_id, order.user_order_id, order.symbol.clone(), reason))
+                        })
+                }
+                _ => None,
+            };
+            if let Some(reject) = breach {
+                eprintln!("Risk: rejected {:?}", reject);
+                if let Some(tx) = clients.read().await.get(&client_id) {
+                    if tx.send(reject).is_err() {
+                        eprintln!("Failed to send to client {}", client_id.0);
+                    }
+                }
+                continue;
+            }
+
+            // Stamp the input with engine time (never behind the journal).
+            clock.advance_to(SystemClock.now_ns());
+            let now = clock.now_ns();
+
+            // Write-ahead: persist before the engine acts on it. An input
+            // that can't be journaled is never processed; the engine stops
+            // (ending every session) rather than run ahead of its journal.
+            let seq = match persistence.as_mut().map(|p| p.append(&msg, now)).transpose() {
+                Ok(seq) => seq.flatten(),
+                Err(e) => {
+                    eprintln!("Journal: append failed, stopping the engine: {}", e);
+                    break 'requests;
+                }
+            };
+
+            // Process message in the matching engine
+            let outputs: Vec<OutputMessage> = engine.process_message(msg.clone());
+            outputs_generated += outputs.len() as u64;
+            risk.on_outputs(&msg, &outputs);
         
-        // Broadcast to all clients
-        let guard = clients.read().await;
-        for (target_id, tx) in guard.iter() {
+            eprintln!("Engine: Generated {} outputs", outputs.len());
             for out in &outputs {
-                if tx.send(out.clone()).is_err() {
-                    eprintln!("Failed to send to client {}", target_id.0);
+                eprintln!("  -> {:?}", out);
+            }
+        
+            // Private reports to their owners, market data to everyone
+            let deliveries = router.route(client_id, &msg, outputs, &engine);
+            let guard = clients.read().await;
+            for (dest, out) in deliveries {
+                match dest {
+                    Destination::Client(target_id) => {
+                        if let Some(tx) = guard.get(&target_id) {
+                            if tx.send(out).is_err() {
+                                eprintln!("Failed to send to client {}", target_id.0);
+                            }
+                        }
+                    }
+                    Destination::All => {
+                        for (target_id, tx) in guard.iter() {
+                            if tx.send(out.clone()).is_err() {
+                                eprintln!("Failed to send to client {}", target_id.0);
+                            }
+                        }
+                    }
+                }
+            }
+            drop(guard);
+
+            if let (Some(p), Some(seq)) = (persistence.as_mut(), seq) {
+                if p.snapshot_every > 0 && seq % p.snapshot_every == 0 {
+                    p.snapshot(&engine, &risk);
                 }
             }
         }
     }
-    
+
+    // Final snapshot (or at least a final fsync) on shutdown.
+    if let Some(p) = persistence.as_mut() {
+        if p.snapshot_path.is_some() {
+            p.snapshot(&engine, &risk);
+        } else if let Err(e) = p.journal.sync() {
+            eprintln!("Journal: final sync failed: {}", e);
+        }
+    }
+
     eprintln!("==============================================================");
     eprintln!("Engine task shutting down.");
     eprintln!("  Requests received:  {}", requests_received);
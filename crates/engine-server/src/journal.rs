@@ -0,0 +1,247 @@
+//! Write-ahead journal of engine inputs.
+//!
+//! Every state-changing `InputMessage` is appended here *before* the
+//! engine processes it. Because `MatchingEngine` is deterministic,
+//! replaying the journal into a fresh engine rebuilds every `OrderBook`.
+//!
+//! On-disk format: a flat sequence of records, each
+//!
+//! ```text
+//! [0..4]    record_len (u32 BE) = 16 + frame length
+//! [4..12]   seq (u64 BE), starting at 1, no gaps
+//! [12..20]  timestamp_ns (u64 BE), the time the engine processed it
+//! [20..]    frame: the message encoded with `binary_codec::encode_input`
+//! ```
+//!
+//! Each frame keeps the protocol version it was written with, so a
+//! journal survives protocol upgrades: replay reads older frames with
+//! `binary_codec::decode_input`.
+//!
+//! A record cut short by a crash is dropped (and truncated away when the
+//! journal is reopened for writing); anything else that fails to decode
+//! is reported as `InvalidData`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use engine_core::InputMessage;
+use engine_protocol::{decode_input, encode_input};
+
+/// Size of the `seq` + `timestamp_ns` record header (after the length).
+const RECORD_HEADER_LEN: usize = 16;
+
+/// When appended records are forced to stable storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FsyncPolicy {
+    /// `fsync` after every record: nothing acknowledged is ever lost.
+    #[default]
+    Always,
+    /// `fsync` after every `n` records.
+    Every(u32),
+    /// Never `fsync`; leave it to the OS (records still reach the page
+    /// cache immediately, so only a machine crash can lose them).
+    Never,
+}
+
+impl FromStr for FsyncPolicy {
+    type Err = String;
+
+    /// Parses `always`, `never` or `every:N` (N > 0).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(FsyncPolicy::Always),
+            "never" => Ok(FsyncPolicy::Never),
+            _ => s
+                .strip_prefix("every:")
+                .and_then(|n| n.parse::<u32>().ok())
+                .filter(|&n| n > 0)
+                .map(FsyncPolicy::Every)
+                .ok_or_else(|| {
+                    format!("Invalid fsync policy '{}', expected always, never or every:N", s)
+                }),
+        }
+    }
+}
+
+/// One journaled input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalRecord {
+    pub seq: u64,
+    pub timestamp_ns: u64,
+    pub msg: InputMessage,
+}
+
+/// Returns `true` for inputs that can change engine (or risk gateway)
+/// state and therefore need journaling (queries and session messages
+/// don't).
+pub fn is_journaled(msg: &InputMessage) -> bool {
+    !matches!(
+        msg,
+        InputMessage::QueryTopOfBook(_)
+            | InputMessage::QueryDepth(_)
+            | InputMessage::QueryInstrument(_)
+            | InputMessage::Logon(_)
+            | InputMessage::Heartbeat
+    )
+}
+
+/// Append-only journal writer.
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+    policy: FsyncPolicy,
+    next_seq: u64,
+    unsynced: u32,
+}
+
+impl Journal {
+    /// Open (or create) the journal at `path` for appending.
+    ///
+    /// Returns the journal positioned after the last complete record,
+    /// together with every record already in it, for replay.
+    pub fn open(path: impl AsRef<Path>, policy: FsyncPolicy) -> io::Result<(Self, Vec<JournalRecord>)> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        let (records, valid_len) = parse_records(&buf)?;
+
+        // Drop a torn tail so new records follow the last good one.
+        if valid_len < buf.len() {
+            file.set_len(valid_len as u64)?;
+        }
+        file.seek(SeekFrom::Start(valid_len as u64))?;
+
+        let next_seq = records.last().map_or(1, |r| r.seq + 1);
+        let journal = Journal {
+            file,
+            policy,
+            next_seq,
+            unsynced: 0,
+        };
+        Ok((journal, records))
+    }
+
+    /// Append `msg`, stamped with the next sequence number and
+    /// `timestamp_ns`, honouring the fsync policy. Returns the sequence
+    /// number.
+    ///
+    /// `timestamp_ns` should be the time the engine's clock shows while
+    /// processing `msg`, so a replay reproduces the same timestamps.
+    pub fn append(&mut self, msg: &InputMessage, timestamp_ns: u64) -> io::Result<u64> {
+        let mut frame = Vec::new();
+        encode_input(msg, &mut frame)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+
+        let seq = self.next_seq;
+        let record_len = u32::try_from(RECORD_HEADER_LEN + frame.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "journal record too large"))?;
+
+        let mut record = Vec::with_capacity(4 + RECORD_HEADER_LEN + frame.len());
+        record.extend_from_slice(&record_len.to_be_bytes());
+        record.extend_from_slice(&seq.to_be_bytes());
+        record.extend_from_slice(&timestamp_ns.to_be_bytes());
+        record.extend_from_slice(&frame);
+
+        // A failed write or sync is rolled back to the last good record,
+        // so nothing torn or unacknowledged is left for replay to trip
+        // over (and the sequence number is reused by the next append).
+        let len = self.file.stream_position()?;
+        if let Err(e) = self.write_record(&record) {
+            self.file.set_len(len)?;
+            self.file.seek(SeekFrom::Start(len))?;
+            return Err(e);
+        }
+        self.next_seq += 1;
+
+        Ok(seq)
+    }
+
+    /// Write one whole record, then fsync if the policy says so.
+    fn write_record(&mut self, record: &[u8]) -> io::Result<()> {
+        // One write per record keeps a crash from interleaving halves.
+        self.file.write_all(record)?;
+
+        let sync = match self.policy {
+            FsyncPolicy::Always => true,
+            FsyncPolicy::Every(n) => self.unsynced + 1 >= n,
+            FsyncPolicy::Never => false,
+        };
+        if sync {
+            self.sync()
+        } else {
+            self.unsynced += 1;
+            Ok(())
+        }
+    }
+
+    /// Force everything appended so far to stable storage.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()?;
+        self.unsynced = 0;
+        Ok(())
+    }
+
+    /// Sequence number of the last record written (`0` if empty).
+    pub fn last_seq(&self) -> u64 {
+        self.next_seq - 1
+    }
+}
+
+/// Read every complete record of the journal at `path` (read-only).
+pub fn read_journal(path: impl AsRef<Path>) -> io::Result<Vec<JournalRecord>> {
+    let mut buf = Vec::new();
+    File::open(path)?.read_to_end(&mut buf)?;
+    parse_records(&buf).map(|(records, _)| records)
+}
+
+/// Parse records from `buf`, returning them plus the byte length of the
+/// complete ones (a torn final record is not an error).
+fn parse_records(buf: &[u8]) -> io::Result<(Vec<JournalRecord>, usize)> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+
+    while buf.len() - offset >= 4 {
+        let record_len = u32::from_be_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize;
+        let start = offset + 4;
+        if record_len < RECORD_HEADER_LEN {
+            return Err(invalid_data(offset, "record shorter than its header"));
+        }
+        if buf.len() - start < record_len {
+            break;
+        }
+
+        let seq = u64::from_be_bytes(buf[start..start + 8].try_into().unwrap());
+        let timestamp_ns = u64::from_be_bytes(buf[start + 8..start + 16].try_into().unwrap());
+        let msg = decode_input(&buf[start + RECORD_HEADER_LEN..start + record_len])
+            .map_err(|e| invalid_data(offset, &e.to_string()))?;
+
+        let expected_seq = records.last().map_or(seq, |r: &JournalRecord| r.seq + 1);
+        if seq != expected_seq {
+            return Err(invalid_data(offset, "sequence gap"));
+        }
+
+        records.push(JournalRecord {
+            seq,
+            timestamp_ns,
+            msg,
+        });
+        offset = start + record_len;
+    }
+
+    Ok((records, offset))
+}
+
+fn invalid_data(offset: usize, what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt journal record at byte {}: {}", offset, what),
+    )
+}
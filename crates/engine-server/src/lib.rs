@@ -5,8 +5,10 @@
 pub mod config;
 pub mod types;
 pub mod server;
-
-// these are internal modules, not re-exported
-mod client;
-mod engine_task;
+pub mod journal;
+pub mod risk;
+pub mod auth;
+pub mod routing;
+pub mod client;
+pub mod engine_task;
 
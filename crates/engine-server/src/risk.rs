@@ -0,0 +1,411 @@
+//! Pre-trade risk checks.
+//!
+//! The [`RiskGateway`] sits between the client readers and the engine:
+//! the engine task asks it about every `NewOrder` and `Replace` before
+//! it is journaled, and a breach is rejected right there with one of the
+//! `Risk*` reject reasons, so the order never reaches the book.
+//!
+//! Per user (see [`RiskLimits`]; `0` = no limit):
+//! - order quantity and order notional,
+//! - number of open orders,
+//! - net position per symbol if the order filled completely (orders that
+//!   reduce the position are always allowed),
+//! - gross exposure: open positions at the symbol's last trade price, plus
+//!   open orders at their price, plus the new order.
+//!
+//! An order's notional uses its limit price, else its stop price, else
+//! the symbol's last trade price. A market order before the symbol's
+//! first trade has no price to use, so any notional or gross exposure
+//! limit rejects it.
+//!
+//! A replace is checked as the order it leaves behind: its new price and
+//! total quantity against the quantity and notional limits, its new
+//! leaves quantity against the position and exposure limits (in place of
+//! what the order counted for before). It doesn't add an open order.
+//!
+//! The gateway only learns from the engine task's inputs and outputs:
+//! open orders from execution reports, net positions and last prices
+//! from `Trade`s, and limit changes from `SetRiskLimits` admin messages,
+//! which are journaled like any other input. Its state is written next
+//! to the engine snapshot (see [`RiskGateway::write_state`]) so a restart
+//! carries on from there.
+//!
+//! Limits from the risk limits file aren't part of that state; they are
+//! read again at every start. A `SetRiskLimits` applies on top of them,
+//! as if it had been sent right after the file was loaded.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use engine_core::order::Order;
+use engine_core::{
+    ExecutionReport, InputMessage, NewOrder, OutputMessage, RejectReason, Replace, RiskLimits,
+    SetRiskLimits, Side,
+};
+
+/// What the gateway keeps of an open order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OpenOrder {
+    /// Limit price, or stop price of a stop-market order.
+    price: u32,
+    leaves_qty: u32,
+}
+
+/// Per-user limits plus the open orders and positions they apply to.
+#[derive(Debug, Default)]
+pub struct RiskGateway {
+    default_limits: RiskLimits,
+    limits: HashMap<u32, RiskLimits>,
+    /// Limits set by `SetRiskLimits` messages (user id `0` = defaults).
+    admin_limits: HashMap<u32, RiskLimits>,
+    /// user id -> user order id -> order
+    open_orders: HashMap<u32, HashMap<u32, OpenOrder>>,
+    /// user id -> symbol -> net position (bought minus sold)
+    positions: HashMap<u32, HashMap<String, i64>>,
+    last_prices: HashMap<String, u32>,
+}
+
+impl RiskGateway {
+    /// A gateway without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a limit update from the risk limits file (`user_id == 0`
+    /// sets the defaults).
+    pub fn set_limits(&mut self, update: &SetRiskLimits) {
+        if update.user_id == 0 {
+            self.default_limits = update.limits;
+        } else {
+            self.limits.insert(update.user_id, update.limits);
+        }
+    }
+
+    /// The limits that apply to `user_id`.
+    pub fn limits(&self, user_id: u32) -> RiskLimits {
+        self.admin_limits
+            .get(&user_id)
+            .or_else(|| self.limits.get(&user_id))
+            .or_else(|| self.admin_limits.get(&0))
+            .copied()
+            .unwrap_or(self.default_limits)
+    }
+
+    /// Net position of `user_id` in `symbol` (negative = short).
+    pub fn position(&self, user_id: u32, symbol: &str) -> i64 {
+        self.positions
+            .get(&user_id)
+            .and_then(|positions| positions.get(symbol))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Number of open orders of `user_id`.
+    pub fn open_orders(&self, user_id: u32) -> usize {
+        self.open_orders.get(&user_id).map_or(0, HashMap::len)
+    }
+
+    /// Gross exposure of `user_id`: open positions at the last trade price
+    /// plus open orders at their price.
+    pub fn gross_exposure(&self, user_id: u32) -> u64 {
+        let positions = self.positions.get(&user_id).into_iter().flatten().map(|(symbol, &position)| {
+            position.unsigned_abs().saturating_mul(u64::from(self.last_price(symbol)))
+        });
+        let orders = self
+            .open_orders
+            .get(&user_id)
+            .into_iter()
+            .flatten()
+            .map(|(_, order)| u64::from(order.price) * u64::from(order.leaves_qty));
+        positions.chain(orders).fold(0, u64::saturating_add)
+    }
+
+    /// Why `msg` breaches its user's limits, if it does.
+    pub fn check(&self, msg: &NewOrder) -> Option<RejectReason> {
+        let limits = self.limits(msg.user_id);
+        let quantity = u64::from(msg.quantity);
+        let price = self.reference_price(msg);
+        let notional = u64::from(price) * quantity;
+
+        if exceeds(quantity, u64::from(limits.max_order_qty)) {
+            return Some(RejectReason::RiskOrderQuantity);
+        }
+        if exceeds_at(price, notional, limits.max_notional) {
+            return Some(RejectReason::RiskNotional);
+        }
+        if limits.max_open_orders > 0 && self.open_orders(msg.user_id) >= limits.max_open_orders as usize {
+            return Some(RejectReason::RiskOpenOrders);
+        }
+
+        if self.breaches_position(msg.user_id, &msg.symbol, msg.side, quantity, &limits) {
+            return Some(RejectReason::RiskPosition);
+        }
+
+        let exposure = self.gross_exposure(msg.user_id).saturating_add(notional);
+        if exceeds_at(price, exposure, limits.max_gross_exposure) {
+            return Some(RejectReason::RiskGrossExposure);
+        }
+        None
+    }
+
+    /// Why replacing `order` (resting, or queued until its symbol accepts
+    /// orders) as `replace` asks breaches its user's limits, if it does.
+    pub fn check_replace(&self, order: &Order, replace: &Replace) -> Option<RejectReason> {
+        let limits = self.limits(order.user_id);
+        let price = match replace.new_price.unwrap_or(order.price) {
+            0 if order.stop_price > 0 => order.stop_price,
+            0 => self.last_price(&order.symbol),
+            price => price,
+        };
+        let quantity = replace.new_quantity.unwrap_or(order.quantity);
+        let leaves_qty = u64::from(quantity.saturating_sub(order.quantity - order.remaining_qty));
+
+        if exceeds(u64::from(quantity), u64::from(limits.max_order_qty)) {
+            return Some(RejectReason::RiskOrderQuantity);
+        }
+        if exceeds_at(price, u64::from(price) * u64::from(quantity), limits.max_notional) {
+            return Some(RejectReason::RiskNotional);
+        }
+        if self.breaches_position(order.user_id, &order.symbol, order.side, leaves_qty, &limits) {
+            return Some(RejectReason::RiskPosition);
+        }
+
+        let current = self
+            .open_orders
+            .get(&order.user_id)
+            .and_then(|orders| orders.get(&order.user_order_id))
+            .map_or(0, |o| u64::from(o.price) * u64::from(o.leaves_qty));
+        let exposure = self
+            .gross_exposure(order.user_id)
+            .saturating_sub(current)
+            .saturating_add(u64::from(price) * leaves_qty);
+        if exceeds_at(price, exposure, limits.max_gross_exposure) {
+            return Some(RejectReason::RiskGrossExposure);
+        }
+        None
+    }
+
+    /// Whether `quantity` more filled on `side` would take the user's net
+    /// position in `symbol` past its limit (reducing it never does).
+    fn breaches_position(&self, user_id: u32, symbol: &str, side: Side, quantity: u64, limits: &RiskLimits) -> bool {
+        let position = self.position(user_id, symbol);
+        let after = match side {
+            Side::Buy => position.saturating_add(quantity as i64),
+            Side::Sell => position.saturating_sub(quantity as i64),
+        };
+        after.unsigned_abs() > position.unsigned_abs() && exceeds(after.unsigned_abs(), limits.max_position)
+    }
+
+    /// Follow the engine's outputs for `request`.
+    pub fn on_outputs(&mut self, request: &InputMessage, outputs: &[OutputMessage]) {
+        match request {
+            InputMessage::Flush(_) => self.open_orders.clear(),
+            InputMessage::SetRiskLimits(update) => {
+                self.admin_limits.insert(update.user_id, update.limits);
+            }
+            _ => {}
+        }
+        let new_order = match request {
+            InputMessage::NewOrder(o) => Some(o),
+            _ => None,
+        };
+
+        for out in outputs {
+            match out {
+                OutputMessage::Trade(t) => {
+                    self.last_prices.insert(t.symbol.clone(), t.price);
+                    let quantity = i64::from(t.quantity);
+                    *self.position_mut(t.user_id_buy, &t.symbol) += quantity;
+                    *self.position_mut(t.user_id_sell, &t.symbol) -= quantity;
+                }
+                OutputMessage::ReplaceAck(r) => {
+                    if let Some(order) = self
+                        .open_orders
+                        .get_mut(&r.user_id)
+                        .and_then(|orders| orders.get_mut(&r.user_order_id))
+                    {
+                        order.price = r.price;
+                    }
+                }
+                OutputMessage::ExecutionReport(report) => self.on_execution_report(report, new_order),
+                _ => {}
+            }
+        }
+    }
+
+    fn on_execution_report(&mut self, report: &ExecutionReport, new_order: Option<&NewOrder>) {
+        let orders = self.open_orders.entry(report.user_id).or_default();
+        if report.order_status.is_final() {
+            orders.remove(&report.user_order_id);
+        } else if let Some(order) = orders.get_mut(&report.user_order_id) {
+            order.leaves_qty = report.leaves_qty;
+        } else if let Some(o) =
+            new_order.filter(|o| (o.user_id, o.user_order_id) == (report.user_id, report.user_order_id))
+        {
+            orders.insert(
+                o.user_order_id,
+                OpenOrder {
+                    price: if o.price > 0 { o.price } else { o.stop_price },
+                    leaves_qty: report.leaves_qty,
+                },
+            );
+        }
+        if orders.is_empty() {
+            self.open_orders.remove(&report.user_id);
+        }
+    }
+
+    fn position_mut(&mut self, user_id: u32, symbol: &str) -> &mut i64 {
+        self.positions
+            .entry(user_id)
+            .or_default()
+            .entry(symbol.to_string())
+            .or_insert(0)
+    }
+
+    fn last_price(&self, symbol: &str) -> u32 {
+        self.last_prices.get(symbol).copied().unwrap_or(0)
+    }
+
+    fn reference_price(&self, msg: &NewOrder) -> u32 {
+        if msg.price > 0 {
+            msg.price
+        } else if msg.stop_price > 0 {
+            msg.stop_price
+        } else {
+            self.last_price(&msg.symbol)
+        }
+    }
+
+    /// Write positions, open orders, last prices and the limits set by
+    /// `SetRiskLimits` as of journal `seq`, one per line:
+    ///
+    /// ```text
+    /// S, seq
+    /// P, userId, symbol, netPosition
+    /// O, userId, userOrderId, price, leavesQty
+    /// L, symbol, lastPrice
+    /// R, userId, maxOrderQty, maxNotional, maxOpenOrders, maxPosition, maxGrossExposure
+    /// ```
+    pub fn write_state<W: Write>(&self, seq: u64, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "S, {}", seq)?;
+
+        let mut positions: Vec<_> = self
+            .positions
+            .iter()
+            .flat_map(|(&user_id, positions)| positions.iter().map(move |(symbol, &p)| (user_id, symbol, p)))
+            .filter(|&(_, _, position)| position != 0)
+            .collect();
+        positions.sort();
+        for (user_id, symbol, position) in positions {
+            writeln!(writer, "P, {}, {}, {}", user_id, symbol, position)?;
+        }
+
+        let mut orders: Vec<_> = self
+            .open_orders
+            .iter()
+            .flat_map(|(&user_id, orders)| orders.iter().map(move |(&id, order)| ((user_id, id), order)))
+            .collect();
+        orders.sort_by_key(|&(key, _)| key);
+        for ((user_id, user_order_id), o) in orders {
+            writeln!(
+                writer,
+                "O, {}, {}, {}, {}",
+                user_id,
+                user_order_id,
+                o.price,
+                o.leaves_qty
+            )?;
+        }
+
+        let mut prices: Vec<_> = self.last_prices.iter().collect();
+        prices.sort();
+        for (symbol, price) in prices {
+            writeln!(writer, "L, {}, {}", symbol, price)?;
+        }
+
+        let mut limits: Vec<_> = self.admin_limits.iter().collect();
+        limits.sort_by_key(|&(&user_id, _)| user_id);
+        for (user_id, l) in limits {
+            writeln!(
+                writer,
+                "R, {}, {}, {}, {}, {}, {}",
+                user_id,
+                l.max_order_qty,
+                l.max_notional,
+                l.max_open_orders,
+                l.max_position,
+                l.max_gross_exposure
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Replace positions, open orders, last prices and the limits set by
+    /// `SetRiskLimits` with what [`write_state`](Self::write_state) wrote,
+    /// and return its journal seq. Limits from the file are kept.
+    pub fn restore_state<R: BufRead>(&mut self, reader: R) -> io::Result<u64> {
+        let mut seq = None;
+        self.open_orders.clear();
+        self.positions.clear();
+        self.last_prices.clear();
+        self.admin_limits.clear();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let fields: Vec<_> = line.split(',').map(str::trim).collect();
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid risk state on line {}: '{}'", index + 1, line),
+                )
+            };
+            match fields.as_slice() {
+                [""] => {}
+                ["S", value] if seq.is_none() => {
+                    seq = Some(value.parse::<u64>().map_err(|_| invalid())?);
+                }
+                ["P", user_id, symbol, position] => {
+                    let user_id = user_id.parse::<u32>().map_err(|_| invalid())?;
+                    *self.position_mut(user_id, symbol) = position.parse::<i64>().map_err(|_| invalid())?;
+                }
+                ["O", user_id, user_order_id, price, leaves_qty] => {
+                    let order = OpenOrder {
+                        price: price.parse().map_err(|_| invalid())?,
+                        leaves_qty: leaves_qty.parse().map_err(|_| invalid())?,
+                    };
+                    self.open_orders
+                        .entry(user_id.parse().map_err(|_| invalid())?)
+                        .or_default()
+                        .insert(user_order_id.parse().map_err(|_| invalid())?, order);
+                }
+                ["L", symbol, price] => {
+                    self.last_prices
+                        .insert(symbol.to_string(), price.parse().map_err(|_| invalid())?);
+                }
+                ["R", user_id, max_order_qty, max_notional, max_open_orders, max_position, max_gross_exposure] => {
+                    let limits = RiskLimits {
+                        max_order_qty: max_order_qty.parse().map_err(|_| invalid())?,
+                        max_notional: max_notional.parse().map_err(|_| invalid())?,
+                        max_open_orders: max_open_orders.parse().map_err(|_| invalid())?,
+                        max_position: max_position.parse().map_err(|_| invalid())?,
+                        max_gross_exposure: max_gross_exposure.parse().map_err(|_| invalid())?,
+                    };
+                    self.admin_limits.insert(user_id.parse().map_err(|_| invalid())?, limits);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        seq.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "risk state without a journal seq"))
+    }
+}
+
+fn exceeds(value: u64, limit: u64) -> bool {
+    limit > 0 && value > limit
+}
+
+/// Like [`exceeds`] for a value of an order at `price`: without a price
+/// (`0`) the value is unknown, so any limit counts as exceeded.
+fn exceeds_at(price: u32, value: u64, limit: u64) -> bool {
+    limit > 0 && (price == 0 || value > limit)
+}
@@ -0,0 +1,278 @@
+// crates/engine-server/src/routing.rs
+
+//! Decides which clients receive each engine output.
+//!
+//! Execution reports are private:
+//! - `Reject` goes back to the client that sent the request, except for
+//!   a queued order rejected when it is released (by an admin request,
+//!   or by the end of a volatility interruption before another request),
+//!   which goes to the order's owner.
+//! - `Ack` / `CancelAck` / `ReplaceAck` / `Expired` / `Triggered` /
+//!   `SelfTradeCancel` / `ExecutionReport` go to the client that entered
+//!   the order.
+//! - Each side of a `Trade` goes to that order's owner, with the
+//!   counterparty ids zeroed.
+//!
+//! An order whose owner is unknown (e.g. one restored from the journal
+//! after a restart) falls back to the requester only if the request acts
+//! for that order's user; otherwise its private reports are dropped.
+//!
+//! Market data is public:
+//! - Every `Trade` is also broadcast as an anonymized `PublicTrade`.
+//! - `TopOfBook` / `DepthUpdate` changes are broadcast; answers to an
+//!   explicit `QueryTopOfBook` / `QueryDepth` go only to the requester.
+//! - `InstrumentDefinition` (the answer to a `QueryInstrument`) goes to
+//!   the requester, as does the `MassCancelAck` summing up a `MassCancel`.
+//! - `IndicativeUncross`, `TradingStatus` and `VolatilityInterruption`
+//!   are always broadcast.
+//!
+//! A `UserTradingStatus` goes to the requester and to every connected
+//! client that has entered orders for that user, or to everyone when it
+//! is about every user.
+
+use std::collections::{HashMap, HashSet};
+
+use engine_core::{InputMessage, MatchingEngine, OutputMessage, PublicTrade, Trade};
+
+use crate::types::ClientId;
+
+/// Where a single output should be delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    Client(ClientId),
+    All,
+}
+
+/// Tracks which client owns each live order, and which clients trade
+/// for each user.
+#[derive(Debug, Default)]
+pub struct Router {
+    owners: HashMap<(u32, u32), ClientId>,
+    sessions: HashMap<u32, HashSet<ClientId>>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turn the outputs of one request into `(destination, message)`
+    /// deliveries, preserving output order.
+    ///
+    /// Must be called right after `engine.process_message(request)` so
+    /// that filled / cancelled orders can be forgotten.
+    pub fn route(
+        &mut self,
+        requester: ClientId,
+        request: &InputMessage,
+        outputs: Vec<OutputMessage>,
+        engine: &MatchingEngine,
+    ) -> Vec<(Destination, OutputMessage)> {
+        let is_query = matches!(
+            request,
+            InputMessage::QueryTopOfBook(_) | InputMessage::QueryDepth(_) | InputMessage::QueryInstrument(_)
+        );
+        let is_admin = matches!(
+            request,
+            InputMessage::Auction(_)
+                | InputMessage::SetTradingState(_)
+                | InputMessage::KillSwitch(_)
+                | InputMessage::EnableTrading(_)
+        );
+        // Outputs about other orders can come first: a volatility
+        // interruption ending releases queued orders before the request.
+        let request_key = match request {
+            InputMessage::NewOrder(o) => Some((o.user_id, o.user_order_id)),
+            InputMessage::Cancel(c) => Some((c.user_id, c.user_order_id)),
+            InputMessage::Replace(r) => Some((r.user_id, r.user_order_id)),
+            _ => None,
+        };
+        let acting_user = match request {
+            InputMessage::MassCancel(m) => Some(m.user_id),
+            _ => request_key.map(|(user_id, _)| user_id),
+        };
+        let owner = |router: &Self, key: (u32, u32)| router.owner_of(key, requester, acting_user);
+        let mut touched = Vec::new();
+        let mut deliveries = Vec::with_capacity(outputs.len());
+
+        for out in outputs {
+            match out {
+                OutputMessage::Reject(ref r)
+                    if is_admin || request_key.is_some_and(|k| k != (r.user_id, r.user_order_id)) =>
+                {
+                    let key = (r.user_id, r.user_order_id);
+                    touched.push(key);
+                    if let Some(client) = owner(self, key) {
+                        deliveries.push((Destination::Client(client), out));
+                    }
+                }
+                OutputMessage::Reject(_)
+                | OutputMessage::InstrumentDefinition(_)
+                | OutputMessage::MassCancelAck(_)
+                | OutputMessage::SessionStatus(_) => {
+                    deliveries.push((Destination::Client(requester), out));
+                }
+                OutputMessage::Ack(ref a) => {
+                    let key = (a.user_id, a.user_order_id);
+                    self.owners.insert(key, requester);
+                    self.sessions.entry(a.user_id).or_default().insert(requester);
+                    touched.push(key);
+                    deliveries.push((Destination::Client(requester), out));
+                }
+                OutputMessage::CancelAck(ref c) => {
+                    let key = (c.user_id, c.user_order_id);
+                    touched.push(key);
+                    if let Some(client) = owner(self, key) {
+                        deliveries.push((Destination::Client(client), out));
+                    }
+                }
+                OutputMessage::ReplaceAck(ref r) => {
+                    let key = (r.user_id, r.user_order_id);
+                    touched.push(key);
+                    if let Some(client) = owner(self, key) {
+                        deliveries.push((Destination::Client(client), out));
+                    }
+                }
+                OutputMessage::Expired(ref e) => {
+                    let key = (e.user_id, e.user_order_id);
+                    touched.push(key);
+                    if let Some(client) = owner(self, key) {
+                        deliveries.push((Destination::Client(client), out));
+                    }
+                }
+                OutputMessage::Triggered(ref t) => {
+                    let key = (t.user_id, t.user_order_id);
+                    touched.push(key);
+                    if let Some(client) = owner(self, key) {
+                        deliveries.push((Destination::Client(client), out));
+                    }
+                }
+                OutputMessage::SelfTradeCancel(ref c) => {
+                    let key = (c.user_id, c.user_order_id);
+                    touched.push(key);
+                    if let Some(client) = owner(self, key) {
+                        deliveries.push((Destination::Client(client), out));
+                    }
+                }
+                OutputMessage::ExecutionReport(ref e) => {
+                    let key = (e.user_id, e.user_order_id);
+                    touched.push(key);
+                    if let Some(client) = owner(self, key) {
+                        deliveries.push((Destination::Client(client), out));
+                    }
+                }
+                OutputMessage::Trade(t) => {
+                    let buy_key = (t.user_id_buy, t.user_order_id_buy);
+                    let sell_key = (t.user_id_sell, t.user_order_id_sell);
+                    touched.push(buy_key);
+                    touched.push(sell_key);
+                    let buyer = owner(self, buy_key);
+                    let seller = owner(self, sell_key);
+                    route_trade(buyer, seller, t, &mut deliveries);
+                }
+                OutputMessage::UserTradingStatus(s) if s.user_id != 0 => {
+                    let mut targets: Vec<ClientId> = self
+                        .sessions
+                        .get(&s.user_id)
+                        .into_iter()
+                        .flatten()
+                        .copied()
+                        .filter(|&client| client != requester)
+                        .collect();
+                    targets.push(requester);
+                    for client in targets {
+                        deliveries.push((Destination::Client(client), out.clone()));
+                    }
+                }
+                OutputMessage::TopOfBook(_) | OutputMessage::DepthUpdate(_) if is_query => {
+                    deliveries.push((Destination::Client(requester), out));
+                }
+                OutputMessage::PublicTrade(_)
+                | OutputMessage::TopOfBook(_)
+                | OutputMessage::DepthUpdate(_)
+                | OutputMessage::IndicativeUncross(_)
+                | OutputMessage::TradingStatus(_)
+                | OutputMessage::VolatilityInterruption(_)
+                | OutputMessage::UserTradingStatus(_) => {
+                    deliveries.push((Destination::All, out));
+                }
+            }
+        }
+
+        // A flush forgets every order, but only once its cancels are
+        // routed to their owners.
+        if matches!(request, InputMessage::Flush(_)) {
+            self.owners.clear();
+        }
+        for key in touched {
+            if !engine.has_order(key.0, key.1) {
+                self.owners.remove(&key);
+            }
+        }
+
+        deliveries
+    }
+
+    /// Forget `client` once its session has ended: it gets no more
+    /// `UserTradingStatus`. Its live orders keep their owner.
+    pub fn disconnect(&mut self, client: ClientId) {
+        self.sessions.retain(|_, clients| {
+            clients.remove(&client);
+            !clients.is_empty()
+        });
+    }
+
+    /// Orders entered by `client` that may still be live, sorted.
+    pub fn orders_of(&self, client: ClientId) -> Vec<(u32, u32)> {
+        let mut orders: Vec<_> = self
+            .owners
+            .iter()
+            .filter(|&(_, &owner)| owner == client)
+            .map(|(&key, _)| key)
+            .collect();
+        orders.sort_unstable();
+        orders
+    }
+
+    /// The client that entered `key`, or the requester if the request
+    /// acts for the order's user.
+    fn owner_of(&self, key: (u32, u32), requester: ClientId, acting_user: Option<u32>) -> Option<ClientId> {
+        self.owners
+            .get(&key)
+            .copied()
+            .or_else(|| (acting_user == Some(key.0)).then_some(requester))
+    }
+}
+
+/// Each side of `trade` to its owner (skipping an unknown one), then the
+/// anonymized print to everyone.
+fn route_trade(
+    buyer: Option<ClientId>,
+    seller: Option<ClientId>,
+    trade: Trade,
+    deliveries: &mut Vec<(Destination, OutputMessage)>,
+) {
+    let public = PublicTrade::from_trade(&trade);
+
+    match (buyer, seller) {
+        (Some(b), Some(s)) if b == s => {
+            deliveries.push((Destination::Client(b), OutputMessage::Trade(trade)));
+        }
+        _ => {
+            if let Some(buyer) = buyer {
+                let mut buy_side = trade.clone();
+                buy_side.user_id_sell = 0;
+                buy_side.user_order_id_sell = 0;
+                deliveries.push((Destination::Client(buyer), OutputMessage::Trade(buy_side)));
+            }
+            if let Some(seller) = seller {
+                let mut sell_side = trade;
+                sell_side.user_id_buy = 0;
+                sell_side.user_order_id_buy = 0;
+                deliveries.push((Destination::Client(seller), OutputMessage::Trade(sell_side)));
+            }
+        }
+    }
+
+    deliveries.push((Destination::All, OutputMessage::PublicTrade(public)));
+}
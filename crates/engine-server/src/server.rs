@@ -1,6 +1,8 @@
 //! TCP listener and top-level server wiring.
 //!
 //! This module:
+//! - Restores the latest snapshot and replays the write-ahead journal
+//!   after it (if configured).
 //! - Binds to a TCP address/port (with port bumping on AddrInUse).
 //! - Accepts new connections and assigns `ClientId`s.
 //! - Spawns:
@@ -16,7 +18,8 @@ use tokio::net::TcpListener;
 use tokio::sync::mpsc;
 use tokio::time;
 
-use crate::config::Config;
+use crate::auth::{Authenticator, Credentials};
+use crate::config::{self, Config};
 use crate::engine_task;
 use crate::types::{
     ClientId, ClientRegistry, EngineRx, EngineTx, OutboundRx, OutboundTx,
@@ -50,7 +53,7 @@ async fn bind_with_port_bump(bind_addr: String, mut port: u16) -> std::io::Resul
                     attempts,
                     port + 1
                 );
-                port = port + 1;
+                port += 1;
             }
             Err(e) => return Err(e),
         }
@@ -59,6 +62,9 @@ async fn bind_with_port_bump(bind_addr: String, mut port: u16) -> std::io::Resul
 
 /// Run the TCP server with the given configuration.
 pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    // Rebuild state from the journal before accepting anyone.
+    let (engine, clock, risk, persistence) = engine_task::recover_engine(&config)?;
+
     // Try to bind listener with port bumping.
     let (listener, bind_addr, bound_port, attempts) =
         bind_with_port_bump(config.bind_addr.clone(), config.port).await?;
@@ -66,6 +72,12 @@ pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
     // Shared registry of clients → outbound channels.
     let clients: ClientRegistry = Arc::new(tokio::sync::RwLock::new(Default::default()));
 
+    // Handed to every session, which authenticates logons and admin
+    // commands.
+    let credentials = config.credentials_path.as_deref().map(config::load_credentials).transpose()?;
+    let credential_count = credentials.as_ref().map(Credentials::len);
+    let auth = Arc::new(Authenticator::new(config.admin_token.clone(), credentials));
+
     // Channel from clients → engine task.
     let (engine_tx, engine_rx): (EngineTx, EngineRx) = mpsc::unbounded_channel();
 
@@ -73,7 +85,8 @@ pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
     {
         let clients_clone = clients.clone();
         tokio::spawn(async move {
-            engine_task::run_engine_loop(engine_rx, clients_clone).await;
+            engine_task::run_engine_loop(engine_rx, clients_clone, engine, clock, risk, persistence)
+                .await;
         });
     }
 
@@ -84,12 +97,29 @@ pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
     eprintln!("Bind address: {}", bind_addr);
     eprintln!("TCP Port:     {}", bound_port);
     eprintln!("Max clients:  {}", config.max_clients);
+    match &config.journal_path {
+        Some(path) => eprintln!("Journal:      {} (fsync: {:?})", path, config.journal_fsync),
+        None => eprintln!("Journal:      disabled"),
+    }
+    if let (Some(path), Some(_)) = (&config.snapshot_path, &config.journal_path) {
+        eprintln!("Snapshot:     {} (every {} records)", path, config.snapshot_every);
+    }
+    match (&config.credentials_path, credential_count) {
+        (Some(path), Some(count)) => eprintln!("Credentials:  {} ({} accounts, logon required)", path, count),
+        _ => eprintln!("Credentials:  none (any session may act for any user)"),
+    }
     if attempts > 1 {
         eprintln!(
             "Note: bound after {} attempts (port bumped due to AddrInUse).",
             attempts
         );
     }
+    if credential_count.is_none() {
+        eprintln!(
+            "Warning: no credentials file, so any session may trade for any user id \
+             (pass --credentials or set ENGINE_CREDENTIALS to require logons)."
+        );
+    }
     eprintln!("==============================================================");
     eprintln!("Queue Configuration:");
     eprintln!("  Engine request queue:  Tokio mpsc::unbounded_channel()");
@@ -144,14 +174,17 @@ pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
 
                         let clients_clone = clients.clone();
                         let engine_tx_clone = engine_tx.clone();
+                        let auth = auth.clone();
 
                         tokio::spawn(async move {
                             if let Err(e) = crate::client::run_client(
                                 client_id,
                                 stream,
                                 engine_tx_clone,
+                                out_tx,
                                 out_rx,
                                 clients_clone,
+                                auth,
                             )
                             .await
                             {
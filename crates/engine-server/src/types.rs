@@ -31,9 +31,14 @@ pub type ClientRegistry = Arc<RwLock<HashMap<ClientId, OutboundTx>>>;
 
 /// Message flowing from a client task into the central engine task.
 #[derive(Debug)]
-pub struct EngineRequest {
-    pub client_id: ClientId,
-    pub msg: InputMessage,
+pub enum EngineRequest {
+    /// An input sent by the client.
+    Input { client_id: ClientId, msg: InputMessage },
+
+    /// The client's session ended. If it logged on with
+    /// cancel-on-disconnect, cancel the orders it entered that are still
+    /// live.
+    Disconnected { client_id: ClientId, cancel_orders: bool },
 }
 
 /// Channel from clients → engine task.
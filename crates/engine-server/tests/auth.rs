@@ -0,0 +1,59 @@
+// crates/engine-server/tests/auth.rs
+use engine_core::Logon;
+use engine_server::auth::{hash_secret, Authenticator, UserIds};
+use engine_server::config::parse_credentials;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn logon(username: &str, secret: &str) -> Logon {
+    Logon {
+        username: username.to_string(),
+        secret: secret.to_string(),
+        ..Logon::default()
+    }
+}
+
+#[test]
+fn logon_binds_the_session_to_the_accounts_user_ids() {
+    let file = format!(
+        "# username, salt, sha256(salt + secret), user ids\n\
+         alice, s1, {}, 1|2\n\
+         ops, s2, {}, *\n",
+        hex(&hash_secret("s1", "alice-secret")),
+        hex(&hash_secret("s2", "ops-secret")),
+    );
+    let credentials = parse_credentials(&file).unwrap();
+    assert_eq!(credentials.len(), 2);
+
+    let auth = Authenticator::new(Some("admin".to_string()), Some(credentials));
+    assert!(auth.requires_logon());
+
+    let alice = auth.logon(&logon("alice", "alice-secret")).unwrap();
+    assert!(alice.contains(1) && alice.contains(2));
+    assert!(!alice.contains(3));
+    assert_eq!(auth.logon(&logon("ops", "ops-secret")), Some(UserIds::Any));
+
+    // A wrong secret, or one that belongs to someone else, gets nothing.
+    assert_eq!(auth.logon(&logon("alice", "ops-secret")), None);
+    assert_eq!(auth.logon(&logon("mallory", "alice-secret")), None);
+
+    assert!(auth.is_admin("admin"));
+    assert!(!auth.is_admin("admin "));
+}
+
+#[test]
+fn without_a_credentials_file_any_logon_passes() {
+    let auth = Authenticator::new(None, None);
+    assert!(!auth.requires_logon());
+    assert_eq!(auth.logon(&Logon::default()), Some(UserIds::Any));
+    assert!(!auth.is_admin(""));
+}
+
+#[test]
+fn malformed_credentials_are_reported() {
+    assert!(parse_credentials("alice, s1, not-hex, 1").is_err());
+    assert!(parse_credentials(&format!("alice, s1, {}, x", hex(&[0; 32]))).is_err());
+    assert!(parse_credentials("alice, s1").is_err());
+}
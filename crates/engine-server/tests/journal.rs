@@ -0,0 +1,163 @@
+// crates/engine-server/tests/journal.rs
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use engine_core::{
+    Cancel, FlushCommand, InputMessage, MatchingEngine, NewOrder, OrderFlags, SelfTradePrevention,
+    SetTradingState, Side, TimeInForce, TradingState,
+};
+use engine_server::journal::{read_journal, FsyncPolicy, Journal};
+
+fn temp_journal(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}.journal", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn new_order(user_id: u32, user_order_id: u32, price: u32, side: Side) -> InputMessage {
+    InputMessage::NewOrder(NewOrder {
+        user_id,
+        symbol: "IBM".to_string(),
+        price,
+        quantity: 100,
+        side,
+        user_order_id,
+        time_in_force: TimeInForce::Day,
+        stop_price: 0,
+        display_qty: 0,
+        flags: OrderFlags::NONE,
+        self_trade_prevention: SelfTradePrevention::None,
+    })
+}
+
+#[test]
+fn replayed_journal_rebuilds_books_and_survives_a_torn_tail() {
+    let path = temp_journal("replay");
+    let inputs = vec![
+        new_order(1, 1, 10, Side::Buy),
+        new_order(1, 2, 9, Side::Buy),
+        new_order(2, 1, 12, Side::Sell),
+        InputMessage::Cancel(Cancel {
+            user_id: 1,
+            user_order_id: 2,
+        }),
+    ];
+
+    let mut live = MatchingEngine::new();
+    {
+        let (mut journal, records) = Journal::open(&path, FsyncPolicy::Every(2)).unwrap();
+        assert!(records.is_empty());
+        for (i, msg) in inputs.iter().enumerate() {
+            journal.append(msg, 1_000 * (i as u64 + 1)).unwrap();
+            live.process_message(msg.clone());
+        }
+        assert_eq!(journal.last_seq(), 4);
+    }
+
+    // Simulate a crash halfway through writing a fifth record.
+    OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(&[0, 0, 0, 40, 0, 0])
+        .unwrap();
+
+    let records = read_journal(&path).unwrap();
+    assert_eq!(records.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    assert_eq!(
+        records.iter().map(|r| r.timestamp_ns).collect::<Vec<_>>(),
+        vec![1_000, 2_000, 3_000, 4_000]
+    );
+    assert_eq!(records.iter().map(|r| r.msg.clone()).collect::<Vec<_>>(), inputs);
+
+    let mut replayed = MatchingEngine::new();
+    for record in records {
+        replayed.process_message(record.msg);
+    }
+    let snapshot = |e: &MatchingEngine| e.get_book("IBM").unwrap().top_of_book_snapshot();
+    assert_eq!(snapshot(&replayed), snapshot(&live));
+    assert!(replayed.has_order(1, 1) && !replayed.has_order(1, 2));
+
+    // Reopening drops the torn tail and continues the sequence.
+    let (mut journal, records) = Journal::open(&path, FsyncPolicy::Always).unwrap();
+    assert_eq!(records.len(), 4);
+    assert_eq!(journal.append(&InputMessage::Flush(FlushCommand::default()), 5_000).unwrap(), 5);
+    assert_eq!(read_journal(&path).unwrap().len(), 5);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// A journal record around an already encoded `frame`.
+fn record(seq: u64, timestamp_ns: u64, frame: &[u8]) -> Vec<u8> {
+    let mut record = Vec::new();
+    record.extend_from_slice(&(16 + frame.len() as u32).to_be_bytes());
+    record.extend_from_slice(&seq.to_be_bytes());
+    record.extend_from_slice(&timestamp_ns.to_be_bytes());
+    record.extend_from_slice(frame);
+    record
+}
+
+#[test]
+fn journal_written_by_an_older_protocol_version_still_replays() {
+    let path = temp_journal("old-version");
+
+    // Protocol 4, the first journaled one: no stop price, display
+    // quantity, flags or self-trade prevention in `NewOrder`.
+    let mut v4_buy = vec![0, 4, 0, 0];
+    for field in [1u32, 1, 10, 100] {
+        v4_buy.extend_from_slice(&field.to_be_bytes());
+    }
+    v4_buy.extend_from_slice(&[0, 0, 3]);
+    v4_buy.extend_from_slice(b"IBM");
+
+    // Protocol 9: today's `NewOrder` layout under an older version byte.
+    let mut v9_sell = vec![0, 9, 0, 0];
+    for field in [2u32, 1, 12, 100] {
+        v9_sell.extend_from_slice(&field.to_be_bytes());
+    }
+    v9_sell.extend_from_slice(&[1, 0]);
+    v9_sell.extend_from_slice(&[0; 10]);
+    v9_sell.extend_from_slice(&[3]);
+    v9_sell.extend_from_slice(b"IBM");
+
+    let v4_cancel = [1, 4, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1];
+
+    // Protocol 21: admin messages other than `SetRiskLimits` had no token.
+    let v21_state = [7, 21, 0, 0, 2, 3, b'I', b'B', b'M'];
+
+    let mut file = Vec::new();
+    file.extend(record(1, 1_000, &v4_buy));
+    file.extend(record(2, 2_000, &v9_sell));
+    file.extend(record(3, 3_000, &v4_cancel));
+    file.extend(record(4, 4_000, &v21_state));
+    std::fs::write(&path, file).unwrap();
+
+    let (mut journal, records) = Journal::open(&path, FsyncPolicy::Always).unwrap();
+    let expected = vec![
+        new_order(1, 1, 10, Side::Buy),
+        new_order(2, 1, 12, Side::Sell),
+        InputMessage::Cancel(Cancel {
+            user_id: 2,
+            user_order_id: 1,
+        }),
+        InputMessage::SetTradingState(SetTradingState {
+            symbol: "IBM".to_string(),
+            state: TradingState::Continuous,
+            admin_token: String::new(),
+        }),
+    ];
+    assert_eq!(records.iter().map(|r| r.msg.clone()).collect::<Vec<_>>(), expected);
+
+    let mut replayed = MatchingEngine::new();
+    for record in records {
+        replayed.process_message(record.msg);
+    }
+    assert!(replayed.has_order(1, 1) && !replayed.has_order(2, 1));
+
+    // New records follow in the current version.
+    assert_eq!(journal.append(&new_order(1, 2, 9, Side::Buy), 5_000).unwrap(), 5);
+    assert_eq!(read_journal(&path).unwrap().len(), 5);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,216 @@
+// crates/engine-server/tests/risk.rs
+use engine_core::order::Order;
+use engine_core::{
+    InputMessage, MatchingEngine, NewOrder, OrderFlags, RejectReason, Replace, RiskLimits,
+    SelfTradePrevention, SetRiskLimits, SetTradingState, Side, TimeInForce, TradingState,
+};
+use engine_server::config::parse_risk_limits;
+use engine_server::risk::RiskGateway;
+
+fn order(user_id: u32, user_order_id: u32, price: u32, quantity: u32, side: Side) -> NewOrder {
+    NewOrder {
+        user_id,
+        symbol: "IBM".to_string(),
+        price,
+        quantity,
+        side,
+        user_order_id,
+        time_in_force: TimeInForce::Day,
+        stop_price: 0,
+        display_qty: 0,
+        flags: OrderFlags::NONE,
+        self_trade_prevention: SelfTradePrevention::None,
+    }
+}
+
+/// Check `order` like the engine task does, and let it through if it passes.
+fn submit(engine: &mut MatchingEngine, risk: &mut RiskGateway, order: NewOrder) -> Option<RejectReason> {
+    if let Some(reason) = risk.check(&order) {
+        return Some(reason);
+    }
+    let msg = InputMessage::NewOrder(order);
+    let outputs = engine.process_message(msg.clone());
+    risk.on_outputs(&msg, &outputs);
+    None
+}
+
+/// Check `replace` like the engine task does, and let it through if it passes.
+fn replace(engine: &mut MatchingEngine, risk: &mut RiskGateway, replace: Replace) -> Option<RejectReason> {
+    let queued = engine
+        .get_queued_order(replace.user_id, replace.user_order_id)
+        .map(|o| Order::from_new_order(o, 0));
+    let order = engine.get_order(replace.user_id, replace.user_order_id).or(queued.as_ref())?;
+    if let Some(reason) = risk.check_replace(order, &replace) {
+        return Some(reason);
+    }
+    let msg = InputMessage::Replace(replace);
+    let outputs = engine.process_message(msg.clone());
+    risk.on_outputs(&msg, &outputs);
+    None
+}
+
+fn replace_to(user_id: u32, user_order_id: u32, new_price: Option<u32>, new_quantity: Option<u32>) -> Replace {
+    Replace {
+        user_id,
+        user_order_id,
+        new_price,
+        new_quantity,
+    }
+}
+
+#[test]
+fn risk_gateway_enforces_limits_against_open_orders_and_positions() {
+    let limits = parse_risk_limits(
+        "# user, qty, notional, open orders, position, gross exposure\n\
+         *, 0, 0, 0, 0, 0\n\
+         1, 100, 5000, 2, 150, 0\n\
+         2, 0, 0, 0, 0, 3000\n",
+    )
+    .unwrap();
+    let mut risk = RiskGateway::new();
+    for update in &limits {
+        risk.set_limits(update);
+    }
+    let mut engine = MatchingEngine::new();
+    engine.set_execution_reports(true);
+
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 1, 10, 101, Side::Buy)), Some(RejectReason::RiskOrderQuantity));
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 1, 60, 100, Side::Buy)), Some(RejectReason::RiskNotional));
+
+    // Two open orders is the max; a fill frees a slot and builds a position.
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 1, 10, 100, Side::Buy)), None);
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 2, 9, 50, Side::Buy)), None);
+    assert_eq!(risk.open_orders(1), 2);
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 3, 8, 10, Side::Buy)), Some(RejectReason::RiskOpenOrders));
+    assert_eq!(submit(&mut engine, &mut risk, order(3, 1, 10, 100, Side::Sell)), None);
+    assert_eq!(risk.open_orders(1), 1);
+    assert_eq!(risk.position(1, "IBM"), 100);
+    assert_eq!(risk.position(3, "IBM"), -100);
+
+    // 100 long: another 60 would breach the position limit, selling never does.
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 3, 8, 60, Side::Buy)), Some(RejectReason::RiskPosition));
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 3, 11, 100, Side::Sell)), None);
+
+    // Gross exposure: the resting order counts at its price.
+    assert_eq!(submit(&mut engine, &mut risk, order(2, 1, 9, 200, Side::Buy)), None);
+    assert_eq!(risk.gross_exposure(2), 1800);
+    assert_eq!(submit(&mut engine, &mut risk, order(2, 2, 9, 150, Side::Buy)), Some(RejectReason::RiskGrossExposure));
+
+    // The state survives a write / restore.
+    let mut state = Vec::new();
+    risk.write_state(42, &mut state).unwrap();
+    let mut restored = RiskGateway::new();
+    assert_eq!(restored.restore_state(&state[..]).unwrap(), 42);
+    assert_eq!(restored.position(1, "IBM"), 100);
+    assert_eq!(restored.open_orders(1), 2);
+    assert_eq!(restored.gross_exposure(2), 1800);
+}
+
+#[test]
+fn risk_gateway_checks_a_replace_at_its_new_quantity_and_price() {
+    let limits = parse_risk_limits(
+        "1, 100, 5000, 0, 0, 0\n\
+         2, 0, 0, 0, 0, 3000\n",
+    )
+    .unwrap();
+    let mut risk = RiskGateway::new();
+    for update in &limits {
+        risk.set_limits(update);
+    }
+    let mut engine = MatchingEngine::new();
+    engine.set_execution_reports(true);
+
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 1, 10, 50, Side::Buy)), None);
+    assert_eq!(replace(&mut engine, &mut risk, replace_to(1, 1, None, Some(150))), Some(RejectReason::RiskOrderQuantity));
+    assert_eq!(replace(&mut engine, &mut risk, replace_to(1, 1, Some(60), Some(100))), Some(RejectReason::RiskNotional));
+    assert_eq!(engine.get_order(1, 1).map(|o| (o.price, o.quantity)), Some((10, 50)));
+    assert_eq!(replace(&mut engine, &mut risk, replace_to(1, 1, Some(50), Some(100))), None);
+    assert_eq!(engine.get_order(1, 1).map(|o| (o.price, o.quantity)), Some((50, 100)));
+
+    // The order's own exposure is replaced, not counted twice.
+    assert_eq!(submit(&mut engine, &mut risk, order(2, 1, 9, 200, Side::Buy)), None);
+    assert_eq!(replace(&mut engine, &mut risk, replace_to(2, 1, None, Some(300))), None);
+    assert_eq!(risk.gross_exposure(2), 2700);
+    assert_eq!(replace(&mut engine, &mut risk, replace_to(2, 1, None, Some(400))), Some(RejectReason::RiskGrossExposure));
+    assert_eq!(risk.gross_exposure(2), 2700);
+}
+
+#[test]
+fn limit_changes_apply_over_the_limits_file_and_survive_a_restore() {
+    let file = parse_risk_limits("*, 100, 0, 0, 0, 0\n1, 50, 0, 0, 0, 0\n").unwrap();
+    let mut risk = RiskGateway::new();
+    for update in &file {
+        risk.set_limits(update);
+    }
+    let mut engine = MatchingEngine::new();
+
+    let update = InputMessage::SetRiskLimits(SetRiskLimits {
+        user_id: 1,
+        limits: RiskLimits {
+            max_order_qty: 10,
+            ..RiskLimits::default()
+        },
+        admin_token: String::new(),
+    });
+    let outputs = engine.process_message(update.clone());
+    risk.on_outputs(&update, &outputs);
+    assert_eq!(risk.limits(1).max_order_qty, 10);
+    assert_eq!(risk.limits(2).max_order_qty, 100);
+
+    // A restart restores the state, then reads the file again: the change
+    // still wins over the file.
+    let mut state = Vec::new();
+    risk.write_state(7, &mut state).unwrap();
+    let mut restored = RiskGateway::new();
+    assert_eq!(restored.restore_state(&state[..]).unwrap(), 7);
+    for update in &file {
+        restored.set_limits(update);
+    }
+    assert_eq!(restored.limits(1).max_order_qty, 10);
+    assert_eq!(restored.limits(2).max_order_qty, 100);
+}
+
+#[test]
+fn market_order_before_the_first_trade_breaches_notional_and_exposure_limits() {
+    let limits = parse_risk_limits(
+        "1, 0, 5000, 0, 0, 0\n\
+         2, 0, 0, 0, 0, 5000\n\
+         3, 0, 0, 0, 0, 0\n",
+    )
+    .unwrap();
+    let mut risk = RiskGateway::new();
+    for update in &limits {
+        risk.set_limits(update);
+    }
+    let mut engine = MatchingEngine::new();
+    engine.set_execution_reports(true);
+
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 1, 0, 10, Side::Buy)), Some(RejectReason::RiskNotional));
+    assert_eq!(submit(&mut engine, &mut risk, order(2, 1, 0, 10, Side::Buy)), Some(RejectReason::RiskGrossExposure));
+
+    // Once IBM has traded, market orders are priced at the last trade.
+    assert_eq!(submit(&mut engine, &mut risk, order(3, 1, 10, 10, Side::Sell)), None);
+    assert_eq!(submit(&mut engine, &mut risk, order(3, 2, 10, 10, Side::Buy)), None);
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 1, 0, 10, Side::Buy)), None);
+}
+
+#[test]
+fn replace_of_a_queued_order_is_checked() {
+    let limits = parse_risk_limits("1, 100, 0, 0, 0, 0\n").unwrap();
+    let mut risk = RiskGateway::new();
+    for update in &limits {
+        risk.set_limits(update);
+    }
+    let mut engine = MatchingEngine::new();
+    engine.set_execution_reports(true);
+    engine.set_queue_when_halted(true);
+    engine.process_message(InputMessage::SetTradingState(SetTradingState {
+        symbol: "IBM".to_string(),
+        state: TradingState::Halted,
+        admin_token: String::new(),
+    }));
+
+    assert_eq!(submit(&mut engine, &mut risk, order(1, 1, 10, 50, Side::Buy)), None);
+    assert!(engine.get_queued_order(1, 1).is_some());
+    assert_eq!(replace(&mut engine, &mut risk, replace_to(1, 1, None, Some(150))), Some(RejectReason::RiskOrderQuantity));
+}
@@ -0,0 +1,169 @@
+// crates/engine-server/tests/routing.rs
+use engine_core::{
+    FlushCommand, InputMessage, KillSwitch, MatchingEngine, NewOrder, OrderFlags, OutputMessage,
+    SelfTradePrevention, Side, TimeInForce,
+};
+use engine_server::routing::{Destination, Router};
+use engine_server::types::ClientId;
+
+const ALICE: ClientId = ClientId(1);
+const BOB: ClientId = ClientId(2);
+const ADMIN: ClientId = ClientId(3);
+
+fn new_order(user_id: u32, user_order_id: u32, price: u32, side: Side) -> InputMessage {
+    InputMessage::NewOrder(NewOrder {
+        user_id,
+        symbol: "IBM".to_string(),
+        price,
+        quantity: 100,
+        side,
+        user_order_id,
+        time_in_force: TimeInForce::Day,
+        stop_price: 0,
+        display_qty: 0,
+        flags: OrderFlags::NONE,
+        self_trade_prevention: SelfTradePrevention::None,
+    })
+}
+
+/// An engine set up like the server's.
+fn engine() -> MatchingEngine {
+    let mut engine = MatchingEngine::new();
+    engine.set_execution_reports(true);
+    engine
+}
+
+fn submit(
+    engine: &mut MatchingEngine,
+    router: &mut Router,
+    client: ClientId,
+    msg: InputMessage,
+) -> Vec<(Destination, OutputMessage)> {
+    let outputs = engine.process_message(msg.clone());
+    router.route(client, &msg, outputs, engine)
+}
+
+/// The user a private order report is about.
+fn report_user(out: &OutputMessage) -> Option<u32> {
+    match out {
+        OutputMessage::Ack(a) => Some(a.user_id),
+        OutputMessage::CancelAck(c) => Some(c.user_id),
+        OutputMessage::ExecutionReport(e) => Some(e.user_id),
+        _ => None,
+    }
+}
+
+/// `(client, user)` for every private report delivered.
+fn private_reports(deliveries: &[(Destination, OutputMessage)]) -> Vec<(ClientId, u32)> {
+    deliveries
+        .iter()
+        .filter_map(|(dest, out)| match dest {
+            Destination::Client(client) => report_user(out).map(|user| (*client, user)),
+            Destination::All => None,
+        })
+        .collect()
+}
+
+fn private_trades(deliveries: &[(Destination, OutputMessage)]) -> Vec<(ClientId, OutputMessage)> {
+    deliveries
+        .iter()
+        .filter_map(|(dest, out)| match (dest, out) {
+            (Destination::Client(client), OutputMessage::Trade(_)) => Some((*client, out.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn flush_reports_each_cancel_to_the_orders_owner() {
+    let mut engine = engine();
+    let mut router = Router::new();
+    submit(&mut engine, &mut router, ALICE, new_order(1, 1, 10, Side::Buy));
+    submit(&mut engine, &mut router, BOB, new_order(2, 1, 12, Side::Sell));
+
+    let deliveries = submit(&mut engine, &mut router, ADMIN, InputMessage::Flush(FlushCommand::default()));
+    let reports = private_reports(&deliveries);
+    assert!(reports.contains(&(ALICE, 1)));
+    assert!(reports.contains(&(BOB, 2)));
+    assert!(reports
+        .iter()
+        .all(|&(client, user)| (client == ALICE && user == 1) || (client == BOB && user == 2)));
+    assert!(router.orders_of(ALICE).is_empty());
+    assert!(router.orders_of(BOB).is_empty());
+}
+
+#[test]
+fn trade_against_an_order_with_unknown_owner_only_reports_the_aggressor() {
+    let mut engine = engine();
+    let mut router = Router::new();
+    // Entered before a restart: the router never saw who owns it.
+    engine.process_message(new_order(1, 1, 10, Side::Sell));
+
+    let deliveries = submit(&mut engine, &mut router, BOB, new_order(2, 1, 10, Side::Buy));
+    let trades = private_trades(&deliveries);
+    assert_eq!(trades.len(), 1);
+    let (client, OutputMessage::Trade(trade)) = &trades[0] else { unreachable!() };
+    assert_eq!(*client, BOB);
+    assert_eq!((trade.user_id_buy, trade.user_id_sell), (2, 0));
+
+    assert!(private_reports(&deliveries).iter().all(|&(client, user)| client == BOB && user == 2));
+    assert!(deliveries
+        .iter()
+        .any(|(dest, out)| *dest == Destination::All && matches!(out, OutputMessage::PublicTrade(_))));
+}
+
+#[test]
+fn each_side_of_a_trade_goes_to_its_owner_without_the_counterparty() {
+    let mut engine = engine();
+    let mut router = Router::new();
+    submit(&mut engine, &mut router, ALICE, new_order(1, 7, 10, Side::Sell));
+
+    let deliveries = submit(&mut engine, &mut router, BOB, new_order(2, 9, 10, Side::Buy));
+    let trades = private_trades(&deliveries);
+    assert_eq!(trades.len(), 2);
+    for (client, trade) in trades {
+        let OutputMessage::Trade(trade) = trade else { unreachable!() };
+        if client == ALICE {
+            assert_eq!((trade.user_id_sell, trade.user_order_id_sell), (1, 7));
+            assert_eq!((trade.user_id_buy, trade.user_order_id_buy), (0, 0));
+        } else {
+            assert_eq!(client, BOB);
+            assert_eq!((trade.user_id_buy, trade.user_order_id_buy), (2, 9));
+            assert_eq!((trade.user_id_sell, trade.user_order_id_sell), (0, 0));
+        }
+    }
+    let reports = private_reports(&deliveries);
+    assert!(reports.contains(&(ALICE, 1)));
+    assert!(reports.iter().all(|&(client, user)| (client == ALICE) == (user == 1)));
+}
+
+#[test]
+fn user_trading_status_only_goes_to_connected_clients_that_entered_orders() {
+    let mut engine = engine();
+    let mut router = Router::new();
+    submit(&mut engine, &mut router, ALICE, new_order(1, 1, 10, Side::Buy));
+    // A rejected order doesn't count.
+    submit(&mut engine, &mut router, BOB, new_order(1, 1, 10, Side::Buy));
+
+    let kill = |router: &mut Router, engine: &mut MatchingEngine| {
+        let msg = InputMessage::KillSwitch(KillSwitch {
+            user_id: 1,
+            cancel_orders: false,
+            admin_token: String::new(),
+        });
+        let deliveries = submit(engine, router, ADMIN, msg);
+        let mut clients: Vec<_> = deliveries
+            .into_iter()
+            .filter_map(|(dest, out)| match (dest, out) {
+                (Destination::Client(client), OutputMessage::UserTradingStatus(_)) => Some(client),
+                _ => None,
+            })
+            .collect();
+        clients.sort_by_key(|client| client.0);
+        clients
+    };
+    assert_eq!(kill(&mut router, &mut engine), vec![ALICE, ADMIN]);
+
+    router.disconnect(ALICE);
+    assert_eq!(kill(&mut router, &mut engine), vec![ADMIN]);
+}
@@ -0,0 +1,227 @@
+// crates/engine-server/tests/session.rs
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use engine_core::{
+    InputMessage, Logon, MatchingEngine, NewOrder, OrderFlags, OutputMessage, RejectReason,
+    ReplayClock, SelfTradePrevention, Side, TimeInForce, TradingState, VolatilityAction,
+    VolatilityBands,
+};
+use engine_protocol::binary_codec;
+use engine_server::auth::Authenticator;
+use engine_server::client::{run_session, Protocol};
+use engine_server::engine_task::run_engine_loop;
+use engine_server::risk::RiskGateway;
+use engine_server::types::{ClientId, ClientRegistry, EngineRequest, EngineTx, OutboundRx};
+use tokio::io::{AsyncWriteExt, DuplexStream};
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time;
+
+const TRADER: ClientId = ClientId(1);
+const OBSERVER: ClientId = ClientId(2);
+
+/// An engine task with no persistence, plus the observer's market data.
+async fn start_engine() -> (EngineTx, ClientRegistry, OutboundRx) {
+    start_engine_with(|_| {}).await
+}
+
+/// Like `start_engine`, with the engine set up by `configure` first.
+async fn start_engine_with(configure: impl FnOnce(&mut MatchingEngine)) -> (EngineTx, ClientRegistry, OutboundRx) {
+    let clients: ClientRegistry = Arc::new(RwLock::new(HashMap::new()));
+    let (observer_tx, observer_rx) = mpsc::unbounded_channel();
+    clients.write().await.insert(OBSERVER, observer_tx);
+
+    let (engine_tx, engine_rx) = mpsc::unbounded_channel();
+    let clock = ReplayClock::new();
+    let mut engine = MatchingEngine::with_clock(Arc::new(clock.clone()));
+    configure(&mut engine);
+    tokio::spawn(run_engine_loop(engine_rx, clients.clone(), engine, clock, RiskGateway::new(), None));
+    (engine_tx, clients, observer_rx)
+}
+
+/// A trader session over an in-memory stream: the test writes to the
+/// returned end and reads replies from the receiver.
+async fn connect(
+    protocol: Protocol,
+    auth: Authenticator,
+    engine_tx: &EngineTx,
+    clients: &ClientRegistry,
+) -> (DuplexStream, OutboundRx, JoinHandle<()>) {
+    let (client_end, server_end) = tokio::io::duplex(1024);
+    let (out_tx, out_rx) = mpsc::unbounded_channel();
+    clients.write().await.insert(TRADER, out_tx.clone());
+    let session = tokio::spawn({
+        let (engine_tx, clients, auth) = (engine_tx.clone(), clients.clone(), Arc::new(auth));
+        async move {
+            let result = run_session(TRADER, protocol, server_end, engine_tx, out_tx, clients, auth).await;
+            assert!(result.is_ok());
+        }
+    });
+    (client_end, out_rx, session)
+}
+
+/// Wait (at most a few seconds) for an output matching `pred`.
+async fn wait_for(rx: &mut OutboundRx, pred: impl Fn(&OutputMessage) -> bool) -> OutputMessage {
+    time::timeout(Duration::from_secs(5), async {
+        loop {
+            let out = rx.recv().await.expect("channel closed");
+            if pred(&out) {
+                return out;
+            }
+        }
+    })
+    .await
+    .expect("no matching output")
+}
+
+fn is_ack(out: &OutputMessage) -> bool {
+    matches!(out, OutputMessage::Ack(a) if (a.user_id, a.user_order_id) == (1, 1))
+}
+
+fn bid_eliminated(out: &OutputMessage) -> bool {
+    matches!(out, OutputMessage::TopOfBook(t) if t.symbol == "IBM" && t.side == Side::Buy && t.price == 0)
+}
+
+/// A length-prefixed binary frame of `msg`.
+fn frame(msg: &InputMessage) -> Vec<u8> {
+    let mut payload = Vec::new();
+    binary_codec::encode_input(msg, &mut payload).unwrap();
+    let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+    frame.extend(payload);
+    frame
+}
+
+#[tokio::test]
+async fn disconnect_cancels_the_sessions_orders() {
+    let (engine_tx, clients, mut observer) = start_engine().await;
+    let (mut stream, mut replies, session) = connect(Protocol::Csv, Authenticator::default(), &engine_tx, &clients).await;
+
+    stream.write_all(b"A, Y\nN, 1, IBM, 10, 100, B, 1\n").await.unwrap();
+    wait_for(&mut replies, is_ack).await;
+
+    drop(stream);
+    time::timeout(Duration::from_secs(5), session).await.unwrap().unwrap();
+    wait_for(&mut observer, bid_eliminated).await;
+    assert!(!clients.read().await.contains_key(&TRADER));
+}
+
+#[tokio::test]
+async fn heartbeat_timeout_mid_frame_ends_the_session_and_cancels_its_orders() {
+    let (engine_tx, clients, mut observer) = start_engine().await;
+    let (mut stream, mut replies, session) = connect(Protocol::Binary, Authenticator::default(), &engine_tx, &clients).await;
+
+    let logon = InputMessage::Logon(Logon {
+        cancel_on_disconnect: true,
+        heartbeat_secs: 1,
+        username: String::new(),
+        secret: String::new(),
+    });
+    let order = InputMessage::NewOrder(NewOrder {
+        user_id: 1,
+        symbol: "IBM".to_string(),
+        price: 10,
+        quantity: 100,
+        side: Side::Buy,
+        user_order_id: 1,
+        time_in_force: TimeInForce::Day,
+        stop_price: 0,
+        display_qty: 0,
+        flags: OrderFlags::NONE,
+        self_trade_prevention: SelfTradePrevention::None,
+    });
+    stream.write_all(&frame(&logon)).await.unwrap();
+    stream.write_all(&frame(&order)).await.unwrap();
+    wait_for(&mut replies, is_ack).await;
+
+    // A length prefix, then nothing: the connection stays open.
+    stream.write_all(&[0, 0, 0, 40]).await.unwrap();
+    time::timeout(Duration::from_secs(5), session).await.unwrap().unwrap();
+    wait_for(&mut observer, bid_eliminated).await;
+    drop(stream);
+}
+
+#[tokio::test]
+async fn admin_messages_need_the_admin_token() {
+    let (engine_tx, clients, mut observer) = start_engine().await;
+    let auth = Authenticator::new(Some("s3cret".to_string()), None);
+    let (mut stream, mut replies, _session) = connect(Protocol::Csv, auth, &engine_tx, &clients).await;
+    let unauthorized = |symbol: &'static str| {
+        move |out: &OutputMessage| matches!(out, OutputMessage::Reject(r) if r.reason == RejectReason::Unauthorized && r.symbol == symbol)
+    };
+
+    stream.write_all(b"N, 1, IBM, 10, 100, B, 1\n").await.unwrap();
+    wait_for(&mut replies, is_ack).await;
+
+    stream.write_all(b"F\n").await.unwrap();
+    wait_for(&mut replies, unauthorized("")).await;
+    stream.write_all(b"S, IBM, HALTED\n").await.unwrap();
+    wait_for(&mut replies, unauthorized("IBM")).await;
+    stream.write_all(b"U, IBM, START, guess\n").await.unwrap();
+    wait_for(&mut replies, unauthorized("IBM")).await;
+    stream.write_all(b"L, 1, 1, 0, 0, 0, 0, guess\n").await.unwrap();
+    wait_for(&mut replies, unauthorized("")).await;
+
+    stream.write_all(b"S, IBM, HALTED, s3cret\nF, s3cret\n").await.unwrap();
+    wait_for(&mut observer, |out| {
+        matches!(out, OutputMessage::TradingStatus(t) if t.symbol == "IBM" && t.state == TradingState::Halted)
+    })
+    .await;
+    wait_for(&mut observer, bid_eliminated).await;
+}
+
+#[tokio::test]
+async fn binary_client_of_an_older_protocol_version_can_still_trade() {
+    let (engine_tx, clients, _observer) = start_engine().await;
+    let (mut stream, mut replies, _session) = connect(Protocol::Binary, Authenticator::default(), &engine_tx, &clients).await;
+
+    // Protocol 1: `NewOrder` ends with the side, then the symbol.
+    let mut v1_buy = vec![0, 1, 0, 0];
+    for field in [1u32, 1, 10, 100] {
+        v1_buy.extend_from_slice(&field.to_be_bytes());
+    }
+    v1_buy.extend_from_slice(&[0, 3]);
+    v1_buy.extend_from_slice(b"IBM");
+    let mut frame = (v1_buy.len() as u32).to_be_bytes().to_vec();
+    frame.extend(v1_buy);
+
+    stream.write_all(&frame).await.unwrap();
+    wait_for(&mut replies, is_ack).await;
+}
+
+#[tokio::test]
+async fn volatility_halt_ends_on_time_without_further_traffic() {
+    let (engine_tx, _clients, mut observer) = start_engine_with(|engine| {
+        engine.set_volatility_bands(Some(VolatilityBands {
+            band_bps: 1_000,
+            duration_ns: 200_000_000,
+            action: VolatilityAction::Halt,
+        }));
+    })
+    .await;
+    let order = |user_id, price, side| {
+        InputMessage::NewOrder(NewOrder {
+            user_id,
+            symbol: "IBM".to_string(),
+            price,
+            quantity: 10,
+            side,
+            user_order_id: 1,
+            time_in_force: TimeInForce::Day,
+            stop_price: 0,
+            display_qty: 0,
+            flags: OrderFlags::NONE,
+            self_trade_prevention: SelfTradePrevention::None,
+        })
+    };
+
+    // A trade at 100, then a buy reaching 120: outside the ±10% band.
+    for msg in [order(1, 100, Side::Buy), order(2, 100, Side::Sell), order(3, 120, Side::Sell), order(4, 125, Side::Buy)] {
+        engine_tx.send(EngineRequest::Input { client_id: TRADER, msg }).unwrap();
+    }
+    let status = |state| move |out: &OutputMessage| matches!(out, OutputMessage::TradingStatus(t) if t.state == state);
+    wait_for(&mut observer, status(TradingState::Halted)).await;
+    wait_for(&mut observer, status(TradingState::Continuous)).await;
+    wait_for(&mut observer, |out| matches!(out, OutputMessage::PublicTrade(t) if t.price == 120)).await;
+}
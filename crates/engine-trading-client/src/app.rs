@@ -1,7 +1,11 @@
 // crates/engine-trading-client/src/app.rs
 
 use chrono::{DateTime, Local};
-use engine_core::{OutputMessage, InputMessage, Side, NewOrder, Cancel};
+use engine_core::{
+    Cancel, DepthAction, DepthQuery, InputMessage, Logon, MassCancel, NewOrder, OrdStatus, OrderFlags, OutputMessage, RejectReason,
+    SelfTradePrevention, Side,
+    TimeInForce,
+};
 use indexmap::IndexMap;
 use std::collections::VecDeque;
 use tokio::sync::mpsc::UnboundedSender;
@@ -39,6 +43,7 @@ pub enum OrderStatus {
     PartiallyFilled,
     Filled,
     Cancelled,
+    Rejected,
 }
 
 #[derive(Clone)]
@@ -52,15 +57,39 @@ pub struct Trade {
 
 #[derive(Default)]
 pub struct OrderBook {
-    pub bids: Vec<(u32, u32)>, // (price, quantity)
-    pub asks: Vec<(u32, u32)>, // (price, quantity)
+    pub bids: Vec<(u32, u32)>, // (price, quantity), best (highest) first
+    pub asks: Vec<(u32, u32)>, // (price, quantity), best (lowest) first
     pub last_update: Option<DateTime<Local>>,
 }
 
+impl OrderBook {
+    /// Apply one level add/change/delete to the ladder, keeping it sorted.
+    fn apply_level(&mut self, side: Side, action: DepthAction, price: u32, quantity: u32) {
+        let levels = match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+        let pos = levels.binary_search_by(|&(p, _)| match side {
+            Side::Buy => price.cmp(&p),
+            Side::Sell => p.cmp(&price),
+        });
+        match (action, pos) {
+            (DepthAction::Delete, Ok(i)) => {
+                levels.remove(i);
+            }
+            (DepthAction::Delete, Err(_)) => {}
+            (_, Ok(i)) => levels[i].1 = quantity,
+            (_, Err(i)) => levels.insert(i, (price, quantity)),
+        }
+    }
+}
+
 pub struct App {
     // Connection state
     pub connected: bool,
     pub user_id: u32,
+    /// False once a kill switch blocks our user.
+    pub trading_enabled: bool,
     
     // UI state
     pub input_mode: InputMode,
@@ -117,6 +146,7 @@ impl App {
         let mut app = Self {
             connected: false,
             user_id,
+            trading_enabled: true,
             input_mode: InputMode::Normal,
             current_panel: Panel::OrderBook,
             should_quit: false,
@@ -156,6 +186,29 @@ impl App {
         self.network_tx = Some(tx);
     }
 
+    /// Log on to a server that checks credentials.
+    pub fn logon(&self, username: String, secret: String) {
+        let logon = InputMessage::Logon(Logon {
+            username,
+            secret,
+            ..Logon::default()
+        });
+        if let Some(tx) = &self.network_tx {
+            let _ = tx.send(logon);
+        }
+    }
+
+    /// Ask the server for a full depth snapshot of the current symbol.
+    pub fn request_depth(&self) {
+        let query = InputMessage::QueryDepth(DepthQuery {
+            symbol: self.current_symbol.clone(),
+            levels: 0,
+        });
+        if let Some(tx) = &self.network_tx {
+            let _ = tx.send(query);
+        }
+    }
+
     pub fn next_panel(&mut self) {
         self.current_panel = match self.current_panel {
             Panel::OrderBook => Panel::Orders,
@@ -194,34 +247,27 @@ impl App {
     }
     
     pub fn cancel_all_orders(&mut self) {
-        // Cancel all open orders
-        for order in self.my_orders.values() {
-            if order.status == OrderStatus::Open || order.status == OrderStatus::PartiallyFilled {
-                let cancel = Cancel {
-                    user_id: self.user_id,
-                    user_order_id: order.order_id,
-                };
-                
-                let cancel_msg = InputMessage::Cancel(cancel);
-                
-                if let Some(tx) = &self.network_tx {
-                    let _ = tx.send(cancel_msg);
-                }
-            }
+        // One mass cancel covers every open order, on every symbol
+        let cancel_msg = InputMessage::MassCancel(MassCancel {
+            user_id: self.user_id,
+            symbol: None,
+            side: None,
+            min_price: None,
+            max_price: None,
+        });
+        
+        if let Some(tx) = &self.network_tx {
+            let _ = tx.send(cancel_msg);
         }
     }
     
     pub fn move_selection_up(&mut self) {
         match self.current_panel {
-            Panel::Orders => {
-                if self.selected_order_index > 0 {
-                    self.selected_order_index -= 1;
-                }
+            Panel::Orders if self.selected_order_index > 0 => {
+                self.selected_order_index -= 1;
             }
-            Panel::OrderBook => {
-                if self.selected_bid_index > 0 {
-                    self.selected_bid_index -= 1;
-                }
+            Panel::OrderBook if self.selected_bid_index > 0 => {
+                self.selected_bid_index -= 1;
             }
             _ => {}
         }
@@ -229,10 +275,8 @@ impl App {
     
     pub fn move_selection_down(&mut self) {
         match self.current_panel {
-            Panel::Orders => {
-                if self.selected_order_index < self.my_orders.len().saturating_sub(1) {
-                    self.selected_order_index += 1;
-                }
+            Panel::Orders if self.selected_order_index < self.my_orders.len().saturating_sub(1) => {
+                self.selected_order_index += 1;
             }
             Panel::OrderBook => {
                 let book = self.order_books.get(&self.current_symbol);
@@ -284,7 +328,7 @@ impl App {
         }
         
         // If we're in order entry mode with a side selected
-        if let Some(side) = self.order_side.clone() {
+        if let Some(side) = self.order_side {
             // Parse quantity
             let quantity = self.input_buffer.parse::<u32>().unwrap_or(0);
             if quantity == 0 {
@@ -312,7 +356,12 @@ impl App {
                 symbol: self.current_symbol.clone(),
                 price,
                 quantity,
-                side: side.clone(),
+                side,
+                time_in_force: TimeInForce::Day,
+                stop_price: 0,
+                display_qty: 0,
+                flags: OrderFlags::NONE,
+                self_trade_prevention: SelfTradePrevention::None,
             };
  
             // Create the order
@@ -386,29 +435,62 @@ impl App {
     pub fn handle_engine_message(&mut self, msg: OutputMessage) {
         self.message_count += 1;
         
+        // Our orders' status and fills come from their ExecutionReports;
+        // the other private messages need no handling of their own.
         match msg {
-            OutputMessage::Ack(ack) => {
-                if ack.user_id == self.user_id {
-                    if let Some(order) = self.my_orders.get_mut(&ack.user_order_id) {
-                        order.status = OrderStatus::Open;
+            OutputMessage::ExecutionReport(report) => {
+                if report.user_id == self.user_id {
+                    if let Some(order) = self.my_orders.get_mut(&report.user_order_id) {
+                        order.filled_qty = report.cum_qty;
+                        if !report.order_status.is_final() {
+                            order.quantity = report.cum_qty + report.leaves_qty;
+                        }
+                        order.status = match report.order_status {
+                            OrdStatus::New => OrderStatus::Open,
+                            OrdStatus::PartiallyFilled => OrderStatus::PartiallyFilled,
+                            OrdStatus::Filled => OrderStatus::Filled,
+                            OrdStatus::Canceled | OrdStatus::Expired => OrderStatus::Cancelled,
+                            // A rejected cancel of a finished order leaves it
+                            // as it was.
+                            OrdStatus::Rejected if order.status == OrderStatus::Pending => {
+                                OrderStatus::Rejected
+                            }
+                            OrdStatus::Rejected => order.status.clone(),
+                        };
                     }
                 }
             }
-            OutputMessage::Trade(trade) => {
+            // Pre-trade risk rejects come from the server without a report.
+            OutputMessage::Reject(reject) => {
+                if reject.user_id == self.user_id {
+                    if let Some(order) = self.my_orders.get_mut(&reject.user_order_id) {
+                        if order.status == OrderStatus::Pending {
+                            order.status = OrderStatus::Rejected;
+                        }
+                    }
+                    if reject.reason == RejectReason::TradingDisabled {
+                        self.trading_enabled = false;
+                    }
+                }
+            }
+            OutputMessage::UserTradingStatus(status) => {
+                if status.user_id == self.user_id || status.user_id == 0 {
+                    self.trading_enabled = status.enabled;
+                }
+            }
+            OutputMessage::Ack(_)
+            | OutputMessage::CancelAck(_)
+            | OutputMessage::Expired(_)
+            | OutputMessage::SelfTradeCancel(_) => {}
+            OutputMessage::PublicTrade(trade) => {
                 self.total_trades += 1;
                 self.total_volume += trade.quantity as u64;
-                
-                // Update our orders if involved
+            }
+            OutputMessage::Trade(trade) => {
+                // Private fill report: only our own side is populated, the
+                // counterparty ids are zeroed by the server. It feeds the
+                // blotter; the order itself is updated by its report.
                 if trade.user_id_buy == self.user_id {
-                    if let Some(order) = self.my_orders.get_mut(&trade.user_order_id_buy) {
-                        order.filled_qty += trade.quantity;
-                        order.status = if order.filled_qty >= order.quantity {
-                            OrderStatus::Filled
-                        } else {
-                            OrderStatus::PartiallyFilled
-                        };
-                    }
-                    
                     // Add to recent trades
                     self.recent_trades.push_front(Trade {
                         symbol: trade.symbol.clone(),
@@ -420,15 +502,6 @@ impl App {
                 }
                 
                 if trade.user_id_sell == self.user_id {
-                    if let Some(order) = self.my_orders.get_mut(&trade.user_order_id_sell) {
-                        order.filled_qty += trade.quantity;
-                        order.status = if order.filled_qty >= order.quantity {
-                            OrderStatus::Filled
-                        } else {
-                            OrderStatus::PartiallyFilled
-                        };
-                    }
-                    
                     self.recent_trades.push_front(Trade {
                         symbol: trade.symbol,
                         price: trade.price,
@@ -443,44 +516,50 @@ impl App {
                     self.recent_trades.pop_back();
                 }
             }
-            OutputMessage::CancelAck(cancel) => {
-                if cancel.user_id == self.user_id {
-                    if let Some(order) = self.my_orders.get_mut(&cancel.user_order_id) {
-                        order.status = OrderStatus::Cancelled;
+            OutputMessage::ReplaceAck(replace) => {
+                if replace.user_id == self.user_id {
+                    if let Some(order) = self.my_orders.get_mut(&replace.user_order_id) {
+                        order.price = replace.price;
                     }
                 }
             }
+            OutputMessage::Triggered(_) => {
+                // A triggered stop stays open; its fills arrive as Trades.
+            }
+            OutputMessage::IndicativeUncross(_) => {
+                // Auction indicatives aren't shown; the uncross arrives as
+                // Trades.
+            }
+            OutputMessage::TradingStatus(_) => {
+                // Not shown; a halted symbol rejects orders with a reason.
+            }
+            OutputMessage::InstrumentDefinition(_) => {
+                // The client never queries instruments.
+            }
+            OutputMessage::MassCancelAck(_) => {
+                // Each cancelled order already got its own ExecutionReport.
+            }
+            OutputMessage::SessionStatus(_) => {
+                // Not shown; a refused logon rejects every order as
+                // unauthorized.
+            }
+            OutputMessage::VolatilityInterruption(_) => {
+                // Not shown; the TradingStatus that follows says it all.
+            }
             OutputMessage::TopOfBook(tob) => {
-                let book = self.order_books.entry(tob.symbol.clone())
-                    .or_insert(OrderBook::default());
-                
-                if !tob.eliminated {
-                    match tob.side {
-                        Side::Buy => {
-                            // Update best bid
-                            if let Some(bid) = book.bids.get_mut(0) {
-                                *bid = (tob.price, tob.total_quantity);
-                            } else {
-                                book.bids.push((tob.price, tob.total_quantity));
-                            }
-                        }
-                        Side::Sell => {
-                            // Update best ask
-                            if let Some(ask) = book.asks.get_mut(0) {
-                                *ask = (tob.price, tob.total_quantity);
-                            } else {
-                                book.asks.push((tob.price, tob.total_quantity));
-                            }
-                        }
-                    }
-                } else {
-                    // Remove eliminated side
-                    match tob.side {
-                        Side::Buy => book.bids.clear(),
-                        Side::Sell => book.asks.clear(),
-                    }
+                // The ladder itself is maintained from DepthUpdate events.
+                let book = self.order_books.entry(tob.symbol).or_default();
+                book.last_update = Some(Local::now());
+            }
+            OutputMessage::DepthUpdate(update) => {
+                let book = self.order_books.entry(update.symbol).or_default();
+                if update.snapshot {
+                    book.bids.clear();
+                    book.asks.clear();
+                }
+                for e in update.events {
+                    book.apply_level(e.side, e.action, e.price, e.quantity);
                 }
-                
                 book.last_update = Some(Local::now());
             }
         }
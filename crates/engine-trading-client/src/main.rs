@@ -41,6 +41,14 @@ struct Cli {
     #[clap(short = 'y', long, default_value = "AAPL")]
     symbol: String,
 
+    /// Username to log on with, if the server checks credentials
+    #[clap(long, requires = "secret")]
+    username: Option<String>,
+
+    /// Secret to log on with
+    #[clap(long, requires = "username")]
+    secret: Option<String>,
+
     /// Enable debug logging
     #[clap(short, long)]
     debug: bool,
@@ -64,7 +72,8 @@ async fn main() -> Result<()> {
 
     // Create app and run
     let app = App::new(cli.user_id, &cli.symbol);
-    let res = run_app(&mut terminal, app, &cli.server).await;
+    let logon = cli.username.zip(cli.secret);
+    let res = run_app(&mut terminal, app, &cli.server, logon).await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -86,6 +95,7 @@ async fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     mut app: App,
     server_addr: &str,
+    logon: Option<(String, String)>,
 ) -> Result<()> {
     // Create channels for network communication
     let (tx_to_network, rx_from_app) = mpsc::unbounded_channel::<InputMessage>();
@@ -101,6 +111,10 @@ async fn run_app<B: Backend>(
     info!("Connecting to {}...", server_addr);
     connection.connect().await?;
     app.set_connected(true);
+    if let Some((username, secret)) = logon {
+        app.logon(username, secret);
+    }
+    app.request_depth();
     
     // Spawn network handler
     let network_handle = tokio::spawn(async move {
@@ -3,6 +3,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Configuration for the trading client
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientConfig {
     pub server_addr: String,
@@ -26,6 +27,7 @@ impl Default for ClientConfig {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Theme {
     Dark,
@@ -48,6 +50,7 @@ pub struct MarketData {
 }
 
 /// Alert configuration
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Alert {
     pub id: u32,
@@ -56,6 +59,7 @@ pub struct Alert {
     pub triggered: bool,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AlertCondition {
     PriceAbove(f64),
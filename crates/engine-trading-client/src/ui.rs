@@ -59,12 +59,18 @@ fn draw_header(f: &mut Frame, area: Rect, app: &App) {
     let connection_symbol = if app.connected { "✓" } else { "✗" };
     let connection_color = if app.connected { Color::Green } else { Color::Red };
     
-    let left_text = vec![
+    let mut left_text = vec![
         Span::styled(&app.current_symbol, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         Span::raw(" - "),
         Span::raw("Connected "),
         Span::styled(connection_symbol, Style::default().fg(connection_color)),
     ];
+    if !app.trading_enabled {
+        left_text.push(Span::styled(
+            " TRADING DISABLED",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
     
     let left_paragraph = Paragraph::new(Line::from(left_text))
         .block(Block::default().borders(Borders::ALL));